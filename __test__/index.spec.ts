@@ -3,7 +3,7 @@ import { readFileSync } from 'node:fs';
 import { dirname, join } from 'node:path';
 import { fileURLToPath } from 'node:url';
 
-import { ParsedSchema } from '../index';
+import { CoordinateBuffer, ParsedSchema } from '../index';
 
 const __dirname = dirname(fileURLToPath(import.meta.url));
 const PETS_SCHEMA = readFileSync(join(__dirname, '../testing/pets.schema.graphql'), 'utf-8');
@@ -32,9 +32,45 @@ test('extractSchemaCoordinates', (t) => {
     ]);
 })
 
+test('extractSchemaCoordinatesInto reuses a buffer', (t) => {
+    const parsedSchema = new ParsedSchema(PETS_SCHEMA);
+    const buffer = new CoordinateBuffer();
+
+    parsedSchema.extractSchemaCoordinatesInto('{ animalOwner { name } }', buffer);
+    t.deepEqual(buffer.toArray().sort(), ['Human.name', 'Root.animalOwner']);
+
+    parsedSchema.extractSchemaCoordinatesInto('mutation { addCat(name: "Tom") { name } }', buffer);
+    t.deepEqual(buffer.toArray().sort(), ['Cat.name', 'Mutation.addCat']);
+})
+
 test('hasField', (t) => {
     const parsedSchema = new ParsedSchema(PETS_SCHEMA);
 
     t.true(parsedSchema.hasField('Cat.name'))
     t.false(parsedSchema.hasField('Yorg.dorg'))
 })
+
+test('extractStreaming calls back with each coordinate once', async (t) => {
+    const parsedSchema = new ParsedSchema(PETS_SCHEMA);
+    const document = '{ animalOwner { name ...on Human { name } } }';
+    const coordinates: string[] = [];
+
+    await parsedSchema.extractStreaming(document, (coordinate) => {
+        coordinates.push(coordinate);
+    });
+    t.deepEqual(coordinates.sort(), parsedSchema.extractSchemaCoordinates(document).sort());
+})
+
+test('extractStreaming rejects with the callback error', async (t) => {
+    const parsedSchema = new ParsedSchema(PETS_SCHEMA);
+    let calls = 0;
+
+    await t.throwsAsync(
+        parsedSchema.extractStreaming('{ animalOwner { name } }', () => {
+            calls += 1;
+            throw new Error('Stop');
+        }),
+        { message: 'Stop' },
+    );
+    t.is(calls, 1);
+})
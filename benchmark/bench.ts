@@ -2,14 +2,21 @@ import { readFileSync } from 'fs';
 import { dirname, join } from 'path';
 import { Bench } from 'tinybench';
 import { fileURLToPath } from 'url';
-import { ParsedSchema } from '../index.js';
+import { CoordinateBuffer, ParsedSchema } from '../index.js';
 
 const __dirname = dirname(fileURLToPath(import.meta.url));
 const PETS_SCHEMA = readFileSync(join(__dirname, '../testing/pets.schema.graphql'), 'utf-8');
 
+// The pets schema padded with thousands of types that the benchmark documents never touch
+const LARGE_SCHEMA = [
+    PETS_SCHEMA,
+    ...Array.from({ length: 10_000 }, (_, i) => `type Generated${i} { id: ID name: String next: Generated${i + 1} }`),
+].join('\n');
+
 const bench = new Bench({ time: 1000 });
 
 const parsedSchema = new ParsedSchema(PETS_SCHEMA);
+const buffer = new CoordinateBuffer();
 
 const simpleDocument = /* GraphQL */ `
     {
@@ -56,6 +63,36 @@ const complexDocument = /* GraphQL */ `
     }
 `;
 
+// The same fragments spread many times at the same depth, as colocated-fragment clients produce
+const repeatedFragmentsDocument = /* GraphQL */ `
+    {
+        animalOwner {
+            ${'...ownerFields\n'.repeat(50)}
+            bestFriend {
+                ${'...ownerFields\n'.repeat(50)}
+            }
+        }
+    }
+
+    fragment ownerFields on Human {
+        name
+        age
+        contactDetails {
+            ...contactFields
+            ...contactFields
+        }
+    }
+
+    fragment contactFields on ContactDetails {
+        email
+        phone
+        address {
+            streetNumber
+            zip
+        }
+    }
+`;
+
 bench
     .add('extractSchemaCoordinates - simple document', () => {
         parsedSchema.extractSchemaCoordinates(simpleDocument);
@@ -63,9 +100,24 @@ bench
     .add('extractSchemaCoordinates - complex document', () => {
         parsedSchema.extractSchemaCoordinates(complexDocument);
     })
+    .add('extractSchemaCoordinates - repeated fragments', () => {
+        parsedSchema.extractSchemaCoordinates(repeatedFragmentsDocument);
+    })
+    .add('extractSchemaCoordinatesInto - complex document, reused buffer', () => {
+        parsedSchema.extractSchemaCoordinatesInto(complexDocument, buffer);
+        buffer.toArray();
+    })
     .add('extractSchemaCoordinates - with schema parsing', () => {
         const schema = new ParsedSchema(PETS_SCHEMA);
         schema.extractSchemaCoordinates(simpleDocument);
+    })
+    .add('extractSchemaCoordinates - with large schema parsing', () => {
+        const schema = new ParsedSchema(LARGE_SCHEMA);
+        schema.extractSchemaCoordinates(simpleDocument);
+    })
+    .add('extractSchemaCoordinates - with large schema parsing, lazy type map', () => {
+        const schema = ParsedSchema.withOptions(LARGE_SCHEMA, { lazyTypeMap: true });
+        schema.extractSchemaCoordinates(simpleDocument);
     });
 
 await bench.run();
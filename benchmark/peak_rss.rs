@@ -0,0 +1,109 @@
+//! Measures the peak resident set size of extracting a huge document, either collecting its
+//! coordinates or streaming them into a sink. Linux only, as it reads `/proc/self/status`.
+//!
+//! Run each mode in its own process so the peaks don't mix:
+//!
+//! ```sh
+//! cargo run --release --example peak_rss -- collect
+//! cargo run --release --example peak_rss -- sink
+//! ```
+
+use faster_graphql_tools::{CoordinateSink, ParsedSchema};
+use std::fmt::Write;
+use std::time::Instant;
+
+const TYPES: usize = 200;
+const FIELDS: usize = 20;
+const OPERATIONS: usize = 2_000;
+
+/// Counts coordinates without keeping them
+struct CountingSink(usize);
+
+impl CoordinateSink for CountingSink {
+    fn emit(&mut self, _coordinate: String) {
+        self.0 += 1;
+    }
+}
+
+/// The peak resident set size of this process so far, in KiB
+fn peak_rss_kib() -> u64 {
+    let status = std::fs::read_to_string("/proc/self/status").expect("Should read process status");
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+        .expect("Should find VmHWM")
+}
+
+fn schema() -> String {
+    let mut schema = String::from("type Query {");
+    for i in 0..TYPES {
+        write!(schema, " t{i}: T{i}").unwrap();
+    }
+    schema.push_str(" }\n");
+    for i in 0..TYPES {
+        write!(schema, "type T{i} {{ next: T{} list: [T{i}]", (i + 1) % TYPES).unwrap();
+        for f in 0..FIELDS {
+            write!(schema, " f{f}(arg: Int): String").unwrap();
+        }
+        schema.push_str(" }\n");
+    }
+    schema
+}
+
+/// Operations selecting every type through fragments, each spread under several parents
+fn document() -> String {
+    let mut document = String::new();
+    for o in 0..OPERATIONS {
+        write!(document, "query Q{o} {{").unwrap();
+        for i in (o % 10..TYPES).step_by(10) {
+            write!(
+                document,
+                " t{i} {{ ...F{i} list {{ ...F{i} }} next {{ ...F{} }} }}",
+                (i + 1) % TYPES
+            )
+            .unwrap();
+        }
+        document.push_str(" }\n");
+    }
+    for i in 0..TYPES {
+        write!(document, "fragment F{i} on T{i} {{").unwrap();
+        for f in 0..FIELDS {
+            write!(document, " f{f}(arg: {f})").unwrap();
+        }
+        document.push_str(" }\n");
+    }
+    document
+}
+
+fn main() {
+    let mode = std::env::args().nth(1).unwrap_or_else(|| "collect".to_string());
+    let parsed_schema = ParsedSchema::new(schema()).expect("Should parse schema");
+    let document = document();
+    let document_kib = document.len() / 1024;
+    let before = peak_rss_kib();
+    let start = Instant::now();
+
+    let coordinates = match mode.as_str() {
+        "collect" => parsed_schema
+            .extract_schema_coordinates(document)
+            .expect("Should extract coordinates")
+            .len(),
+        "sink" => {
+            let mut sink = CountingSink(0);
+            parsed_schema
+                .extract_into_sink(&document, &mut sink)
+                .expect("Should extract coordinates");
+            sink.0
+        }
+        _ => panic!("Unknown mode {mode}, expected collect or sink"),
+    };
+
+    let elapsed = start.elapsed();
+    let after = peak_rss_kib();
+    println!(
+        "{mode}: {coordinates} coordinates from a {document_kib} KiB document in {elapsed:?}, \
+         peak RSS {before} KiB before extracting, {after} KiB after (+{} KiB)",
+        after - before
+    );
+}
@@ -1,11 +1,792 @@
 /* auto-generated by NAPI-RS */
 /* eslint-disable */
+/**
+ * A reusable set of coordinates, filled by `ParsedSchema.extractSchemaCoordinatesInto`.
+ * Reusing one buffer across extractions keeps its allocation alive between calls.
+ */
+export declare class CoordinateBuffer {
+  /** Create a new, empty CoordinateBuffer */
+  constructor()
+  /** The number of coordinates currently held by the buffer */
+  get length(): number
+  /** Copy the coordinates currently held by the buffer into an array */
+  toArray(): Array<string>
+  /** Remove all coordinates while keeping the allocated capacity */
+  clear(): void
+}
+
+/**
+ * Extracts schema coordinates one document at a time, created by `ParsedSchema.extractor`.
+ * The set coordinates are collected into is cleared and reused by each call.
+ */
+export declare class Extractor {
+  /** Extract schema coordinates from a document */
+  extract(documentText: string): Array<string>
+}
+
+/**
+ * Fragments shared between documents, such as a client's fragments file, parsed once and
+ * resolved by `ParsedSchema.extractWithRegistry` when documents spread them
+ */
+export declare class FragmentRegistry {
+  /** Parse a document of fragment definitions. Throws if it contains operations. */
+  constructor(fragmentsText: string)
+  /** The number of fragments in the registry */
+  get length(): number
+}
+
+/**
+ * Extracts schema coordinates from successive versions of a document, such as one being edited
+ * live, created by `ParsedSchema.incrementalExtractor`. Each operation and fragment's own
+ * coordinates are cached by a hash of its content, so only definitions that changed since the
+ * previous call are traversed again.
+ */
+export declare class IncrementalExtractor {
+  /**
+   * Extract schema coordinates from the latest version of a document. The result is the same
+   * as `ParsedSchema.extractSchemaCoordinates`, but only operations and fragments that are
+   * new or changed since the previous call are traversed, and fragments only once an
+   * operation reaches them.
+   */
+  extract(documentText: string): Array<string>
+  /** The number of operations and fragments traversed by the previous call to `extract` */
+  get recomputed(): number
+}
+
 /** A parsed GraphQL schema that can be reused to extract coordinates from multiple documents */
 export declare class ParsedSchema {
   /** Create a new ParsedSchema from a schema string */
   constructor(schemaText: string)
+  /** Create a new ParsedSchema from a schema string, with options for extraction */
+  static withOptions(schemaText: string, options: ExtractionOptions): ParsedSchema
+  /**
+   * Create a new ParsedSchema from a schema split across several strings, such as one per
+   * file. Types defined in one string can be extended in another.
+   */
+  static fromSchemas(schemaTexts: Array<string>, options?: ExtractionOptions | undefined | null): ParsedSchema
+  /**
+   * Create a new ParsedSchema from a gzipped schema string, with options for extraction. Only
+   * available in builds with the `flate2` feature. Throws for schemas that decompress to more
+   * than 64 MiB.
+   */
+  static fromGzip(schemaGzip: Buffer, options?: ExtractionOptions | undefined | null): ParsedSchema
+  /**
+   * Extract schema coordinates from a gzipped document. Only available in builds with the
+   * `flate2` feature. Throws for documents that decompress to more than 64 MiB.
+   */
+  extractSchemaCoordinatesGzip(documentGzip: Buffer): Array<string>
   /** Extract schema coordinates from a document using this parsed schema */
   extractSchemaCoordinates(documentText: string): Array<string>
+  /**
+   * Extract schema coordinates from an automatic persisted query, caching the result under
+   * its hash. If the hash is already registered, the cached coordinates are returned without
+   * parsing the document. The hash isn't checked against the document. At most
+   * `persistedQueryCapacity` queries are cached, evicting the least recently used one.
+   */
+  registerAndExtract(hash: string, documentText: string): Array<string>
+  /**
+   * Look up the schema coordinates of a persisted query registered with `registerAndExtract`,
+   * marking it as recently used. Returns null for hashes that were never registered or have
+   * been evicted.
+   */
+  extractByHash(hash: string): Array<string> | null
+  /**
+   * Find the root field coordinate of a document's subscription, such as
+   * `Subscription.messageAdded`, for keying subscription channels. Returns null when the
+   * document has no subscription, and throws if the subscription selects several root fields.
+   */
+  subscriptionEntryPoint(documentText: string): string | null
+  /**
+   * Whether a document only reads: every operation in it is a query, with no mutations or
+   * subscriptions. Fragments alone don't write, so a document of only fragments is read-only.
+   */
+  isReadOnly(documentText: string): boolean
+  /**
+   * Whether a document only selects introspection meta-fields, such as `__schema` and
+   * `__type`, at the root of every operation, looking through fragments. Documents without
+   * operations aren't introspection-only.
+   */
+  isIntrospectionOnly(documentText: string): boolean
+  /**
+   * Extract schema coordinates from a document, keeping only those of the given types, such as
+   * `Human.name` for `Human`. Root type aliases like `Query` match their actual type.
+   * Directive coordinates are never kept.
+   */
+  extractForTypes(documentText: string, types: Array<string>): Array<string>
+  /**
+   * List which of the given changed coordinates a document uses, such as to check whether a
+   * schema change affects it. The changed coordinates are given without `coordinate_prefix`,
+   * and may have their case normalized when `normalize_case` is set.
+   */
+  coordinatesIntersecting(documentText: string, changedCoordinates: Array<string>): Array<string>
+  /**
+   * Extract the union of the schema coordinates used by several documents. Each document is
+   * parsed on its own, so fragment spreads only resolve against their own document's fragments.
+   */
+  extractSchemaCoordinatesUnion(documentTexts: Array<string>): Array<string>
+  /**
+   * Split a blob of concatenated documents on a separator, such as `"\0"`, and extract the
+   * schema coordinates of each part separately. A part that fails to parse or extract gets
+   * its error instead of failing the others. A trailing separator doesn't start another part.
+   */
+  extractSchemaCoordinatesMulti(blob: string, separator: string): Array<PartExtraction>
+  /**
+   * Extract schema coordinates into a reusable buffer, replacing its previous contents.
+   * The buffer is left empty if the document fails to parse.
+   */
+  extractSchemaCoordinatesInto(documentText: string, buffer: CoordinateBuffer): void
+  /**
+   * Extract schema coordinates from a document whose fragment spreads can also refer to the
+   * fragments of a registry. Fragments defined in the document take precedence over registry
+   * fragments of the same name.
+   */
+  extractWithRegistry(documentText: string, registry: FragmentRegistry): Array<string>
+  /**
+   * Extract schema coordinates from a document, using the JSON variables it's executed with
+   * to also extract the `Input.field` coordinates of the input object fields they set.
+   * Only fields present in the variables are extracted, recursing into nested objects and
+   * lists. Throws for variables nested more than 128 levels deep.
+   */
+  extractWithVariables(documentText: string, variablesJson: string): Array<string>
+  /**
+   * Create an `IncrementalExtractor` for extracting successive versions of a document, such as
+   * one being edited. Throws if depth windows or `onlyUsedVariables` are set, since they make
+   * a fragment's coordinates depend on where it's spread.
+   */
+  incrementalExtractor(): IncrementalExtractor
+  /**
+   * Create an `Extractor` for extracting one document at a time, reusing its result set
+   * between calls
+   */
+  extractor(): Extractor
+  /**
+   * Extract schema coordinates from a document, along with stats about the work done.
+   * Useful for finding pathological documents.
+   */
+  extractWithStats(documentText: string): ExtractionWithStats
+  /**
+   * Extract schema coordinates from a document, along with how many selections were visited,
+   * the widest selection set, and how many fragment spreads were expanded. Useful for tuning
+   * query cost limits.
+   */
+  extractSecurityReport(documentText: string): SecurityReport
+  /**
+   * List the variables that a document's operations reference in arguments or directives,
+   * with their declared types, in declaration order. Declared but unused variables are omitted.
+   */
+  extractUsedVariables(documentText: string): Array<UsedVariable>
+  /**
+   * Extract schema coordinates per operation, separating those produced by the operation's
+   * own selections and variables from those produced by each fragment it spreads. Coordinates
+   * from nested fragments are attributed to the innermost fragment.
+   */
+  extractAttributed(documentText: string): Array<AttributedCoordinates>
+  /**
+   * Map each schema coordinate a document uses to the sorted names of the operations that use
+   * it, directly or through fragments. Anonymous operations are named `<anonymous>`.
+   */
+  extractCoordinateToOperations(documentText: string): Record<string, Array<string>>
+  /**
+   * List the distinct `@directive` coordinates applied anywhere in a document: on operations,
+   * fragments, fields, fragment spreads and inline fragments. They're prefixed and ordered
+   * like extracted coordinates, and sorted when no order is configured.
+   */
+  extractDirectives(documentText: string): Array<string>
+  /**
+   * List the deprecated fields, arguments and input fields that a document uses, sorted by
+   * coordinate. Input fields are found in object values passed as arguments.
+   */
+  extractDeprecatedUsages(documentText: string): Array<DeprecatedUsage>
+  /**
+   * Extract the coordinates a document uses whose type has the given directive applied in the
+   * schema, such as `Ledger.balance` for `type Ledger @internal`. The directive name can be
+   * given with or without its `@`.
+   */
+  extractFlaggedByDirective(documentText: string, directiveName: string): Array<string>
+  /**
+   * Extract the coordinates of the fields a document selects that return an interface or
+   * union type, such as `Root.allSpecies` returning `[Animal]`
+   */
+  extractAbstractReturningFields(documentText: string): Array<string>
+  /**
+   * Extract only the coordinates of the root fields a document's operations select, such as
+   * `Root.animalOwner`, including those selected through fragments. Selections beneath root
+   * fields aren't traversed.
+   */
+  extractRootFields(documentText: string): Array<string>
+  /**
+   * Extract schema coordinates from a document, sorted, each with the smallest selection depth
+   * it's used at. Root fields are at depth 1, and fragments add no depth.
+   */
+  extractWithDepths(documentText: string): Array<CoordinateDepth>
+  /**
+   * Extract schema coordinates from a document, sorted, each with how the document uses it:
+   * in a selection, as a variable's type, in an argument, or in a directive. A coordinate used
+   * in several ways lists each of them.
+   */
+  extractWithOrigins(documentText: string): Array<CoordinateOrigins>
+  /**
+   * Extract schema coordinates from a document, along with the deepest selection depth of any
+   * field in it. Root fields are at depth 1, and fragments add no depth.
+   */
+  extractWithMaxDepth(documentText: string): ExtractionWithMaxDepth
+  /**
+   * Extract schema coordinates from a document, along with the names of spread fragments it
+   * doesn't define, sorted. Unresolved spreads are skipped rather than treated as errors.
+   */
+  extractWithUnresolved(documentText: string): ExtractionWithUnresolved
+  /**
+   * Check a document against the schema while extracting its coordinates, collecting every
+   * unknown field, argument, fragment and type instead of stopping at the first. Coordinates
+   * are still extracted from the rest of the document. Issues are sorted by position.
+   */
+  validateAndExtract(documentText: string): ValidatedExtraction
+  /**
+   * Extract schema coordinates from a document, along with an ETag derived from them. Documents
+   * that use the same coordinates share an ETag, however they're written.
+   */
+  extractWithEtag(documentText: string): ExtractionWithEtag
+  /**
+   * Extract schema coordinates from a document on a worker thread, calling `callback` with
+   * each coordinate the first time it's found instead of collecting them. The extraction
+   * waits for each call to return before continuing. Once the callback throws, it isn't
+   * called again and the returned promise rejects with its error.
+   */
+  extractStreaming(documentText: string, callback: ((arg: string) => void)): Promise<void>
+  /**
+   * Extract schema coordinates from a document as CSV, with a `coordinate,count` header and a
+   * row per coordinate, sorted by coordinate. The count is the number of times the document
+   * uses the coordinate, counting a fragment's coordinates each time it's spread.
+   */
+  extractCountsCsv(documentText: string): string
+  /**
+   * Extract schema coordinates from a document, sorted, each with the byte spans of the field
+   * and argument names in the document that use it
+   */
+  extractWithSpans(documentText: string): Array<CoordinateSpans>
+  /**
+   * Extract schema coordinates from a document that may contain template literal
+   * interpolations (`${...}`) or other minor syntax noise, such as the body of a `gql`
+   * tagged template. Definitions that fail to parse are skipped instead of failing the
+   * whole document, so this never throws.
+   */
+  extractTolerant(documentText: string): Array<string>
+  /** List the `InputType.field` coordinate of every input object field in the schema, sorted */
+  allInputCoordinates(): Array<string>
+  /** List every coordinate defined by the schema, sorted */
+  allCoordinates(): Array<string>
+  /**
+   * List every coordinate defined by the schema, sorted, each tagged with the kind of schema
+   * element it refers to
+   */
+  allCoordinatesDetailed(): Array<Coordinate>
+  /**
+   * List the `@directive` and `@directive(arg:)` coordinates of every directive defined by the
+   * schema, sorted
+   */
+  allDirectiveCoordinates(): Array<string>
+  /**
+   * Extract schema coordinates from a document as a bitset, one bit per coordinate listed by
+   * `all_coordinates`, in order. Bit `i` is bit `i % 8` of byte `i / 8`, counting from the
+   * least significant bit. Coordinates the schema doesn't define, like unknown fields, are
+   * left out. Bitsets are only meaningful for the schema they were extracted with. With
+   * `normalize_case`, bits follow the coordinates' order before normalizing, so coordinates
+   * differing only in case keep separate bits.
+   */
+  extractCoordinateBitset(documentText: string): Buffer
+  /**
+   * List the coordinates set in a bitset from `extract_coordinate_bitset`, sorted. Bits past
+   * the schema's last coordinate are ignored.
+   */
+  decodeCoordinateBitset(bitset: Buffer): Array<string>
+  /**
+   * Check if the schema defines a coordinate, written as extraction returns it: a type,
+   * `Type.field`, `Type.field(arg:)`, `Enum.VALUE`, `@directive` or `@directive(arg:)`.
+   * Malformed coordinates aren't defined.
+   */
+  hasCoordinate(coordinate: string): boolean
   /** Check if a field exists on a type */
   hasField(coordinate: string): boolean
+  /**
+   * List the object types an interface or union can resolve to, sorted: the object types
+   * implementing an interface, or the members of a union. Empty for other and unknown types.
+   */
+  possibleTypes(abstractTypeName: string): Array<string>
+}
+
+/**
+ * Named schemas, such as the subgraphs of a federated graph, for finding which of them define
+ * the coordinates a document uses
+ */
+export declare class SchemaRegistry {
+  constructor()
+  /** Parse and register a schema under a name, replacing any schema registered under it */
+  register(name: string, schemaText: string): void
+  /**
+   * Map each schema coordinate a document uses to the names of the registered schemas that
+   * define it, in registration order. The document is extracted against every schema, and
+   * coordinates no schema defines map to `<unknown>`.
+   */
+  attributeCoordinates(documentText: string): Record<string, Array<string>>
+}
+
+/**
+ * Versions of a schema, such as a deployment's schema before and after a rollout, parsed once
+ * and kept so documents can be extracted against a specific version. Holds at most `capacity`
+ * versions, evicting the least recently used one to make room for another.
+ */
+export declare class VersionedSchemaRegistry {
+  /** Create a registry holding at most `capacity` schema versions */
+  constructor(capacity: number)
+  /**
+   * Parse and store a schema under a version, such as a hash of its text, replacing any
+   * schema stored under it. Evicts the least recently used version when the registry is full.
+   */
+  put(version: string, schemaText: string): void
+  /**
+   * Extract schema coordinates from a document against a stored schema version, marking it
+   * as recently used. Throws if the version isn't stored, such as after being evicted.
+   */
+  extract(version: string, documentText: string): Array<string>
+  /** The stored versions, from least to most recently used */
+  versions(): Array<string>
+}
+
+/** A coordinate defined by the schema, as listed by `ParsedSchema.allCoordinatesDetailed` */
+export interface Coordinate {
+  coordinate: string
+  kind: CoordinateKind
+}
+
+/** The kind of schema element a coordinate refers to */
+export declare enum CoordinateKind {
+  /** An object, interface, custom scalar, enum or input object type, such as `Human` */
+  Type = 'Type',
+  /** A field of an object or interface type, such as `Human.name` */
+  OutputField = 'OutputField',
+  /** A field of an input object type, such as `VetDetailsInput.name` */
+  InputField = 'InputField',
+  /** An argument of a field or directive, such as `Mutation.addCat(name:)` */
+  Argument = 'Argument',
+  /** A value of an enum type, such as `Species.DOG` */
+  EnumValue = 'EnumValue',
+  /** A directive defined by the schema, such as `@auth` */
+  Directive = 'Directive'
+}
+
+/** A variable referenced by an operation, as listed by `ParsedSchema.extractUsedVariables` */
+export interface UsedVariable {
+  name: string
+  /** The variable's declared type, such as `[ID!]!` */
+  type: string
+}
+
+/** The schema coordinates of one operation, as listed by `ParsedSchema.extractAttributed` */
+export interface AttributedCoordinates {
+  /** The operation's name, if it has one */
+  operation?: string
+  /** Sorted coordinates produced by the operation's own selections and variables */
+  direct: Array<string>
+  /** Sorted coordinates produced by each spread fragment, keyed by fragment name */
+  fragments: Record<string, Array<string>>
+}
+
+/**
+ * A deprecated schema element used by a document, as listed by
+ * `ParsedSchema.extractDeprecatedUsages`
+ */
+export interface DeprecatedUsage {
+  coordinate: string
+  /** Whether the coordinate is an output field, an argument or an input field */
+  kind: CoordinateKind
+  /** The `reason` given by the `@deprecated` directive, if any */
+  reason?: string
+}
+
+/** A coordinate used by a document, as listed by `ParsedSchema.extractWithDepths` */
+export interface CoordinateDepth {
+  coordinate: string
+  /**
+   * The smallest selection depth the coordinate is used at. Coordinates used outside any
+   * selection, such as variables' input types, are at depth 0
+   */
+  minDepth: number
+}
+
+/** A coordinate used by a document, as listed by `ParsedSchema.extractWithSpans` */
+export interface CoordinateSpans {
+  coordinate: string
+  /**
+   * Where the field and argument names using the coordinate are in the document. Empty for
+   * other coordinates, such as variables' input types
+   */
+  spans: Array<SourceSpan>
+}
+
+/** A range of bytes in a document's source text */
+export interface SourceSpan {
+  /** Offset of the first byte */
+  start: number
+  /** Offset just past the last byte */
+  end: number
+}
+
+/** How a document uses a coordinate, as listed by `ParsedSchema.extractWithOrigins` */
+export declare enum CoordinateOrigin {
+  /** A selected field, such as `Human.name` */
+  Selection = 'Selection',
+  /**
+   * A variable's input type, or the input fields and enum values extracted for it, such as
+   * `VetDetailsInput`
+   */
+  Variable = 'Variable',
+  /**
+   * A field argument, or the enum values and input fields passed to it, such as
+   * `Root.pets(species:)`
+   */
+  Argument = 'Argument',
+  /** An applied directive, its arguments, or the input fields passed to them, such as `@auth` */
+  Directive = 'Directive'
+}
+
+/** A coordinate used by a document, as listed by `ParsedSchema.extractWithOrigins` */
+export interface CoordinateOrigins {
+  coordinate: string
+  /** Every way the document uses the coordinate */
+  origins: Array<CoordinateOrigin>
+}
+
+/** Schema coordinates extracted from a document, as returned by `ParsedSchema.extractWithEtag` */
+export interface ExtractionWithEtag {
+  coordinates: Array<string>
+  /**
+   * A 64-bit FNV-1a hash of the sorted coordinates, as 16 hex digits. It's stable across
+   * versions and platforms, but needs quoting for use as an HTTP `ETag` header.
+   */
+  etag: string
+}
+
+/** A line and column in a document's source text, both starting at 1 */
+export interface SourcePosition {
+  line: number
+  column: number
+}
+
+/** The kind of problem found by `ParsedSchema.validateAndExtract` */
+export declare enum IssueKind {
+  /** A field its parent type doesn't declare, such as `Human.wings` */
+  UnknownField = 'UnknownField',
+  /** An argument its field doesn't declare, such as `Root.pets(limit:)` */
+  UnknownArgument = 'UnknownArgument',
+  /** A spread of a fragment the document doesn't define */
+  UnknownFragment = 'UnknownFragment',
+  /** A type condition or variable type the schema doesn't define */
+  UnknownType = 'UnknownType'
+}
+
+/** A problem found in a document by `ParsedSchema.validateAndExtract` */
+export interface Issue {
+  kind: IssueKind
+  message: string
+  /**
+   * Where the field, fragment spread, fragment or variable with the problem starts. Argument
+   * issues are at their field.
+   */
+  position: SourcePosition
+}
+
+/**
+ * Schema coordinates extracted from a document, with the problems found in it, as returned by
+ * `ParsedSchema.validateAndExtract`
+ */
+export interface ValidatedExtraction {
+  coordinates: Array<string>
+  issues: Array<Issue>
+}
+
+/**
+ * Schema coordinates extracted from a document, as returned by
+ * `ParsedSchema.extractWithMaxDepth`
+ */
+export interface ExtractionWithMaxDepth {
+  coordinates: Array<string>
+  /** The deepest selection depth of any field in the document, or 0 if it selects no fields */
+  maxDepth: number
+}
+
+/**
+ * The schema coordinates of one document in a blob, as listed by
+ * `ParsedSchema.extractSchemaCoordinatesMulti`
+ */
+export interface PartExtraction {
+  /** The part's coordinates, or empty if it failed */
+  coordinates: Array<string>
+  /** Why the part failed to parse or extract, if it did */
+  error?: string
+}
+
+/**
+ * Schema coordinates extracted from a document, as returned by
+ * `ParsedSchema.extractWithUnresolved`
+ */
+export interface ExtractionWithUnresolved {
+  coordinates: Array<string>
+  /** The sorted names of spread fragments that the document doesn't define */
+  unresolvedFragments: Array<string>
+}
+
+/** Counters describing the work done while extracting coordinates from a document */
+export interface ExtractionStats {
+  /** Number of field selections visited, including fields inside fragments */
+  fieldsVisited: number
+  /** Number of fragment spreads that were expanded */
+  fragmentsExpanded: number
+  /** Time taken to parse the document and extract its coordinates, in microseconds */
+  durationMicros: number
+  /**
+   * Number of fields, fragment spreads and inline fragments visited, including those inside
+   * fragments
+   */
+  selectionsVisited: number
+  /** Number of selections in the widest selection set visited */
+  maxFanout: number
+}
+
+/** Schema coordinates extracted from a document, along with stats about the extraction */
+export interface ExtractionWithStats {
+  coordinates: Array<string>
+  stats: ExtractionStats
+}
+
+/**
+ * Schema coordinates extracted from a document, along with measures of how costly the
+ * document is to traverse, as returned by `ParsedSchema.extractSecurityReport`
+ */
+export interface SecurityReport {
+  coordinates: Array<string>
+  /**
+   * Number of fields, fragment spreads and inline fragments visited, counting those inside
+   * a fragment each time it's expanded
+   */
+  nodesVisited: number
+  /** Number of selections in the widest selection set */
+  maxFanout: number
+  /** Number of fragment spreads that were expanded */
+  fragmentExpansions: number
+}
+
+/** How extracted coordinates are ordered, as set by `ExtractionOptions.order` */
+export declare enum CoordinateOrder {
+  /** Sorted alphabetically */
+  Alphabetical = 'alphabetical',
+  /**
+   * By where their type is defined in the schema, then alphabetically, with directive
+   * coordinates last
+   */
+  Schema = 'schema',
+  /** In the order the document first uses them, for `extractSchemaCoordinates` */
+  Document = 'document'
+}
+
+/** Which coordinate forms are extracted, as set by `ExtractionOptions.outputFormat` */
+export declare enum OutputFormat {
+  /** Only the forms enabled by the other options */
+  Legacy = 'legacy',
+  /**
+   * Every form in the schema coordinate spec: types, fields, arguments, directives, directive
+   * arguments, enum values and input fields, with a `.` separator and no prefix
+   */
+  Rfc = 'rfc'
+}
+
+/**
+ * Which input field coordinates are extracted for variables of input object types, as set by
+ * `ExtractionOptions.inputFieldsFromVariables`
+ */
+export declare enum VariableInputFields {
+  /** No input fields, only the input type */
+  None = 'none',
+  /**
+   * Every declared field, recursing into nested input types, plus the enum values that
+   * fields default to
+   */
+  All = 'all'
+}
+
+/**
+ * Which types `__typename` selected on an interface or union is extracted under, as set by
+ * `ExtractionOptions.typenameAttribution`
+ */
+export declare enum TypenameAttribution {
+  /** The abstract type, such as `Animal.__typename` */
+  Abstract = 'abstract',
+  /** Each of its possible object types, such as `Dog.__typename` */
+  Concrete = 'concrete'
+}
+
+/** Options controlling which coordinates are extracted from documents */
+export interface ExtractionOptions {
+  /** Also extract `@name` coordinates for directives applied in documents (default: false) */
+  includeDirectives?: boolean
+  /** Separator placed between a type name and a field name in coordinates (default: ".") */
+  separator?: string
+  /**
+   * Only extract input types for variables that are referenced in arguments or directives,
+   * rather than every declared variable (default: false)
+   */
+  onlyUsedVariables?: boolean
+  /**
+   * Build each type's info the first time it's looked up instead of up front. Speeds up
+   * construction for very large schemas when documents only touch a few types (default: false)
+   */
+  lazyTypeMap?: boolean
+  /**
+   * Return an error when a subscription operation selects more than one root field, which
+   * the spec disallows. By default all root fields are extracted (default: false)
+   */
+  singleSubscriptionRoot?: boolean
+  /**
+   * Only extract field coordinates at this selection depth or deeper, where root fields are at
+   * depth 1. Fragments don't add depth (default: no minimum)
+   */
+  minDepth?: number
+  /**
+   * Only extract field coordinates at this selection depth or shallower, where root fields are
+   * at depth 1. Fragments don't add depth (default: no maximum)
+   */
+  maxDepth?: number
+  /**
+   * Also extract `Type.field(arg:)` coordinates for arguments supplied in documents
+   * (default: false)
+   */
+  includeArguments?: boolean
+  /**
+   * Also extract the enum type of enum-typed arguments, plus `Enum.VALUE` coordinates for enum
+   * values passed as literals (default: false)
+   */
+  includeEnumValues?: boolean
+  /**
+   * Treat the schema as an Apollo Federation subgraph, adding the `_service` and `_entities`
+   * root fields and their `_Service`, `_Entity` and `_Any` types (default: false)
+   */
+  fromSubgraph?: boolean
+  /**
+   * Return an error naming the coordinates of selected fields that aren't declared on their
+   * (known) parent type. By default they are extracted like any other field (default: false)
+   */
+  errorOnUnknownFields?: boolean
+  /**
+   * Which input field coordinates to extract for variables of input object types
+   * (default: "none")
+   */
+  inputFieldsFromVariables?: VariableInputFields
+  /**
+   * Return an error naming the coordinates of scalar and enum fields that have a selection
+   * set. By default the selections are skipped (default: false)
+   */
+  errorOnSubselectionOfScalar?: boolean
+  /**
+   * Extra type name aliases, mapped to the schema types they resolve to, on top of the
+   * `Query`, `Mutation` and `Subscription` root aliases. Coordinates always use the actual
+   * type name. Aliases can't reuse the name of a schema type or a root alias, and must
+   * resolve to a type the schema defines (default: none)
+   */
+  rootAliases?: Record<string, string>
+  /**
+   * Namespace prepended to every extracted and listed coordinate, such as `pets` in
+   * `pets:Root.animalOwner` (default: none)
+   */
+  coordinatePrefix?: string
+  /** Separator placed between the coordinate prefix and the coordinate (default: ":") */
+  coordinatePrefixSeparator?: string
+  /**
+   * Return an error, before extracting anything, for documents with more operations than this
+   * (default: no limit)
+   */
+  maxOperations?: number
+  /**
+   * Also extract `Input.field` coordinates for the input object fields set in argument values,
+   * including inside lists and nested objects, for both field and directive arguments
+   * (default: false)
+   */
+  includeInputFields?: boolean
+  /**
+   * Also extract `@directive(arg:)` coordinates for directive arguments supplied in documents
+   * (default: false)
+   */
+  includeDirectiveArguments?: boolean
+  /**
+   * Which coordinate forms to extract. Individual forms of the `"rfc"` format can still be
+   * turned off explicitly (default: "legacy")
+   */
+  outputFormat?: OutputFormat
+  /**
+   * Extract fields selected on an interface that only its implementations declare, such as
+   * `breed` in a fragment on `Animal`, as `Dog.breed` for each implementation declaring them,
+   * instead of as `Animal.breed`. Use `error_on_unknown_fields` to reject them instead
+   * (default: false)
+   */
+  attributeInterfaceFieldsToMembers?: boolean
+  /**
+   * Coordinates to leave out of extraction output, matched exactly and without the
+   * coordinate prefix, such as `Root.__typename` (default: none)
+   */
+  excludeCoordinates?: Array<string>
+  /**
+   * Also skip the selections of excluded fields, so nothing beneath them is extracted
+   * (default: false)
+   */
+  skipChildrenOfExcluded?: boolean
+  /** Return extracted coordinates sorted, rather than in no particular order (default: false) */
+  sorted?: boolean
+  /**
+   * Schema type names mapped to new names to report them under, such as `Human` to `Person`.
+   * Documents can select the type by either name (default: none)
+   */
+  typeRenames?: Record<string, string>
+  /**
+   * Skip fields, fragment spreads and inline fragments whose `@skip` or `@include` condition
+   * is a literal that excludes them, such as `@include(if: false)` (default: false)
+   */
+  evaluateStaticConditions?: boolean
+  /**
+   * Also extract the fields named by the `@requires` and `@provides` field sets of selected
+   * fields in a subgraph schema, such as `Product.weight` for
+   * `shippingEstimate: Int @requires(fields: "weight")` (default: false)
+   */
+  expandFederationFieldSets?: boolean
+  /**
+   * Extract fields selected on an object type that its interfaces declare under each declaring
+   * interface, such as `Animal.name` for `name` on `Dog`, instead of the object type
+   * (default: false)
+   */
+  collapseToInterface?: boolean
+  /**
+   * Which types `__typename` is extracted under when selected on an interface or union
+   * (default: "abstract")
+   */
+  typenameAttribution?: TypenameAttribution
+  /**
+   * How to order extracted coordinates. Can't be combined with `sorted`, which is the same as
+   * `"alphabetical"` (default: no particular order)
+   */
+  order?: CoordinateOrder
+  /**
+   * Return an error naming the types of fragment type conditions that the schema doesn't
+   * define. By default their selections are extracted under the unknown type (default: false)
+   */
+  errorOnUnknownTypeConditions?: boolean
+  /**
+   * Lowercase the type and field names in extracted and listed coordinates, such as
+   * `root.animalowner`, for case-insensitive aggregation keys. Directive coordinates, the
+   * separator and the coordinate prefix keep their case. Types are still filtered, ordered and
+   * looked up by their original names, and `has_coordinate` accepts normalized coordinates
+   * (default: false)
+   */
+  normalizeCase?: boolean
+  /**
+   * Most persisted queries `register_and_extract` keeps, evicting the least recently used one
+   * to make room for another (default: 1000)
+   */
+  persistedQueryCapacity?: number
 }
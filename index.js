@@ -572,4 +572,17 @@ if (!nativeBinding) {
 }
 
 module.exports = nativeBinding
+module.exports.CoordinateBuffer = nativeBinding.CoordinateBuffer
+module.exports.CoordinateKind = nativeBinding.CoordinateKind
+module.exports.CoordinateOrder = nativeBinding.CoordinateOrder
+module.exports.CoordinateOrigin = nativeBinding.CoordinateOrigin
+module.exports.Extractor = nativeBinding.Extractor
+module.exports.FragmentRegistry = nativeBinding.FragmentRegistry
+module.exports.IncrementalExtractor = nativeBinding.IncrementalExtractor
+module.exports.IssueKind = nativeBinding.IssueKind
+module.exports.OutputFormat = nativeBinding.OutputFormat
 module.exports.ParsedSchema = nativeBinding.ParsedSchema
+module.exports.SchemaRegistry = nativeBinding.SchemaRegistry
+module.exports.TypenameAttribution = nativeBinding.TypenameAttribution
+module.exports.VariableInputFields = nativeBinding.VariableInputFields
+module.exports.VersionedSchemaRegistry = nativeBinding.VersionedSchemaRegistry
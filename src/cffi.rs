@@ -0,0 +1,146 @@
+//! A C ABI for extracting coordinates outside Node, such as from Go or Python through FFI.
+//!
+//! Coordinates are returned as one buffer of length-delimited UTF-8 strings: each coordinate is
+//! a little-endian `u32` byte length followed by its bytes.
+
+use crate::ParsedSchema;
+use std::{ptr, slice, str};
+
+/// The coordinates were extracted and written to the out-pointers
+pub const FGT_OK: i32 = 0;
+/// A required pointer was null
+pub const FGT_NULL_POINTER: i32 = 1;
+/// The schema or document wasn't valid UTF-8
+pub const FGT_INVALID_UTF8: i32 = 2;
+/// The schema failed to parse
+pub const FGT_SCHEMA_ERROR: i32 = 3;
+/// The document failed to parse or extract
+pub const FGT_DOCUMENT_ERROR: i32 = 4;
+
+/// Extracts the schema coordinates used by a document, writing a length-delimited buffer of them
+/// to `out_buffer` and `out_length`. On success the buffer must be released with
+/// `fgt_free_buffer`. On failure nothing is written to the out-pointers.
+///
+/// # Safety
+///
+/// `schema` and `document` must point to `schema_length` and `document_length` readable bytes,
+/// and `out_buffer` and `out_length` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fgt_extract_schema_coordinates(
+    schema: *const u8,
+    schema_length: usize,
+    document: *const u8,
+    document_length: usize,
+    out_buffer: *mut *mut u8,
+    out_length: *mut usize,
+) -> i32 {
+    if schema.is_null() || document.is_null() || out_buffer.is_null() || out_length.is_null() {
+        return FGT_NULL_POINTER;
+    }
+
+    let schema_bytes = slice::from_raw_parts(schema, schema_length);
+    let document_bytes = slice::from_raw_parts(document, document_length);
+    let (Ok(schema_text), Ok(document_text)) =
+        (str::from_utf8(schema_bytes), str::from_utf8(document_bytes))
+    else {
+        return FGT_INVALID_UTF8;
+    };
+
+    let Ok(parsed_schema) = ParsedSchema::new(schema_text.to_string()) else {
+        return FGT_SCHEMA_ERROR;
+    };
+    let Ok(coordinates) = parsed_schema.extract_schema_coordinates(document_text.to_string())
+    else {
+        return FGT_DOCUMENT_ERROR;
+    };
+
+    let buffer = encode_coordinates(&coordinates).into_boxed_slice();
+    *out_length = buffer.len();
+    *out_buffer = Box::into_raw(buffer) as *mut u8;
+    FGT_OK
+}
+
+/// Releases a buffer returned by `fgt_extract_schema_coordinates`. Null buffers are ignored.
+///
+/// # Safety
+///
+/// `buffer` and `length` must be exactly as returned by a successful extraction, and the buffer
+/// must not be used or released again afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn fgt_free_buffer(buffer: *mut u8, length: usize) {
+    if !buffer.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buffer, length)));
+    }
+}
+
+/// Writes each coordinate as its little-endian `u32` byte length followed by its bytes
+fn encode_coordinates(coordinates: &[String]) -> Vec<u8> {
+    let length = coordinates.iter().map(|c| 4 + c.len()).sum();
+    let mut buffer = Vec::with_capacity(length);
+    for coordinate in coordinates {
+        buffer.extend_from_slice(&(coordinate.len() as u32).to_le_bytes());
+        buffer.extend_from_slice(coordinate.as_bytes());
+    }
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = include_str!("../testing/pets.schema.graphql");
+
+    /// Calls the extraction through raw pointers, like a C caller would
+    fn extract(schema: &[u8], document: &[u8]) -> (i32, Vec<String>) {
+        let mut buffer = ptr::null_mut();
+        let mut length = 0;
+        let status = unsafe {
+            fgt_extract_schema_coordinates(
+                schema.as_ptr(),
+                schema.len(),
+                document.as_ptr(),
+                document.len(),
+                &mut buffer,
+                &mut length,
+            )
+        };
+        if status != FGT_OK {
+            assert!(buffer.is_null());
+            return (status, Vec::new());
+        }
+
+        let bytes = unsafe { slice::from_raw_parts(buffer, length) }.to_vec();
+        unsafe { fgt_free_buffer(buffer, length) };
+
+        let mut coordinates = Vec::new();
+        let mut rest = bytes.as_slice();
+        while let Some((prefix, tail)) = rest.split_first_chunk::<4>() {
+            let (coordinate, tail) = tail.split_at(u32::from_le_bytes(*prefix) as usize);
+            coordinates.push(String::from_utf8(coordinate.to_vec()).expect("Should be UTF-8"));
+            rest = tail;
+        }
+        coordinates.sort();
+        (status, coordinates)
+    }
+
+    #[test]
+    fn test_extracts_length_delimited_coordinates() {
+        let (status, coordinates) = extract(SCHEMA.as_bytes(), b"{ animalOwner { name } }");
+
+        assert_eq!(status, FGT_OK);
+        assert_eq!(coordinates, vec!["Human.name", "Root.animalOwner"]);
+    }
+
+    #[test]
+    fn test_error_statuses() {
+        assert_eq!(
+            extract(b"type {", b"{ animalOwner { name } }").0,
+            FGT_SCHEMA_ERROR
+        );
+        assert_eq!(
+            extract(SCHEMA.as_bytes(), b"{ animalOwner {").0,
+            FGT_DOCUMENT_ERROR
+        );
+        assert_eq!(extract(SCHEMA.as_bytes(), b"{ \xff }").0, FGT_INVALID_UTF8);
+    }
+}
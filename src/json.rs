@@ -0,0 +1,286 @@
+//! Parsing JSON, such as the variables a document is executed with, into GraphQL values so they
+//! can be walked like values written in documents.
+
+use graphql_parser::query::{Number, Value};
+use std::collections::BTreeMap;
+use std::str::CharIndices;
+
+/// How deeply objects and lists can be nested. Parsing recurses into each level, so deeper
+/// input could overflow the stack.
+pub(crate) const MAX_NESTING: usize = 128;
+
+/// Why parsing JSON failed, with the byte offset parsing stopped at
+#[derive(Debug, PartialEq)]
+pub(crate) enum JsonError {
+    /// The text isn't valid JSON
+    Syntax(usize),
+    /// Objects and lists are nested more than `MAX_NESTING` levels deep
+    TooDeep(usize),
+}
+
+/// Parses a JSON document into the equivalent GraphQL value. Numbers that fit in a 32-bit integer
+/// become `Int`s and other numbers `Float`s.
+pub(crate) fn parse_json(text: &str) -> Result<Value<'static, String>, JsonError> {
+    let mut parser = Parser {
+        text,
+        offset: 0,
+        nesting: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.offset < text.len() {
+        return Err(JsonError::Syntax(parser.offset));
+    }
+    Ok(value)
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    offset: usize,
+    /// How many objects and lists enclose the current offset
+    nesting: usize,
+}
+
+impl Parser<'_> {
+    fn value(&mut self) -> Result<Value<'static, String>, JsonError> {
+        self.skip_whitespace();
+        let rest = &self.text[self.offset..];
+        match rest.as_bytes().first() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.list(),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => {
+                let (literal, value) = if rest.starts_with("true") {
+                    ("true", Value::Boolean(true))
+                } else if rest.starts_with("false") {
+                    ("false", Value::Boolean(false))
+                } else if rest.starts_with("null") {
+                    ("null", Value::Null)
+                } else {
+                    return Err(JsonError::Syntax(self.offset));
+                };
+                self.offset += literal.len();
+                Ok(value)
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value<'static, String>, JsonError> {
+        self.enter()?;
+        let mut fields = BTreeMap::new();
+        if self.next_is(b'}') {
+            self.nesting -= 1;
+            return Ok(Value::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if !self.text[self.offset..].starts_with('"') {
+                return Err(JsonError::Syntax(self.offset));
+            }
+            let name = self.string()?;
+            self.expect(b':')?;
+            fields.insert(name, self.value()?);
+
+            if !self.next_is(b',') {
+                self.expect(b'}')?;
+                self.nesting -= 1;
+                return Ok(Value::Object(fields));
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<Value<'static, String>, JsonError> {
+        self.enter()?;
+        let mut items = Vec::new();
+        if self.next_is(b']') {
+            self.nesting -= 1;
+            return Ok(Value::List(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            if !self.next_is(b',') {
+                self.expect(b']')?;
+                self.nesting -= 1;
+                return Ok(Value::List(items));
+            }
+        }
+    }
+
+    /// Steps into the object or list starting at the current offset, unless that would nest
+    /// too deeply
+    fn enter(&mut self) -> Result<(), JsonError> {
+        if self.nesting == MAX_NESTING {
+            return Err(JsonError::TooDeep(self.offset));
+        }
+        self.nesting += 1;
+        self.offset += 1;
+        Ok(())
+    }
+
+    /// Parses the string starting at the current offset, unescaping it
+    fn string(&mut self) -> Result<String, JsonError> {
+        let start = self.offset + 1;
+        let mut string = String::new();
+        let mut chars = self.text[start..].char_indices();
+
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.offset = start + index + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some((_, '"')) => '"',
+                        Some((_, '\\')) => '\\',
+                        Some((_, '/')) => '/',
+                        Some((_, 'b')) => '\u{8}',
+                        Some((_, 'f')) => '\u{c}',
+                        Some((_, 'n')) => '\n',
+                        Some((_, 'r')) => '\r',
+                        Some((_, 't')) => '\t',
+                        Some((_, 'u')) => {
+                            unicode_escape(&mut chars).ok_or(JsonError::Syntax(start + index))?
+                        }
+                        _ => return Err(JsonError::Syntax(start + index)),
+                    };
+                    string.push(escaped);
+                }
+                c if c < ' ' => return Err(JsonError::Syntax(start + index)),
+                c => string.push(c),
+            }
+        }
+        Err(JsonError::Syntax(self.text.len()))
+    }
+
+    fn number(&mut self) -> Result<Value<'static, String>, JsonError> {
+        let start = self.offset;
+        let length = self.text[start..]
+            .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+            .unwrap_or(self.text.len() - start);
+        let number = &self.text[start..start + length];
+
+        let value = match number.parse::<i32>() {
+            Ok(int) => Value::Int(Number::from(int)),
+            Err(_) => Value::Float(number.parse().map_err(|_| JsonError::Syntax(start))?),
+        };
+        self.offset += length;
+        Ok(value)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.offset..];
+        self.offset += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    /// Skips whitespace and then `byte` if it's next, returning whether it was
+    fn next_is(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let is_next = self.text.as_bytes().get(self.offset) == Some(&byte);
+        if is_next {
+            self.offset += 1;
+        }
+        is_next
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        if self.next_is(byte) {
+            Ok(())
+        } else {
+            Err(JsonError::Syntax(self.offset))
+        }
+    }
+}
+
+/// Reads the four hex digits of a `\u` escape, and the low surrogate escape following a high
+/// surrogate. Unpaired surrogates are replaced with U+FFFD.
+fn unicode_escape(chars: &mut CharIndices) -> Option<char> {
+    fn hex_digits(chars: &mut CharIndices) -> Option<u32> {
+        let digits: String = chars.take(4).map(|(_, c)| c).collect();
+        (digits.len() == 4)
+            .then(|| u32::from_str_radix(&digits, 16).ok())
+            .flatten()
+    }
+
+    let code = hex_digits(chars)?;
+    if !(0xd800..0xdc00).contains(&code) {
+        return Some(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+    }
+
+    let mut lookahead = chars.clone();
+    if let (Some((_, '\\')), Some((_, 'u'))) = (lookahead.next(), lookahead.next()) {
+        if let Some(low) = hex_digits(&mut lookahead).filter(|low| (0xdc00..0xe000).contains(low)) {
+            *chars = lookahead;
+            return char::from_u32(0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00));
+        }
+    }
+    Some(char::REPLACEMENT_CHARACTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_nested_values() {
+        let value = parse_json(r#" {"a": [1, -2.5, true, null], "b": {"c": "d"}, "e": {}} "#)
+            .expect("Should parse JSON");
+
+        assert_eq!(
+            value,
+            Value::Object(BTreeMap::from([
+                (
+                    "a".to_string(),
+                    Value::List(vec![
+                        Value::Int(Number::from(1)),
+                        Value::Float(-2.5),
+                        Value::Boolean(true),
+                        Value::Null,
+                    ])
+                ),
+                (
+                    "b".to_string(),
+                    Value::Object(BTreeMap::from([(
+                        "c".to_string(),
+                        Value::String("d".to_string())
+                    )]))
+                ),
+                ("e".to_string(), Value::Object(BTreeMap::new())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_unescapes_strings() {
+        assert_eq!(
+            parse_json(r#""a\"b\\c\n\u00e9\ud83d\udc36""#),
+            Ok(Value::String("a\"b\\c\né🐶".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_reports_error_offset() {
+        assert_eq!(parse_json(r#"{"a": 1,}"#), Err(JsonError::Syntax(8)));
+        assert_eq!(parse_json(r#"{"a": 1} x"#), Err(JsonError::Syntax(9)));
+        assert_eq!(parse_json(r#"["a"#), Err(JsonError::Syntax(3)));
+    }
+
+    #[test]
+    fn test_limits_nesting() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+
+        assert!(parse_json(&nested(MAX_NESTING)).is_ok());
+        assert_eq!(
+            parse_json(&nested(MAX_NESTING + 1)),
+            Err(JsonError::TooDeep(MAX_NESTING))
+        );
+        assert_eq!(
+            parse_json(&format!(r#"{{"a": {}}}"#, "[".repeat(1_000_000))),
+            Err(JsonError::TooDeep(6 + MAX_NESTING - 1))
+        );
+    }
+}
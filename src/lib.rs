@@ -2,778 +2,11971 @@
 
 use graphql_parser::{query, schema};
 use napi::bindgen_prelude::*;
+use napi::threadsafe_function::{ThreadsafeFunction, ThreadsafeFunctionCallMode};
+use napi::{Env, Task};
 use napi_derive::napi;
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+#[cfg(feature = "cffi")]
+mod cffi;
+mod json;
+mod spans;
+mod tolerant;
+
+/// The parser whose documents `ParsedSchema::from_schema_document` and
+/// `ParsedSchema::extract_from_query_document` accept, so Rust callers parse with the same version
+pub use graphql_parser;
 
 /// A parsed GraphQL schema that can be reused to extract coordinates from multiple documents
 #[napi]
 pub struct ParsedSchema {
-    type_map: Arc<HashMap<String, TypeInfo>>,
+    /// The schema's state, shared with the extractors created from it
+    data: Arc<SchemaData>,
+    /// Coordinates of registered persisted queries, keyed by their hash
+    persisted_queries: Mutex<PersistedQueries>,
 }
 
-#[napi]
-impl ParsedSchema {
-    /// Create a new ParsedSchema from a schema string
-    #[napi(constructor)]
-    pub fn new(schema_text: String) -> Result<Self> {
-        // Parse the schema
-        let schema_doc = schema::parse_schema::<String>(&schema_text)
-            .map_err(|e| Error::from_reason(format!("Failed to parse schema: {}", e)))?;
+/// The state of a parsed schema, which doesn't change once it's built
+#[derive(Clone)]
+struct SchemaData {
+    type_map: Arc<TypeMap>,
+    options: ExtractionOptions,
+    /// The directives defined by the schema, keyed by name
+    directives: HashMap<String, DirectiveInfo>,
+    /// The object types implementing each interface, keyed by interface name
+    implementations: HashMap<String, Vec<String>>,
+    /// The member types of each union, keyed by union name
+    union_members: HashMap<String, Vec<String>>,
+    /// Coordinates suppressed from extraction output, from `exclude_coordinates`
+    excluded_coordinates: HashSet<String>,
+    /// The sorted coordinates of the schema, indexing the bits of coordinate bitsets. Listed on
+    /// first use.
+    coordinate_index: OnceLock<Vec<String>>,
+    /// The coordinates of the schema with their case normalized, for looking up coordinates
+    /// returned with `normalize_case`. Listed on first use.
+    normalized_coordinates: OnceLock<HashSet<String>>,
+    /// Decides which other types count as leaf scalars, from `with_scalar_predicate`
+    scalar_predicate: Option<ScalarPredicate>,
+}
 
-        // Build type map and wrap in Arc
-        let type_map = Arc::new(build_type_map(&schema_doc));
+/// The coordinates of persisted queries registered with `ParsedSchema.registerAndExtract`,
+/// holding at most `capacity` of them and evicting the least recently used one to make room for
+/// another
+#[derive(Default)]
+struct PersistedQueries {
+    /// Coordinates keyed by query hash, with when the query was last used
+    entries: HashMap<String, (u64, Vec<String>)>,
+    /// Counts uses, to order entries by when they were last used
+    uses: u64,
+}
 
-        Ok(ParsedSchema { type_map })
+impl PersistedQueries {
+    /// Looks up the coordinates of a registered query, marking it as recently used
+    fn get(&mut self, hash: &str) -> Option<Vec<String>> {
+        self.uses += 1;
+        let (last_used, coordinates) = self.entries.get_mut(hash)?;
+        *last_used = self.uses;
+        Some(coordinates.clone())
     }
 
-    /// Extract schema coordinates from a document using this parsed schema
-    #[napi]
-    pub fn extract_schema_coordinates(&self, document_text: String) -> Result<Vec<String>> {
-        let mut coordinates = HashSet::new();
-
-        // Parse the document
-        let query_doc = query::parse_query::<String>(&document_text)
-            .map_err(|e| Error::from_reason(format!("Failed to parse document: {}", e)))?;
-
-        // Extract coordinates from the document
-        for definition in &query_doc.definitions {
-            match definition {
-                query::Definition::Operation(operation) => {
-                    extract_from_operation(
-                        operation,
-                        &self.type_map,
-                        &query_doc,
-                        &mut coordinates,
-                    )?;
-                }
-                query::Definition::Fragment(_fragment) => {
-                    // Fragments are processed when referenced in operations
-                }
+    /// Registers a query's coordinates, evicting the least recently used query when full
+    fn insert(&mut self, hash: String, coordinates: Vec<String>, capacity: usize) {
+        if !self.entries.contains_key(&hash) && self.entries.len() >= capacity {
+            let least_recently_used = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(hash, _)| hash.clone());
+            if let Some(least_recently_used) = least_recently_used {
+                self.entries.remove(&least_recently_used);
             }
         }
+        self.uses += 1;
+        self.entries.insert(hash, (self.uses, coordinates));
+    }
+}
 
-        let result: Vec<String> = coordinates.into_iter().collect();
+/// Decides whether a type counts as a leaf scalar, given its name
+type ScalarPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
 
-        Ok(result)
-    }
+/// How extracted coordinates are ordered, as set by `ExtractionOptions.order`
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateOrder {
+    /// Sorted alphabetically
+    Alphabetical,
+    /// By where their type is defined in the schema, then alphabetically, with directive
+    /// coordinates last
+    Schema,
+    /// In the order the document first uses them, for `extractSchemaCoordinates`
+    Document,
+}
 
-    /// Check if a field exists on a type
-    #[napi]
-    pub fn has_field(&self, coordinate: String) -> Result<bool> {
-        // Parse the coordinate in the format "TypeName.fieldName"
-        let parts: Vec<&str> = coordinate.split('.').collect();
+/// Which coordinate forms are extracted, as set by `ExtractionOptions.outputFormat`
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Only the forms enabled by the other options
+    Legacy,
+    /// Every form in the schema coordinate spec: types, fields, arguments, directives, directive
+    /// arguments, enum values and input fields, with a `.` separator and no prefix
+    Rfc,
+}
 
-        if parts.len() != 2 {
-            return Ok(false);
-        }
+/// Which input field coordinates are extracted for variables of input object types, as set by
+/// `ExtractionOptions.inputFieldsFromVariables`
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableInputFields {
+    /// No input fields, only the input type
+    None,
+    /// Every declared field, recursing into nested input types, plus the enum values that
+    /// fields default to
+    All,
+}
 
-        let type_name = parts[0];
-        let field_name = parts[1];
+/// Which types `__typename` selected on an interface or union is extracted under, as set by
+/// `ExtractionOptions.typenameAttribution`
+#[napi(string_enum = "lowercase")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypenameAttribution {
+    /// The abstract type, such as `Animal.__typename`
+    Abstract,
+    /// Each of its possible object types, such as `Dog.__typename`
+    Concrete,
+}
 
-        if type_name.is_empty() || field_name.is_empty() {
-            return Ok(false);
-        }
+/// Options controlling which coordinates are extracted from documents
+#[napi(object)]
+#[derive(Debug, Clone, Default)]
+pub struct ExtractionOptions {
+    /// Also extract `@name` coordinates for directives applied in documents (default: false)
+    pub include_directives: Option<bool>,
+    /// Separator placed between a type name and a field name in coordinates (default: ".")
+    pub separator: Option<String>,
+    /// Only extract input types for variables that are referenced in arguments or directives,
+    /// rather than every declared variable (default: false)
+    pub only_used_variables: Option<bool>,
+    /// Build each type's info the first time it's looked up instead of up front. Speeds up
+    /// construction for very large schemas when documents only touch a few types (default: false)
+    pub lazy_type_map: Option<bool>,
+    /// Return an error when a subscription operation selects more than one root field, which
+    /// the spec disallows. By default all root fields are extracted (default: false)
+    pub single_subscription_root: Option<bool>,
+    /// Only extract field coordinates at this selection depth or deeper, where root fields are at
+    /// depth 1. Fragments don't add depth (default: no minimum)
+    pub min_depth: Option<u32>,
+    /// Only extract field coordinates at this selection depth or shallower, where root fields are
+    /// at depth 1. Fragments don't add depth (default: no maximum)
+    pub max_depth: Option<u32>,
+    /// Also extract `Type.field(arg:)` coordinates for arguments supplied in documents
+    /// (default: false)
+    pub include_arguments: Option<bool>,
+    /// Also extract the enum type of enum-typed arguments, plus `Enum.VALUE` coordinates for enum
+    /// values passed as literals (default: false)
+    pub include_enum_values: Option<bool>,
+    /// Treat the schema as an Apollo Federation subgraph, adding the `_service` and `_entities`
+    /// root fields and their `_Service`, `_Entity` and `_Any` types (default: false)
+    pub from_subgraph: Option<bool>,
+    /// Return an error naming the coordinates of selected fields that aren't declared on their
+    /// (known) parent type. By default they are extracted like any other field (default: false)
+    pub error_on_unknown_fields: Option<bool>,
+    /// Which input field coordinates to extract for variables of input object types
+    /// (default: "none")
+    pub input_fields_from_variables: Option<VariableInputFields>,
+    /// Return an error naming the coordinates of scalar and enum fields that have a selection
+    /// set. By default the selections are skipped (default: false)
+    pub error_on_subselection_of_scalar: Option<bool>,
+    /// Extra type name aliases, mapped to the schema types they resolve to, on top of the
+    /// `Query`, `Mutation` and `Subscription` root aliases. Coordinates always use the actual
+    /// type name. Aliases can't reuse the name of a schema type or a root alias, and must
+    /// resolve to a type the schema defines (default: none)
+    pub root_aliases: Option<HashMap<String, String>>,
+    /// Namespace prepended to every extracted and listed coordinate, such as `pets` in
+    /// `pets:Root.animalOwner` (default: none)
+    pub coordinate_prefix: Option<String>,
+    /// Separator placed between the coordinate prefix and the coordinate (default: ":")
+    pub coordinate_prefix_separator: Option<String>,
+    /// Return an error, before extracting anything, for documents with more operations than this
+    /// (default: no limit)
+    pub max_operations: Option<u32>,
+    /// Also extract `Input.field` coordinates for the input object fields set in argument values,
+    /// including inside lists and nested objects, for both field and directive arguments
+    /// (default: false)
+    pub include_input_fields: Option<bool>,
+    /// Also extract `@directive(arg:)` coordinates for directive arguments supplied in documents
+    /// (default: false)
+    pub include_directive_arguments: Option<bool>,
+    /// Which coordinate forms to extract. Individual forms of the `"rfc"` format can still be
+    /// turned off explicitly (default: "legacy")
+    pub output_format: Option<OutputFormat>,
+    /// Extract fields selected on an interface that only its implementations declare, such as
+    /// `breed` in a fragment on `Animal`, as `Dog.breed` for each implementation declaring them,
+    /// instead of as `Animal.breed`. Use `error_on_unknown_fields` to reject them instead
+    /// (default: false)
+    pub attribute_interface_fields_to_members: Option<bool>,
+    /// Coordinates to leave out of extraction output, matched exactly and without the
+    /// coordinate prefix, such as `Root.__typename` (default: none)
+    pub exclude_coordinates: Option<Vec<String>>,
+    /// Also skip the selections of excluded fields, so nothing beneath them is extracted
+    /// (default: false)
+    pub skip_children_of_excluded: Option<bool>,
+    /// Return extracted coordinates sorted, rather than in no particular order (default: false)
+    pub sorted: Option<bool>,
+    /// Schema type names mapped to new names to report them under, such as `Human` to `Person`.
+    /// Documents can select the type by either name (default: none)
+    pub type_renames: Option<HashMap<String, String>>,
+    /// Skip fields, fragment spreads and inline fragments whose `@skip` or `@include` condition
+    /// is a literal that excludes them, such as `@include(if: false)` (default: false)
+    pub evaluate_static_conditions: Option<bool>,
+    /// Also extract the fields named by the `@requires` and `@provides` field sets of selected
+    /// fields in a subgraph schema, such as `Product.weight` for
+    /// `shippingEstimate: Int @requires(fields: "weight")` (default: false)
+    pub expand_federation_field_sets: Option<bool>,
+    /// Extract fields selected on an object type that its interfaces declare under each declaring
+    /// interface, such as `Animal.name` for `name` on `Dog`, instead of the object type
+    /// (default: false)
+    pub collapse_to_interface: Option<bool>,
+    /// Which types `__typename` is extracted under when selected on an interface or union
+    /// (default: "abstract")
+    pub typename_attribution: Option<TypenameAttribution>,
+    /// How to order extracted coordinates. Can't be combined with `sorted`, which is the same as
+    /// `"alphabetical"` (default: no particular order)
+    pub order: Option<CoordinateOrder>,
+    /// Return an error naming the types of fragment type conditions that the schema doesn't
+    /// define. By default their selections are extracted under the unknown type (default: false)
+    pub error_on_unknown_type_conditions: Option<bool>,
+    /// Lowercase the type and field names in extracted and listed coordinates, such as
+    /// `root.animalowner`, for case-insensitive aggregation keys. Directive coordinates, the
+    /// separator and the coordinate prefix keep their case. Types are still filtered, ordered and
+    /// looked up by their original names, and `has_coordinate` accepts normalized coordinates
+    /// (default: false)
+    pub normalize_case: Option<bool>,
+    /// Most persisted queries `register_and_extract` keeps, evicting the least recently used one
+    /// to make room for another (default: 1000)
+    pub persisted_query_capacity: Option<u32>,
+}
 
-        // Look up the type in the type map
-        if let Some(type_info) = self.type_map.get(type_name) {
-            // Check if the field exists on this type
-            Ok(type_info.fields.contains_key(field_name))
-        } else {
-            // Type doesn't exist
-            Ok(false)
+impl ExtractionOptions {
+    fn separator(&self) -> &str {
+        self.separator.as_deref().unwrap_or(".")
+    }
+
+    /// Rejects invalid option values, and applies the output format
+    fn validate(&mut self) -> Result<()> {
+        if self.output_format == Some(OutputFormat::Rfc) {
+            self.use_rfc_format()?;
+        }
+        if self.separator().is_empty() {
+            return Err(Error::from_reason("Coordinate separator must not be empty"));
         }
+        if self.persisted_query_capacity == Some(0) {
+            return Err(Error::from_reason(
+                "Persisted query capacity must be at least 1",
+            ));
+        }
+        if self.order.is_some() && self.sorted.is_some() {
+            return Err(Error::from_reason(
+                "order and sorted can't both be set. Use order: \"alphabetical\" instead of sorted",
+            ));
+        }
+        Ok(())
     }
-}
 
-fn build_type_map(schema_doc: &schema::Document<'_, String>) -> HashMap<String, TypeInfo> {
-    let mut type_map = HashMap::new();
-    let mut query_type = "Query".to_string();
-    let mut mutation_type = "Mutation".to_string();
+    /// Rejects root aliases that reuse the name of a schema type or a standard root alias, which
+    /// they would shadow, and those resolving to a type the schema doesn't define, including
+    /// root operation types named by the schema definition
+    fn validate_root_aliases(&self, schema_doc: &schema::Document<'_, String>) -> Result<()> {
+        let type_names: HashSet<&str> = schema_doc
+            .definitions
+            .iter()
+            .filter_map(definition_type_name)
+            .collect();
+        let check_defined = |alias: &str, actual_name: &str| {
+            if type_names.contains(actual_name) {
+                return Ok(());
+            }
+            Err(Error::from_reason(format!(
+                "Root alias {} resolves to {}, which the schema doesn't define",
+                alias, actual_name
+            )))
+        };
 
-    // Find the schema definition to get root operation types
-    for definition in &schema_doc.definitions {
-        if let schema::Definition::SchemaDefinition(schema_def) = definition {
-            if let Some(type_def) = &schema_def.query {
-                query_type = type_def.to_string();
+        for (standard_name, actual_name) in root_type_names(schema_doc) {
+            if standard_name != actual_name {
+                check_defined(standard_name, &actual_name)?;
             }
-            if let Some(type_def) = &schema_def.mutation {
-                mutation_type = type_def.to_string();
+        }
+
+        let Some(root_aliases) = &self.root_aliases else {
+            return Ok(());
+        };
+        let mut aliases: Vec<(&String, &String)> = root_aliases.iter().collect();
+        aliases.sort();
+        for (alias, actual_name) in aliases {
+            if type_names.contains(alias.as_str())
+                || ["Query", "Mutation", "Subscription"].contains(&alias.as_str())
+            {
+                return Err(Error::from_reason(format!(
+                    "Root alias {} can't reuse the name of a schema type or root alias",
+                    alias
+                )));
             }
+            check_defined(alias, actual_name)?;
         }
+        Ok(())
     }
 
-    // Build the type map
-    for definition in &schema_doc.definitions {
-        match definition {
-            schema::Definition::TypeDefinition(type_def) => {
-                process_type_definition(type_def, &mut type_map);
-            }
-            schema::Definition::TypeExtension(type_ext) => {
-                process_type_extension(type_ext, &mut type_map);
+    /// How extracted coordinates are ordered, if at all. `sorted` orders them alphabetically.
+    fn order(&self) -> Option<CoordinateOrder> {
+        self.order.or(self
+            .sorted
+            .unwrap_or(false)
+            .then_some(CoordinateOrder::Alphabetical))
+    }
+
+    /// Most persisted queries kept by `register_and_extract`
+    fn persisted_query_capacity(&self) -> usize {
+        self.persisted_query_capacity.unwrap_or(1000) as usize
+    }
+
+    /// Rejects documents with more operations than `max_operations`
+    fn check_operation_count(&self, query_doc: &query::Document<String>) -> Result<()> {
+        if let Some(max_operations) = self.max_operations {
+            let operation_count = query_doc
+                .definitions
+                .iter()
+                .filter(|definition| matches!(definition, query::Definition::Operation(_)))
+                .count();
+            if operation_count > max_operations as usize {
+                return Err(Error::from_reason(format!(
+                    "Document contains {} operations, exceeding the limit of {}",
+                    operation_count, max_operations
+                )));
             }
-            _ => {}
         }
+        Ok(())
     }
 
-    // Create aliases for Query and Mutation to map to the actual schema types
-    create_root_type_alias(&mut type_map, "Query", &query_type);
-    create_root_type_alias(&mut type_map, "Mutation", &mutation_type);
-
-    type_map
-}
+    /// Enables every coordinate form from the schema coordinate spec that hasn't been turned off,
+    /// and rejects options that would produce coordinates outside its grammar
+    fn use_rfc_format(&mut self) -> Result<()> {
+        if self.separator() != "." || self.coordinate_prefix.is_some() {
+            return Err(Error::from_reason(
+                "The \"rfc\" output format can't be combined with a custom separator or prefix",
+            ));
+        }
 
-/// Creates an alias for a root operation type if it differs from the standard name
-fn create_root_type_alias(
-    type_map: &mut HashMap<String, TypeInfo>,
-    standard_name: &str,
-    actual_name: &str,
-) {
-    if standard_name != actual_name {
-        let fields = type_map
-            .get(actual_name)
-            .map(|t| t.fields.clone())
-            .unwrap_or_default();
-        type_map.insert(
-            standard_name.to_string(),
-            TypeInfo {
-                name: actual_name.to_string(),
-                fields,
-            },
-        );
+        for include in [
+            &mut self.include_arguments,
+            &mut self.include_directives,
+            &mut self.include_directive_arguments,
+            &mut self.include_enum_values,
+            &mut self.include_input_fields,
+        ] {
+            include.get_or_insert(true);
+        }
+        Ok(())
     }
-}
 
-fn process_type_definition(
-    type_def: &schema::TypeDefinition<'_, String>,
-    type_map: &mut HashMap<String, TypeInfo>,
-) {
-    match type_def {
-        schema::TypeDefinition::Object(obj) => {
-            let fields = extract_fields_from_definition(&obj.fields);
-            type_map.insert(
-                obj.name.to_string(),
-                TypeInfo {
-                    name: obj.name.to_string(),
-                    fields,
-                },
-            );
+    /// Prepends the configured coordinate prefix, if any
+    fn prefixed(&self, coordinate: String) -> String {
+        match &self.coordinate_prefix {
+            Some(prefix) => format!(
+                "{}{}{}",
+                prefix,
+                self.coordinate_prefix_separator.as_deref().unwrap_or(":"),
+                coordinate
+            ),
+            None => coordinate,
         }
-        schema::TypeDefinition::Interface(iface) => {
-            let fields = extract_fields_from_definition(&iface.fields);
-            type_map.insert(
-                iface.name.to_string(),
-                TypeInfo {
-                    name: iface.name.to_string(),
-                    fields,
-                },
-            );
+    }
+
+    /// Lowercases the parts of a prefixed coordinate around the separator when `normalize_case`
+    /// is set, as the last step before returning it. Lookups use the original case. The prefix
+    /// and directive coordinates are left as they are.
+    fn case_normalized(&self, coordinate: String) -> String {
+        if !self.normalize_case.unwrap_or(false) {
+            return coordinate;
         }
-        schema::TypeDefinition::InputObject(input) => {
-            type_map.insert(
-                input.name.to_string(),
-                TypeInfo {
-                    name: input.name.to_string(),
-                    fields: HashMap::new(),
-                },
-            );
+        let prefix_len = self.coordinate_prefix.as_ref().map_or(0, |prefix| {
+            prefix.len()
+                + self
+                    .coordinate_prefix_separator
+                    .as_deref()
+                    .unwrap_or(":")
+                    .len()
+        });
+        let Some((prefix, unprefixed)) = coordinate.split_at_checked(prefix_len) else {
+            return coordinate;
+        };
+        if unprefixed.starts_with('@') {
+            return coordinate;
         }
-        _ => {}
+        let normalized = unprefixed
+            .split(self.separator())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+            .join(self.separator());
+        format!("{}{}", prefix, normalized)
     }
-}
 
-/// Extracts field names and their types from a list of field definitions
-fn extract_fields_from_definition(
-    fields: &[schema::Field<String>],
-) -> HashMap<String, String> {
-    fields
-        .iter()
-        .map(|field| (field.name.to_string(), get_field_type(&field.field_type)))
-        .collect()
-}
+    /// Whether every input field of a variable's input type should be extracted
+    fn all_input_fields_from_variables(&self) -> bool {
+        self.input_fields_from_variables == Some(VariableInputFields::All)
+    }
 
-fn process_type_extension(
-    type_ext: &schema::TypeExtension<'_, String>,
-    type_map: &mut HashMap<String, TypeInfo>,
-) {
-    match type_ext {
-        schema::TypeExtension::Object(obj) => {
-            let entry = type_map
-                .entry(obj.name.to_string())
-                .or_insert_with(|| TypeInfo {
-                    name: obj.name.to_string(),
-                    fields: HashMap::new(),
-                });
-            for field in &obj.fields {
-                entry
-                    .fields
-                    .insert(field.name.to_string(), get_field_type(&field.field_type));
-            }
-        }
-        _ => {}
+    /// Whether field coordinates at `depth` fall within the configured depth window
+    fn is_within_depth(&self, depth: u32) -> bool {
+        self.min_depth.is_none_or(|min_depth| depth >= min_depth)
+            && self.max_depth.is_none_or(|max_depth| depth <= max_depth)
     }
-}
 
-fn get_field_type(field_type: &schema::Type<'_, String>) -> String {
-    match field_type {
-        schema::Type::NamedType(name) => name.to_string(),
-        schema::Type::NonNullType(inner) => get_field_type(inner),
-        schema::Type::ListType(inner) => get_field_type(inner),
+    /// Formats a `Type.field` coordinate using the configured separator
+    fn field_coordinate(&self, type_name: &str, field_name: &str) -> String {
+        format!("{}{}{}", type_name, self.separator(), field_name)
+    }
+
+    /// Formats a `Type.field(arg:)` coordinate using the configured separator
+    fn argument_coordinate(
+        &self,
+        type_name: &str,
+        field_name: &str,
+        argument_name: &str,
+    ) -> String {
+        format!(
+            "{}{}{}({}:)",
+            type_name,
+            self.separator(),
+            field_name,
+            argument_name
+        )
     }
 }
 
-fn extract_from_operation(
-    operation: &query::OperationDefinition<String>,
-    type_map: &Arc<HashMap<String, TypeInfo>>,
-    query_doc: &query::Document<String>,
-    coordinates: &mut HashSet<String>,
-) -> Result<()> {
-    let empty_variables = Vec::new();
+#[napi]
+impl ParsedSchema {
+    /// Create a new ParsedSchema from a schema string
+    #[napi(constructor)]
+    pub fn new(schema_text: String) -> Result<Self> {
+        Self::with_options(schema_text, ExtractionOptions::default())
+    }
 
-    let (root_type, selection_set, variable_defs) = match operation {
-        query::OperationDefinition::Query(q) => {
-            ("Query", &q.selection_set, &q.variable_definitions)
-        }
-        query::OperationDefinition::Mutation(m) => {
-            ("Mutation", &m.selection_set, &m.variable_definitions)
-        }
-        query::OperationDefinition::Subscription(_) => {
-            return Err(Error::from_reason(
-                "Schema is not configured to execute subscription",
-            ));
+    /// Create a new ParsedSchema from a schema string, with options for extraction
+    #[napi(factory)]
+    pub fn with_options(schema_text: String, mut options: ExtractionOptions) -> Result<Self> {
+        options.validate()?;
+
+        // Parse the schema
+        let schema_doc = schema::parse_schema::<String>(&schema_text)
+            .map_err(|e| Error::from_reason(format!("Failed to parse schema: {}", e)))?
+            .into_static();
+
+        Self::from_schema_doc(schema_doc, options)
+    }
+
+    /// Create a new ParsedSchema from a schema split across several strings, such as one per
+    /// file. Types defined in one string can be extended in another.
+    #[napi(factory)]
+    pub fn from_schemas(
+        schema_texts: Vec<String>,
+        options: Option<ExtractionOptions>,
+    ) -> Result<Self> {
+        let mut options = options.unwrap_or_default();
+        options.validate()?;
+
+        let mut schema_doc = schema::Document {
+            definitions: Vec::new(),
+        };
+        for (index, schema_text) in schema_texts.iter().enumerate() {
+            let part = schema::parse_schema::<String>(schema_text).map_err(|e| {
+                Error::from_reason(format!("Failed to parse schema {}: {}", index, e))
+            })?;
+            schema_doc
+                .definitions
+                .extend(part.into_static().definitions);
         }
-        query::OperationDefinition::SelectionSet(ss) => ("Query", ss, &empty_variables),
-    };
 
-    // Extract input types from variable definitions
-    for var_def in variable_defs {
-        extract_input_types(&var_def.var_type, type_map, coordinates);
+        Self::from_schema_doc(schema_doc, options)
     }
 
-    // Extract coordinates from selection set
-    extract_from_selection_set(
-        &selection_set.items,
-        root_type,
-        type_map,
-        query_doc,
-        coordinates,
-    );
+    /// Create a new ParsedSchema from a gzipped schema string, with options for extraction. Only
+    /// available in builds with the `flate2` feature. Throws for schemas that decompress to more
+    /// than 64 MiB.
+    #[cfg(feature = "flate2")]
+    #[napi(factory)]
+    pub fn from_gzip(schema_gzip: Buffer, options: Option<ExtractionOptions>) -> Result<Self> {
+        let schema_text = gunzip(&schema_gzip, "schema", MAX_DECOMPRESSED_SIZE)?;
+        Self::with_options(schema_text, options.unwrap_or_default())
+    }
 
-    Ok(())
-}
+    /// Extract schema coordinates from a gzipped document. Only available in builds with the
+    /// `flate2` feature. Throws for documents that decompress to more than 64 MiB.
+    #[cfg(feature = "flate2")]
+    #[napi]
+    pub fn extract_schema_coordinates_gzip(&self, document_gzip: Buffer) -> Result<Vec<String>> {
+        self.extract_schema_coordinates(gunzip(&document_gzip, "document", MAX_DECOMPRESSED_SIZE)?)
+    }
 
-fn extract_input_types(
-    var_type: &query::Type<String>,
-    type_map: &Arc<HashMap<String, TypeInfo>>,
-    coordinates: &mut HashSet<String>,
-) {
-    match var_type {
-        query::Type::NamedType(name) => {
-            // Only add if it's an input type (exists in type map and not a scalar)
-            if type_map.contains_key(name) && !is_scalar(name) {
-                coordinates.insert(name.to_string());
-            }
-        }
-        query::Type::NonNullType(inner) => {
-            extract_input_types(inner, type_map, coordinates);
-        }
-        query::Type::ListType(inner) => {
-            extract_input_types(inner, type_map, coordinates);
+    /// Extract schema coordinates from a document using this parsed schema
+    #[napi]
+    pub fn extract_schema_coordinates(&self, document_text: String) -> Result<Vec<String>> {
+        if self.data.options.order() == Some(CoordinateOrder::Document) {
+            let mut coordinates: Vec<String> = Vec::new();
+            self.extract_into_sink(&document_text, &mut coordinates)?;
+
+            let mut seen = HashSet::new();
+            coordinates.retain(|coordinate| seen.insert(coordinate.clone()));
+            return Ok(coordinates);
         }
-    }
-}
 
-/// Built-in GraphQL scalar types that should not be included in schema coordinates
-const BUILTIN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+        let mut coordinates = HashSet::new();
+        self.collect_coordinates(&document_text, &mut coordinates)?;
 
-fn is_scalar(type_name: &str) -> bool {
-    BUILTIN_SCALARS.contains(&type_name)
-}
+        let result: Vec<String> = self.output(coordinates);
 
-fn extract_from_selection_set(
-    selection_set: &[query::Selection<String>],
-    parent_type: &str,
-    type_map: &Arc<HashMap<String, TypeInfo>>,
-    query_doc: &query::Document<String>,
-    coordinates: &mut HashSet<String>,
-) {
-    for selection in selection_set {
-        match selection {
-            query::Selection::Field(field) => {
-                // Resolve the canonical type name from the schema (e.g., Query -> Root for custom root types)
-                let canonical_parent_type = type_map
-                    .get(parent_type)
-                    .map(|info| info.name.as_str())
-                    .unwrap_or(parent_type);
-
-                // Add the coordinate using the canonical type name
-                let coordinate = format!("{}.{}", canonical_parent_type, field.name);
-                coordinates.insert(coordinate);
-
-                // If field has selections, traverse them with the field's type
-                if !field.selection_set.items.is_empty() {
-                    // Look up the field's return type from the schema
-                    if let Some(type_info) = type_map.get(parent_type) {
-                        if let Some(field_type_name) = type_info.fields.get(&field.name) {
-                            extract_from_selection_set(
-                                &field.selection_set.items,
-                                field_type_name,
-                                type_map,
-                                query_doc,
-                                coordinates,
-                            );
-                        }
-                        // If field doesn't exist in schema, skip traversing its children
-                        // to avoid processing invalid nested selections
-                    }
-                }
-            }
-            query::Selection::FragmentSpread(spread) => {
-                // Find the fragment definition
-                for definition in &query_doc.definitions {
-                    if let query::Definition::Fragment(fragment) = definition {
-                        if fragment.name == spread.fragment_name {
-                            let fragment_type = match &fragment.type_condition {
-                                query::TypeCondition::On(type_name) => type_name.as_str(),
-                            };
-                            extract_from_selection_set(
-                                &fragment.selection_set.items,
-                                fragment_type,
-                                type_map,
-                                query_doc,
-                                coordinates,
-                            );
-                        }
-                    }
-                }
-            }
-            query::Selection::InlineFragment(inline) => {
-                let fragment_type = match &inline.type_condition {
-                    Some(query::TypeCondition::On(type_name)) => type_name.as_str(),
-                    None => parent_type,
-                };
-                extract_from_selection_set(
-                    &inline.selection_set.items,
-                    fragment_type,
-                    type_map,
-                    query_doc,
-                    coordinates,
-                );
-            }
+        Ok(result)
+    }
+
+    /// Extract schema coordinates from an automatic persisted query, caching the result under
+    /// its hash. If the hash is already registered, the cached coordinates are returned without
+    /// parsing the document. The hash isn't checked against the document. At most
+    /// `persistedQueryCapacity` queries are cached, evicting the least recently used one.
+    #[napi]
+    pub fn register_and_extract(&self, hash: String, document_text: String) -> Result<Vec<String>> {
+        if let Some(coordinates) = self.extract_by_hash(hash.clone()) {
+            return Ok(coordinates);
         }
+
+        let coordinates = self.extract_schema_coordinates(document_text)?;
+        self.persisted_queries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                hash,
+                coordinates.clone(),
+                self.data.options.persisted_query_capacity(),
+            );
+        Ok(coordinates)
     }
-}
 
-#[derive(Debug, Clone)]
-struct TypeInfo {
-    name: String,
-    fields: HashMap<String, String>,
-}
+    /// Look up the schema coordinates of a persisted query registered with `registerAndExtract`,
+    /// marking it as recently used. Returns null for hashes that were never registered or have
+    /// been evicted.
+    #[napi]
+    pub fn extract_by_hash(&self, hash: String) -> Option<Vec<String>> {
+        self.persisted_queries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(&hash)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::LazyLock;
+    /// Find the root field coordinate of a document's subscription, such as
+    /// `Subscription.messageAdded`, for keying subscription channels. Returns null when the
+    /// document has no subscription, and throws if the subscription selects several root fields.
+    #[napi]
+    pub fn subscription_entry_point(&self, document_text: String) -> Result<Option<String>> {
+        let query_doc = parse_document(&document_text)?;
 
-    const PETS_SCHEMA: &str = include_str!("../testing/pets.schema.graphql");
+        let mut coordinates = HashSet::new();
+        Extraction::new(self, &query_doc, &mut coordinates).subscription_entry_point()
+    }
 
-    static PARSED_SCHEMA: LazyLock<ParsedSchema> = LazyLock::new(|| {
-        ParsedSchema::new(PETS_SCHEMA.to_string()).expect("Should parse schema")
-    });
+    /// Whether a document only reads: every operation in it is a query, with no mutations or
+    /// subscriptions. Fragments alone don't write, so a document of only fragments is read-only.
+    #[napi]
+    pub fn is_read_only(&self, document_text: String) -> Result<bool> {
+        let query_doc = parse_document(&document_text)?;
 
-    mod extract_schema_coordinates_tests {
-        use super::*;
+        Ok(query_doc.definitions.iter().all(|definition| {
+            matches!(
+                definition,
+                query::Definition::Fragment(_)
+                    | query::Definition::Operation(
+                        query::OperationDefinition::Query(_)
+                            | query::OperationDefinition::SelectionSet(_)
+                    )
+            )
+        }))
+    }
 
-        fn extract_and_sort(document: &str) -> Vec<String> {
-            let mut result = PARSED_SCHEMA
+    /// Whether a document only selects introspection meta-fields, such as `__schema` and
+    /// `__type`, at the root of every operation, looking through fragments. Documents without
+    /// operations aren't introspection-only.
+    #[napi]
+    pub fn is_introspection_only(&self, document_text: String) -> Result<bool> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut operations = query_doc
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                query::Definition::Operation(operation) => Some(operation),
+                query::Definition::Fragment(_) => None,
+            })
+            .peekable();
+        if operations.peek().is_none() {
+            return Ok(false);
+        }
+
+        Ok(operations.all(|operation| {
+            let selection_set = match operation {
+                query::OperationDefinition::Query(q) => &q.selection_set,
+                query::OperationDefinition::Mutation(m) => &m.selection_set,
+                query::OperationDefinition::Subscription(s) => &s.selection_set,
+                query::OperationDefinition::SelectionSet(ss) => ss,
+            };
+            selects_only_meta_fields(&selection_set.items, &query_doc, &mut HashSet::new())
+        }))
+    }
+
+    /// Extract schema coordinates from a document, keeping only those of the given types, such as
+    /// `Human.name` for `Human`. Root type aliases like `Query` match their actual type.
+    /// Directive coordinates are never kept.
+    #[napi]
+    pub fn extract_for_types(
+        &self,
+        document_text: String,
+        types: Vec<String>,
+    ) -> Result<Vec<String>> {
+        let allowed_types: HashSet<&str> = types
+            .iter()
+            .map(|type_name| {
+                self.data
+                    .type_map
+                    .get(type_name)
+                    .map_or(type_name.as_str(), |info| info.name.as_str())
+            })
+            .collect();
+
+        let mut coordinates = HashSet::new();
+        self.collect_coordinates(&document_text, &mut coordinates)?;
+        coordinates.retain(|coordinate| {
+            self.coordinate_type_name(coordinate)
+                .is_some_and(|type_name| allowed_types.contains(type_name))
+        });
+
+        Ok(self.output(coordinates))
+    }
+
+    /// List which of the given changed coordinates a document uses, such as to check whether a
+    /// schema change affects it. The changed coordinates are given without `coordinate_prefix`,
+    /// and may have their case normalized when `normalize_case` is set.
+    #[napi]
+    pub fn coordinates_intersecting(
+        &self,
+        document_text: String,
+        changed_coordinates: Vec<String>,
+    ) -> Result<Vec<String>> {
+        let options = &self.data.options;
+        let changed_coordinates: HashSet<String> = changed_coordinates
+            .into_iter()
+            .map(|coordinate| options.prefixed(coordinate))
+            .collect();
+
+        let mut coordinates = HashSet::new();
+        self.collect_coordinates(&document_text, &mut coordinates)?;
+        coordinates.retain(|coordinate| {
+            changed_coordinates.contains(coordinate)
+                || (options.normalize_case.unwrap_or(false)
+                    && changed_coordinates.contains(&options.case_normalized(coordinate.clone())))
+        });
+
+        Ok(self.output(coordinates))
+    }
+
+    /// Extract the union of the schema coordinates used by several documents. Each document is
+    /// parsed on its own, so fragment spreads only resolve against their own document's fragments.
+    #[napi]
+    pub fn extract_schema_coordinates_union(
+        &self,
+        document_texts: Vec<String>,
+    ) -> Result<Vec<String>> {
+        let mut coordinates = HashSet::new();
+
+        for (index, document_text) in document_texts.iter().enumerate() {
+            self.collect_coordinates(document_text, &mut coordinates)
+                .map_err(|e| Error::from_reason(format!("Document {}: {}", index, e.reason)))?;
+        }
+
+        Ok(self.output(coordinates))
+    }
+
+    /// Split a blob of concatenated documents on a separator, such as `"\0"`, and extract the
+    /// schema coordinates of each part separately. A part that fails to parse or extract gets
+    /// its error instead of failing the others. A trailing separator doesn't start another part.
+    #[napi]
+    pub fn extract_schema_coordinates_multi(
+        &self,
+        blob: String,
+        separator: String,
+    ) -> Result<Vec<PartExtraction>> {
+        if separator.is_empty() {
+            return Err(Error::from_reason("Document separator must not be empty"));
+        }
+
+        let parts = blob
+            .split_terminator(separator.as_str())
+            .map(|part| {
+                let mut coordinates = HashSet::new();
+                match self.collect_coordinates(part, &mut coordinates) {
+                    Ok(()) => PartExtraction {
+                        coordinates: self.output(coordinates),
+                        error: None,
+                    },
+                    Err(e) => PartExtraction {
+                        coordinates: Vec::new(),
+                        error: Some(e.reason),
+                    },
+                }
+            })
+            .collect();
+        Ok(parts)
+    }
+
+    /// Extract schema coordinates into a reusable buffer, replacing its previous contents.
+    /// The buffer is left empty if the document fails to parse.
+    #[napi]
+    pub fn extract_schema_coordinates_into(
+        &self,
+        document_text: String,
+        buffer: &mut CoordinateBuffer,
+    ) -> Result<()> {
+        buffer.coordinates.clear();
+
+        let result = self.extract_into_sink(&document_text, &mut buffer.coordinates);
+        if result.is_err() {
+            buffer.coordinates.clear();
+        }
+
+        result
+    }
+
+    /// Extract schema coordinates from a document whose fragment spreads can also refer to the
+    /// fragments of a registry. Fragments defined in the document take precedence over registry
+    /// fragments of the same name.
+    #[napi]
+    pub fn extract_with_registry(
+        &self,
+        document_text: String,
+        registry: &FragmentRegistry,
+    ) -> Result<Vec<String>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.fragment_registry = Some(registry);
+        extraction.extract_document()?;
+
+        Ok(self.output(coordinates))
+    }
+
+    /// Extract schema coordinates from a document, using the JSON variables it's executed with
+    /// to also extract the `Input.field` coordinates of the input object fields they set.
+    /// Only fields present in the variables are extracted, recursing into nested objects and
+    /// lists. Throws for variables nested more than 128 levels deep.
+    #[napi]
+    pub fn extract_with_variables(
+        &self,
+        document_text: String,
+        variables_json: String,
+    ) -> Result<Vec<String>> {
+        let query::Value::Object(variable_values) =
+            json::parse_json(&variables_json).map_err(|error| match error {
+                json::JsonError::Syntax(offset) => {
+                    Error::from_reason(format!("Failed to parse variables at byte {}", offset))
+                }
+                json::JsonError::TooDeep(offset) => Error::from_reason(format!(
+                    "Variables are nested more than {} levels deep at byte {}",
+                    json::MAX_NESTING,
+                    offset
+                )),
+            })?
+        else {
+            return Err(Error::from_reason("Variables must be a JSON object"));
+        };
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.variable_values = Some(&variable_values);
+        extraction.extract_document()?;
+
+        Ok(self.output(coordinates))
+    }
+
+    /// Create an `IncrementalExtractor` for extracting successive versions of a document, such as
+    /// one being edited. Throws if depth windows or `onlyUsedVariables` are set, since they make
+    /// a fragment's coordinates depend on where it's spread.
+    #[napi]
+    pub fn incremental_extractor(&self) -> Result<IncrementalExtractor> {
+        if self.data.options.min_depth.is_some()
+            || self.data.options.max_depth.is_some()
+            || self.data.options.only_used_variables.unwrap_or(false)
+        {
+            return Err(Error::from_reason(
+                "Incremental extraction doesn't support the minDepth, maxDepth or \
+                 onlyUsedVariables options",
+            ));
+        }
+
+        Ok(IncrementalExtractor {
+            schema: self.extractor().schema,
+            definitions: HashMap::new(),
+            recomputed: 0,
+        })
+    }
+
+    /// Create an `Extractor` for extracting one document at a time, reusing its result set
+    /// between calls
+    #[napi]
+    pub fn extractor(&self) -> Extractor {
+        Extractor {
+            schema: ParsedSchema {
+                data: Arc::clone(&self.data),
+                persisted_queries: Mutex::default(),
+            },
+            coordinates: HashSet::new(),
+        }
+    }
+
+    /// Extract schema coordinates from a document, along with stats about the work done.
+    /// Useful for finding pathological documents.
+    #[napi]
+    pub fn extract_with_stats(&self, document_text: String) -> Result<ExtractionWithStats> {
+        let start = Instant::now();
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.stats = Some(ExtractionStats::default());
+        extraction.extract_document()?;
+
+        let mut stats = extraction.stats.take().unwrap_or_default();
+        stats.duration_micros = start.elapsed().as_micros().try_into().unwrap_or(u32::MAX);
+
+        Ok(ExtractionWithStats {
+            coordinates: self.output(coordinates),
+            stats,
+        })
+    }
+
+    /// Extract schema coordinates from a document, along with how many selections were visited,
+    /// the widest selection set, and how many fragment spreads were expanded. Useful for tuning
+    /// query cost limits.
+    #[napi]
+    pub fn extract_security_report(&self, document_text: String) -> Result<SecurityReport> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.stats = Some(ExtractionStats::default());
+        extraction.extract_document()?;
+
+        let stats = extraction.stats.take().unwrap_or_default();
+        Ok(SecurityReport {
+            coordinates: self.output(coordinates),
+            nodes_visited: stats.selections_visited,
+            max_fanout: stats.max_fanout,
+            fragment_expansions: stats.fragments_expanded,
+        })
+    }
+
+    /// List the variables that a document's operations reference in arguments or directives,
+    /// with their declared types, in declaration order. Declared but unused variables are omitted.
+    #[napi]
+    pub fn extract_used_variables(&self, document_text: String) -> Result<Vec<UsedVariable>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.variables = Some(Vec::new());
+        extraction.extract_document()?;
+
+        Ok(extraction.variables.take().unwrap_or_default())
+    }
+
+    /// Extract schema coordinates per operation, separating those produced by the operation's
+    /// own selections and variables from those produced by each fragment it spreads. Coordinates
+    /// from nested fragments are attributed to the innermost fragment.
+    #[napi]
+    pub fn extract_attributed(&self, document_text: String) -> Result<Vec<AttributedCoordinates>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.attributed = Some(Vec::new());
+        extraction.extract_document()?;
+
+        let mut attributed = extraction.attributed.take().unwrap_or_default();
+        for operation in &mut attributed {
+            for coordinates in
+                std::iter::once(&mut operation.direct).chain(operation.fragments.values_mut())
+            {
+                for coordinate in coordinates.iter_mut() {
+                    *coordinate = self
+                        .data
+                        .options
+                        .case_normalized(std::mem::take(coordinate));
+                }
+                coordinates.sort_unstable();
+                coordinates.dedup();
+            }
+        }
+        Ok(attributed)
+    }
+
+    /// Map each schema coordinate a document uses to the sorted names of the operations that use
+    /// it, directly or through fragments. Anonymous operations are named `<anonymous>`.
+    #[napi]
+    pub fn extract_coordinate_to_operations(
+        &self,
+        document_text: String,
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut operations_by_coordinate: HashMap<String, Vec<String>> = HashMap::new();
+        for operation in self.extract_attributed(document_text)? {
+            let operation_name = operation
+                .operation
+                .unwrap_or_else(|| ANONYMOUS_OPERATION.to_string());
+            let coordinates = operation
+                .direct
+                .into_iter()
+                .chain(operation.fragments.into_values().flatten());
+            for coordinate in coordinates {
+                operations_by_coordinate
+                    .entry(coordinate)
+                    .or_default()
+                    .push(operation_name.clone());
+            }
+        }
+
+        for operation_names in operations_by_coordinate.values_mut() {
+            operation_names.sort_unstable();
+            operation_names.dedup();
+        }
+        Ok(operations_by_coordinate)
+    }
+
+    /// List the distinct `@directive` coordinates applied anywhere in a document: on operations,
+    /// fragments, fields, fragment spreads and inline fragments. They're prefixed and ordered
+    /// like extracted coordinates, and sorted when no order is configured.
+    #[napi]
+    pub fn extract_directives(&self, document_text: String) -> Result<Vec<String>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut directive_names = Vec::new();
+        for definition in &query_doc.definitions {
+            let (directives, selection_set) = match definition {
+                query::Definition::Operation(operation) => match operation {
+                    query::OperationDefinition::Query(q) => (&q.directives[..], &q.selection_set),
+                    query::OperationDefinition::Mutation(m) => {
+                        (&m.directives[..], &m.selection_set)
+                    }
+                    query::OperationDefinition::Subscription(s) => {
+                        (&s.directives[..], &s.selection_set)
+                    }
+                    query::OperationDefinition::SelectionSet(ss) => (&[][..], ss),
+                },
+                query::Definition::Fragment(fragment) => {
+                    (&fragment.directives[..], &fragment.selection_set)
+                }
+            };
+            collect_directive_names(directives, &mut directive_names);
+            collect_selection_directive_names(&selection_set.items, &mut directive_names);
+        }
+
+        let mut seen = HashSet::new();
+        let mut directives: Vec<String> = directive_names
+            .into_iter()
+            .filter(|name| seen.insert(*name))
+            .map(|name| self.data.options.prefixed(format!("@{}", name)))
+            .collect();
+        if self.data.options.order().is_none() {
+            directives.sort_unstable();
+        }
+        Ok(self.output(directives))
+    }
+
+    /// List the deprecated fields, arguments and input fields that a document uses, sorted by
+    /// coordinate. Input fields are found in object values passed as arguments.
+    #[napi]
+    pub fn extract_deprecated_usages(&self, document_text: String) -> Result<Vec<DeprecatedUsage>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.deprecated_usages = Some(Vec::new());
+        extraction.extract_document()?;
+
+        let mut usages = extraction.deprecated_usages.take().unwrap_or_default();
+        for usage in &mut usages {
+            usage.coordinate = self
+                .data
+                .options
+                .case_normalized(std::mem::take(&mut usage.coordinate));
+        }
+        usages.sort_by(|a, b| a.coordinate.cmp(&b.coordinate));
+        usages.dedup_by(|a, b| a.coordinate == b.coordinate);
+        Ok(usages)
+    }
+
+    /// Extract the coordinates a document uses whose type has the given directive applied in the
+    /// schema, such as `Ledger.balance` for `type Ledger @internal`. The directive name can be
+    /// given with or without its `@`.
+    #[napi]
+    pub fn extract_flagged_by_directive(
+        &self,
+        document_text: String,
+        directive_name: String,
+    ) -> Result<Vec<String>> {
+        let directive_name = directive_name.trim_start_matches('@');
+
+        let mut coordinates = HashSet::new();
+        self.collect_coordinates(&document_text, &mut coordinates)?;
+        coordinates.retain(|coordinate| {
+            self.coordinate_type_name(coordinate)
+                .and_then(|type_name| self.data.type_map.get(type_name))
+                .is_some_and(|type_info| type_info.directives.iter().any(|d| d == directive_name))
+        });
+
+        Ok(self.output(coordinates))
+    }
+
+    /// Extract the coordinates of the fields a document selects that return an interface or
+    /// union type, such as `Root.allSpecies` returning `[Animal]`
+    #[napi]
+    pub fn extract_abstract_returning_fields(&self, document_text: String) -> Result<Vec<String>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.abstract_returning_fields = Some(HashSet::new());
+        extraction.extract_document()?;
+
+        // Fields are recorded before exclusion and prefixing, so keep those that were extracted
+        let abstract_returning_fields = extraction
+            .abstract_returning_fields
+            .take()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|coordinate| self.data.options.prefixed(coordinate))
+            .filter(|coordinate| coordinates.contains(coordinate));
+        Ok(self.output(abstract_returning_fields))
+    }
+
+    /// Extract only the coordinates of the root fields a document's operations select, such as
+    /// `Root.animalOwner`, including those selected through fragments. Selections beneath root
+    /// fields aren't traversed.
+    #[napi]
+    pub fn extract_root_fields(&self, document_text: String) -> Result<Vec<String>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.root_fields = Some(HashSet::new());
+        extraction.extract_document()?;
+
+        let root_fields = extraction.root_fields.take().unwrap_or_default();
+        Ok(self.output(root_fields))
+    }
+
+    /// Extract schema coordinates from a document, sorted, each with the smallest selection depth
+    /// it's used at. Root fields are at depth 1, and fragments add no depth.
+    #[napi]
+    pub fn extract_with_depths(&self, document_text: String) -> Result<Vec<CoordinateDepth>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.min_depths = Some(HashMap::new());
+        extraction.extract_document()?;
+
+        let mut min_depths: BTreeMap<String, u32> = BTreeMap::new();
+        for (coordinate, min_depth) in extraction.min_depths.take().unwrap_or_default() {
+            let coordinate = self.data.options.case_normalized(coordinate);
+            let depth = min_depths.entry(coordinate).or_insert(min_depth);
+            *depth = (*depth).min(min_depth);
+        }
+        Ok(min_depths
+            .into_iter()
+            .map(|(coordinate, min_depth)| CoordinateDepth {
+                coordinate,
+                min_depth,
+            })
+            .collect())
+    }
+
+    /// Extract schema coordinates from a document, sorted, each with how the document uses it:
+    /// in a selection, as a variable's type, in an argument, or in a directive. A coordinate used
+    /// in several ways lists each of them.
+    #[napi]
+    pub fn extract_with_origins(&self, document_text: String) -> Result<Vec<CoordinateOrigins>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.origins = Some(HashMap::new());
+        extraction.extract_document()?;
+
+        let mut origins_by_coordinate: BTreeMap<String, BTreeSet<CoordinateOrigin>> =
+            BTreeMap::new();
+        for (coordinate, origins) in extraction.origins.take().unwrap_or_default() {
+            origins_by_coordinate
+                .entry(self.data.options.case_normalized(coordinate))
+                .or_default()
+                .extend(origins);
+        }
+        Ok(origins_by_coordinate
+            .into_iter()
+            .map(|(coordinate, origins)| CoordinateOrigins {
+                coordinate,
+                origins: origins.into_iter().collect(),
+            })
+            .collect())
+    }
+
+    /// Extract schema coordinates from a document, along with the deepest selection depth of any
+    /// field in it. Root fields are at depth 1, and fragments add no depth.
+    #[napi]
+    pub fn extract_with_max_depth(&self, document_text: String) -> Result<ExtractionWithMaxDepth> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.max_depth = Some(0);
+        extraction.extract_document()?;
+
+        let max_depth = extraction.max_depth.take().unwrap_or_default();
+        Ok(ExtractionWithMaxDepth {
+            coordinates: self.output(coordinates),
+            max_depth,
+        })
+    }
+
+    /// Extract schema coordinates from a document, along with the names of spread fragments it
+    /// doesn't define, sorted. Unresolved spreads are skipped rather than treated as errors.
+    #[napi]
+    pub fn extract_with_unresolved(
+        &self,
+        document_text: String,
+    ) -> Result<ExtractionWithUnresolved> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.unresolved_fragments = Some(BTreeSet::new());
+        extraction.extract_document()?;
+
+        let unresolved_fragments = extraction.unresolved_fragments.take().unwrap_or_default();
+        Ok(ExtractionWithUnresolved {
+            coordinates: self.output(coordinates),
+            unresolved_fragments: unresolved_fragments.into_iter().collect(),
+        })
+    }
+
+    /// Check a document against the schema while extracting its coordinates, collecting every
+    /// unknown field, argument, fragment and type instead of stopping at the first. Coordinates
+    /// are still extracted from the rest of the document. Issues are sorted by position.
+    #[napi]
+    pub fn validate_and_extract(&self, document_text: String) -> Result<ValidatedExtraction> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.issues = Some(Vec::new());
+        for definition in &query_doc.definitions {
+            match definition {
+                query::Definition::Fragment(fragment) => {
+                    let query::TypeCondition::On(type_name) = &fragment.type_condition;
+                    extraction.record_unknown_type(type_name, fragment.position);
+                }
+                query::Definition::Operation(operation) => {
+                    let variable_definitions = match operation {
+                        query::OperationDefinition::Query(q) => &q.variable_definitions,
+                        query::OperationDefinition::Mutation(m) => &m.variable_definitions,
+                        query::OperationDefinition::Subscription(s) => &s.variable_definitions,
+                        query::OperationDefinition::SelectionSet(_) => continue,
+                    };
+                    for var_def in variable_definitions {
+                        let type_name = get_field_type(&var_def.var_type);
+                        extraction.record_unknown_type(&type_name, var_def.position);
+                    }
+                }
+            }
+        }
+        extraction.extract_document()?;
+
+        let mut issues = extraction.issues.take().unwrap_or_default();
+        issues.sort_by(|a, b| {
+            (a.position.line, a.position.column, &a.message).cmp(&(
+                b.position.line,
+                b.position.column,
+                &b.message,
+            ))
+        });
+        issues.dedup();
+        Ok(ValidatedExtraction {
+            coordinates: self.output(coordinates),
+            issues,
+        })
+    }
+
+    /// Extract schema coordinates from a document, along with an ETag derived from them. Documents
+    /// that use the same coordinates share an ETag, however they're written.
+    #[napi]
+    pub fn extract_with_etag(&self, document_text: String) -> Result<ExtractionWithEtag> {
+        let mut coordinates = HashSet::new();
+        self.collect_coordinates(&document_text, &mut coordinates)?;
+
+        let coordinates = self.output(coordinates);
+        let mut sorted: Vec<&String> = coordinates.iter().collect();
+        sorted.sort_unstable();
+        let etag = coordinates_etag(&sorted);
+
+        Ok(ExtractionWithEtag { coordinates, etag })
+    }
+
+    /// Extract schema coordinates from a document on a worker thread, calling `callback` with
+    /// each coordinate the first time it's found instead of collecting them. The extraction
+    /// waits for each call to return before continuing. Once the callback throws, it isn't
+    /// called again and the returned promise rejects with its error.
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn extract_streaming(
+        &self,
+        document_text: String,
+        callback: ThreadsafeFunction<String, (), String, Status, false>,
+    ) -> AsyncTask<ExtractStreamingTask> {
+        AsyncTask::new(ExtractStreamingTask {
+            schema: ParsedSchema {
+                data: Arc::clone(&self.data),
+                persisted_queries: Mutex::default(),
+            },
+            document_text,
+            callback,
+        })
+    }
+
+    /// Extract schema coordinates from a document as CSV, with a `coordinate,count` header and a
+    /// row per coordinate, sorted by coordinate. The count is the number of times the document
+    /// uses the coordinate, counting a fragment's coordinates each time it's spread.
+    #[napi]
+    pub fn extract_counts_csv(&self, document_text: String) -> Result<String> {
+        let mut usages: Vec<String> = Vec::new();
+        self.extract_into_sink(&document_text, &mut usages)?;
+
+        let mut counts: BTreeMap<String, u32> = BTreeMap::new();
+        for coordinate in usages {
+            *counts.entry(coordinate).or_default() += 1;
+        }
+
+        let mut csv = String::from("coordinate,count\n");
+        for (coordinate, count) in counts {
+            csv.push_str(&csv_field(&coordinate));
+            csv.push(',');
+            csv.push_str(&count.to_string());
+            csv.push('\n');
+        }
+        Ok(csv)
+    }
+
+    /// Extract schema coordinates from a document, sorted, each with the byte spans of the field
+    /// and argument names in the document that use it
+    #[napi]
+    pub fn extract_with_spans(&self, document_text: String) -> Result<Vec<CoordinateSpans>> {
+        let query_doc = parse_document(&document_text)?;
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+        extraction.field_occurrences = Some(Vec::new());
+        extraction.extract_document()?;
+        let field_occurrences = extraction.field_occurrences.take().unwrap_or_default();
+
+        let mut spans_by_coordinate: HashMap<String, Vec<SourceSpan>> = coordinates
+            .into_iter()
+            .map(|coordinate| (coordinate, Vec::new()))
+            .collect();
+        let mut add_span = |coordinate: String, start: usize, end: usize| {
+            // Coordinates that were excluded have no entry
+            if let Some(spans) =
+                spans_by_coordinate.get_mut(&self.data.options.prefixed(coordinate))
+            {
+                spans.push(SourceSpan {
+                    start: start as u32,
+                    end: end as u32,
+                });
+            }
+        };
+        for occurrence in &field_occurrences {
+            let Some((start, end)) = spans::byte_offset(&document_text, occurrence.position)
+                .and_then(|offset| {
+                    spans::field_name_span(
+                        &document_text,
+                        offset,
+                        occurrence.alias.as_deref(),
+                        &occurrence.field_name,
+                    )
+                })
+            else {
+                // Fields from federation field sets aren't in the document
+                continue;
+            };
+            add_span(
+                self.data
+                    .options
+                    .field_coordinate(&occurrence.type_name, &occurrence.field_name),
+                start,
+                end,
+            );
+            for (argument_name, start, end) in spans::argument_spans(&document_text, end) {
+                add_span(
+                    self.data.options.argument_coordinate(
+                        &occurrence.type_name,
+                        &occurrence.field_name,
+                        argument_name,
+                    ),
+                    start,
+                    end,
+                );
+            }
+        }
+
+        let mut normalized_spans: BTreeMap<String, Vec<SourceSpan>> = BTreeMap::new();
+        for (coordinate, spans) in spans_by_coordinate {
+            normalized_spans
+                .entry(self.data.options.case_normalized(coordinate))
+                .or_default()
+                .extend(spans);
+        }
+        Ok(normalized_spans
+            .into_iter()
+            .map(|(coordinate, mut spans)| {
+                // Fragments spread at several depths are traversed more than once
+                spans.sort_unstable();
+                spans.dedup();
+                CoordinateSpans { coordinate, spans }
+            })
+            .collect())
+    }
+
+    /// Extract schema coordinates from a document that may contain template literal
+    /// interpolations (`${...}`) or other minor syntax noise, such as the body of a `gql`
+    /// tagged template. Definitions that fail to parse are skipped instead of failing the
+    /// whole document, so this never throws.
+    #[napi]
+    pub fn extract_tolerant(&self, document_text: String) -> Vec<String> {
+        let document_text = tolerant::strip_interpolations(&document_text);
+
+        let query_doc = match query::parse_query::<String>(&document_text) {
+            Ok(query_doc) => query_doc,
+            // Fall back to parsing each top-level definition on its own, keeping the ones that parse
+            Err(_) => query::Document {
+                definitions: tolerant::split_definitions(&document_text)
+                    .into_iter()
+                    .filter_map(|text| query::parse_query::<String>(text).ok())
+                    .flat_map(|doc| doc.definitions)
+                    .collect(),
+            },
+        };
+
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, &query_doc, &mut coordinates);
+
+        for definition in &query_doc.definitions {
+            if let query::Definition::Operation(operation) = definition {
+                // Unsupported operations are skipped like unparseable definitions
+                let _ = extraction.extract_from_operation(operation);
+            }
+        }
+
+        self.output(coordinates)
+    }
+
+    /// List the `InputType.field` coordinate of every input object field in the schema, sorted
+    #[napi]
+    pub fn all_input_coordinates(&self) -> Vec<String> {
+        let mut coordinates: Vec<String> = self
+            .data
+            .type_map
+            .iter()
+            // Skip root type aliases, which are keyed by a name other than their own
+            .filter(|(key, info)| info.kind == TypeKind::InputObject && *key == &info.name)
+            .flat_map(|(_, info)| {
+                info.fields.keys().map(|field| {
+                    let coordinate = self.data.options.field_coordinate(&info.name, field);
+                    let coordinate = self.data.options.prefixed(coordinate);
+                    self.data.options.case_normalized(coordinate)
+                })
+            })
+            .collect();
+        coordinates.sort();
+        coordinates.dedup();
+        coordinates
+    }
+
+    /// List every coordinate defined by the schema, sorted
+    #[napi]
+    pub fn all_coordinates(&self) -> Vec<String> {
+        self.all_coordinates_detailed()
+            .into_iter()
+            .map(|coordinate| coordinate.coordinate)
+            .collect()
+    }
+
+    /// List every coordinate defined by the schema, sorted, each tagged with the kind of schema
+    /// element it refers to
+    #[napi]
+    pub fn all_coordinates_detailed(&self) -> Vec<Coordinate> {
+        let mut coordinates = self.schema_coordinates();
+        if self.data.options.normalize_case.unwrap_or(false) {
+            for coordinate in &mut coordinates {
+                coordinate.coordinate = self
+                    .data
+                    .options
+                    .case_normalized(std::mem::take(&mut coordinate.coordinate));
+            }
+            coordinates.sort_by(|a, b| a.coordinate.cmp(&b.coordinate));
+            coordinates.dedup_by(|a, b| a.coordinate == b.coordinate);
+        }
+        coordinates
+    }
+
+    /// Lists every coordinate defined by the schema in its original case, sorted
+    fn schema_coordinates(&self) -> Vec<Coordinate> {
+        let mut coordinates = Vec::new();
+        let mut push = |coordinate: String, kind: CoordinateKind| {
+            let coordinate = self.data.options.prefixed(coordinate);
+            coordinates.push(Coordinate { coordinate, kind });
+        };
+
+        for (key, info) in self.data.type_map.iter() {
+            // Skip root type aliases, which are keyed by a name other than their own
+            if key != &info.name {
+                continue;
+            }
+
+            push(info.name.clone(), CoordinateKind::Type);
+            for value in &info.values {
+                push(
+                    self.data.options.field_coordinate(&info.name, value),
+                    CoordinateKind::EnumValue,
+                );
+            }
+            for (field_name, field_info) in &info.fields {
+                let kind = if info.kind == TypeKind::InputObject {
+                    CoordinateKind::InputField
+                } else {
+                    CoordinateKind::OutputField
+                };
+                push(
+                    self.data.options.field_coordinate(&info.name, field_name),
+                    kind,
+                );
+                for argument_name in field_info.arguments.keys() {
+                    push(
+                        self.data.options.argument_coordinate(
+                            &info.name,
+                            field_name,
+                            argument_name,
+                        ),
+                        CoordinateKind::Argument,
+                    );
+                }
+            }
+        }
+
+        for (directive_name, directive_info) in &self.data.directives {
+            push(format!("@{}", directive_name), CoordinateKind::Directive);
+            for argument_name in directive_info.arguments.keys() {
+                push(
+                    directive_argument_coordinate(directive_name, argument_name),
+                    CoordinateKind::Argument,
+                );
+            }
+        }
+
+        coordinates.sort_by(|a, b| a.coordinate.cmp(&b.coordinate));
+        coordinates
+    }
+
+    /// List the `@directive` and `@directive(arg:)` coordinates of every directive defined by the
+    /// schema, sorted
+    #[napi]
+    pub fn all_directive_coordinates(&self) -> Vec<String> {
+        let mut coordinates: Vec<String> = self
+            .data
+            .directives
+            .iter()
+            .flat_map(|(directive_name, directive_info)| {
+                std::iter::once(format!("@{}", directive_name)).chain(
+                    directive_info.arguments.keys().map(|argument_name| {
+                        directive_argument_coordinate(directive_name, argument_name)
+                    }),
+                )
+            })
+            .map(|coordinate| self.data.options.prefixed(coordinate))
+            .collect();
+        coordinates.sort();
+        coordinates
+    }
+
+    /// Extract schema coordinates from a document as a bitset, one bit per coordinate listed by
+    /// `all_coordinates`, in order. Bit `i` is bit `i % 8` of byte `i / 8`, counting from the
+    /// least significant bit. Coordinates the schema doesn't define, like unknown fields, are
+    /// left out. Bitsets are only meaningful for the schema they were extracted with. With
+    /// `normalize_case`, bits follow the coordinates' order before normalizing, so coordinates
+    /// differing only in case keep separate bits.
+    #[napi]
+    pub fn extract_coordinate_bitset(&self, document_text: String) -> Result<Buffer> {
+        let mut coordinates = HashSet::new();
+        self.collect_coordinates(&document_text, &mut coordinates)?;
+
+        let coordinate_index = self.coordinate_index();
+        let mut bitset = vec![0u8; coordinate_index.len().div_ceil(8)];
+        for coordinate in &coordinates {
+            if let Ok(index) = coordinate_index.binary_search(coordinate) {
+                bitset[index / 8] |= 1 << (index % 8);
+            }
+        }
+        Ok(bitset.into())
+    }
+
+    /// List the coordinates set in a bitset from `extract_coordinate_bitset`, sorted. Bits past
+    /// the schema's last coordinate are ignored.
+    #[napi]
+    pub fn decode_coordinate_bitset(&self, bitset: Buffer) -> Vec<String> {
+        let mut coordinates: Vec<String> = self
+            .coordinate_index()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                bitset
+                    .get(index / 8)
+                    .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+            })
+            .map(|(_, coordinate)| self.data.options.case_normalized(coordinate.clone()))
+            .collect();
+        if self.data.options.normalize_case.unwrap_or(false) {
+            coordinates.sort_unstable();
+            coordinates.dedup();
+        }
+        coordinates
+    }
+
+    /// Check if the schema defines a coordinate, written as extraction returns it: a type,
+    /// `Type.field`, `Type.field(arg:)`, `Enum.VALUE`, `@directive` or `@directive(arg:)`.
+    /// Malformed coordinates aren't defined.
+    #[napi]
+    pub fn has_coordinate(&self, coordinate: String) -> bool {
+        self.defines_coordinate(&coordinate)
+            || (self.data.options.normalize_case.unwrap_or(false)
+                && self.normalized_coordinates().contains(&coordinate))
+    }
+
+    /// Check if a field exists on a type
+    #[napi]
+    pub fn has_field(&self, coordinate: String) -> Result<bool> {
+        // Parse the coordinate in the format "TypeName.fieldName"
+        let parts: Vec<&str> = coordinate.split(self.data.options.separator()).collect();
+
+        if parts.len() != 2 {
+            return Ok(false);
+        }
+
+        let type_name = parts[0];
+        let field_name = parts[1];
+
+        if type_name.is_empty() || field_name.is_empty() {
+            return Ok(false);
+        }
+
+        // Look up the type in the type map
+        if let Some(type_info) = self.data.type_map.get(type_name) {
+            // Check if the field exists on this type
+            Ok(type_info.fields.contains_key(field_name))
+        } else {
+            // Type doesn't exist
+            Ok(false)
+        }
+    }
+
+    /// List the object types an interface or union can resolve to, sorted: the object types
+    /// implementing an interface, or the members of a union. Empty for other and unknown types.
+    #[napi]
+    pub fn possible_types(&self, abstract_type_name: String) -> Vec<String> {
+        possible_object_types(
+            &self.data.type_map,
+            &self.data.implementations,
+            &self.data.union_members,
+            &abstract_type_name,
+        )
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+    }
+}
+
+impl ParsedSchema {
+    /// Builds a ParsedSchema from a parsed schema document, with validated options
+    fn from_schema_doc(
+        mut schema_doc: schema::Document<'static, String>,
+        options: ExtractionOptions,
+    ) -> Result<Self> {
+        if options.from_subgraph.unwrap_or(false) {
+            add_federation_definitions(&mut schema_doc);
+        }
+        if let Some(type_renames) = &options.type_renames {
+            rename_types(&mut schema_doc, type_renames);
+        }
+        options.validate_root_aliases(&schema_doc)?;
+
+        let directives = build_directive_map(&schema_doc);
+        let implementations = build_implementations_map(&schema_doc);
+        let union_members = build_union_members_map(&schema_doc);
+        let excluded_coordinates = options
+            .exclude_coordinates
+            .iter()
+            .flatten()
+            .cloned()
+            .collect();
+
+        // Build type map and wrap in Arc
+        let root_aliases = root_type_aliases(&schema_doc, &options);
+        let type_map = if options.lazy_type_map.unwrap_or(false) {
+            TypeMap::Lazy(LazyTypeMap::new(schema_doc, root_aliases))
+        } else {
+            TypeMap::Eager(build_type_map(&schema_doc, root_aliases))
+        };
+        let type_map = Arc::new(type_map);
+
+        Ok(ParsedSchema {
+            data: Arc::new(SchemaData {
+                type_map,
+                options,
+                directives,
+                implementations,
+                union_members,
+                excluded_coordinates,
+                coordinate_index: OnceLock::new(),
+                normalized_coordinates: OnceLock::new(),
+                scalar_predicate: None,
+            }),
+            persisted_queries: Mutex::default(),
+        })
+    }
+
+    /// Converts extracted coordinates into the returned array, ordering them if configured and
+    /// then normalizing their case
+    fn output(&self, coordinates: impl IntoIterator<Item = String>) -> Vec<String> {
+        let mut result: Vec<String> = coordinates.into_iter().collect();
+        match self.data.options.order() {
+            Some(CoordinateOrder::Alphabetical) => result.sort_unstable(),
+            Some(CoordinateOrder::Schema) => result.sort_by_cached_key(|coordinate| {
+                (self.definition_index(coordinate), coordinate.clone())
+            }),
+            Some(CoordinateOrder::Document) | None => {}
+        }
+        if self.data.options.normalize_case.unwrap_or(false) {
+            // Coordinates differing only in case are returned once
+            let mut seen = HashSet::new();
+            result = result
+                .into_iter()
+                .map(|coordinate| self.data.options.case_normalized(coordinate))
+                .filter(|coordinate| seen.insert(coordinate.clone()))
+                .collect();
+            if self.data.options.order() == Some(CoordinateOrder::Alphabetical) {
+                result.sort_unstable();
+            }
+        }
+        result
+    }
+
+    /// Where the type of a coordinate is defined in the schema, with directive coordinates and
+    /// unknown types after every type
+    fn definition_index(&self, coordinate: &str) -> usize {
+        self.coordinate_type_name(coordinate)
+            .and_then(|type_name| self.data.type_map.get(type_name))
+            .map_or(usize::MAX, |type_info| type_info.definition_index)
+    }
+
+    /// The sorted coordinates of the schema in their original case, listed on first use
+    fn coordinate_index(&self) -> &[String] {
+        self.data.coordinate_index.get_or_init(|| {
+            self.schema_coordinates()
+                .into_iter()
+                .map(|coordinate| coordinate.coordinate)
+                .collect()
+        })
+    }
+
+    /// The coordinates of the schema with their case normalized, listed on first use
+    fn normalized_coordinates(&self) -> &HashSet<String> {
+        self.data.normalized_coordinates.get_or_init(|| {
+            self.coordinate_index()
+                .iter()
+                .map(|coordinate| self.data.options.case_normalized(coordinate.clone()))
+                .collect()
+        })
+    }
+
+    /// Strips the configured coordinate prefix, or `None` if the coordinate lacks it
+    fn unprefixed<'c>(&self, coordinate: &'c str) -> Option<&'c str> {
+        match &self.data.options.coordinate_prefix {
+            Some(prefix) => coordinate.strip_prefix(prefix.as_str())?.strip_prefix(
+                self.data
+                    .options
+                    .coordinate_prefix_separator
+                    .as_deref()
+                    .unwrap_or(":"),
+            ),
+            None => Some(coordinate),
+        }
+    }
+
+    /// Whether the schema defines an extracted coordinate: a type, field, input field, enum
+    /// value, argument, directive or directive argument
+    fn defines_coordinate(&self, coordinate: &str) -> bool {
+        let Some(coordinate) = self.unprefixed(coordinate) else {
+            return false;
+        };
+
+        if let Some(directive) = coordinate.strip_prefix('@') {
+            return match parse_argument(directive) {
+                Some((directive_name, argument_name)) => self
+                    .data
+                    .directives
+                    .get(directive_name)
+                    .is_some_and(|info| info.arguments.contains_key(argument_name)),
+                None => self.data.directives.contains_key(directive),
+            };
+        }
+
+        let Some((type_name, member)) = coordinate.split_once(self.data.options.separator()) else {
+            return self.data.type_map.contains_key(coordinate);
+        };
+        let Some(type_info) = self.data.type_map.get(type_name) else {
+            return false;
+        };
+        match parse_argument(member) {
+            Some((field_name, argument_name)) => type_info
+                .fields
+                .get(field_name)
+                .is_some_and(|field_info| field_info.arguments.contains_key(argument_name)),
+            None => {
+                type_info.fields.contains_key(member)
+                    || type_info.values.iter().any(|value| value == member)
+            }
+        }
+    }
+
+    /// The type name an extracted coordinate belongs to, or `None` for directive coordinates
+    fn coordinate_type_name<'c>(&self, coordinate: &'c str) -> Option<&'c str> {
+        let coordinate = self.unprefixed(coordinate)?;
+        if coordinate.starts_with('@') {
+            return None;
+        }
+
+        Some(
+            coordinate
+                .split_once(self.data.options.separator())
+                .map_or(coordinate, |(type_name, _)| type_name),
+        )
+    }
+
+    /// Parse a document and add its schema coordinates to `coordinates`, in their original case
+    fn collect_coordinates(
+        &self,
+        document_text: &str,
+        coordinates: &mut HashSet<String>,
+    ) -> Result<()> {
+        let query_doc = parse_document(document_text)?;
+
+        Extraction::new(self, &query_doc, coordinates).extract_document()
+    }
+
+    /// Parse a document and emit its schema coordinates to `sink` as they're found, without
+    /// collecting them first. A coordinate is emitted each time the document uses it, and
+    /// coordinates emitted before an extraction error aren't retracted. Fragments are traversed
+    /// again each time they're spread rather than replayed, so nothing but the parsed document
+    /// is held in memory while extracting.
+    pub fn extract_into_sink(
+        &self,
+        document_text: &str,
+        sink: &mut dyn CoordinateSink,
+    ) -> Result<()> {
+        let query_doc = parse_document(document_text)?;
+
+        let mut normalized_sink;
+        let sink: &mut dyn CoordinateSink = if self.data.options.normalize_case.unwrap_or(false) {
+            normalized_sink = CaseNormalizedSink {
+                options: &self.data.options,
+                sink,
+            };
+            &mut normalized_sink
+        } else {
+            sink
+        };
+        let mut extraction = Extraction::new(self, &query_doc, sink);
+        extraction.replay_fragments = false;
+        extraction.extract_document()
+    }
+
+    /// Extracts one operation or fragment of a document without expanding the fragments it
+    /// spreads, which are listed instead
+    fn extract_definition<'a, 'd>(
+        &self,
+        query_doc: &'a query::Document<'d, String>,
+        definition: &'a query::Definition<'d, String>,
+    ) -> Result<CachedDefinition> {
+        let mut coordinates = HashSet::new();
+        let mut extraction = Extraction::new(self, query_doc, &mut coordinates);
+        extraction.spreads = Some(HashSet::new());
+        match definition {
+            query::Definition::Operation(operation) => {
+                extraction.extract_from_operation(operation)?
+            }
+            query::Definition::Fragment(fragment) => {
+                let query::TypeCondition::On(type_name) = &fragment.type_condition;
+                extraction.extract_from_fragment(fragment, type_name, 1)
+            }
+        }
+        let spreads = extraction.spreads.take().unwrap_or_default();
+        let unknown_fields = std::mem::take(&mut extraction.unknown_fields);
+        let unknown_type_conditions = std::mem::take(&mut extraction.unknown_type_conditions);
+        let scalar_subselections = std::mem::take(&mut extraction.scalar_subselections);
+        Ok(CachedDefinition {
+            coordinates,
+            spreads,
+            unknown_fields,
+            unknown_type_conditions,
+            scalar_subselections,
+        })
+    }
+
+    /// Parse a document and call `callback` with each of its schema coordinates the first time
+    /// it's found. Once the callback returns an error, it isn't called again and the error is
+    /// returned.
+    pub fn extract_streaming_with(
+        &self,
+        document_text: &str,
+        callback: impl FnMut(String) -> Result<()>,
+    ) -> Result<()> {
+        let mut sink = UniqueCallbackSink {
+            seen: HashSet::new(),
+            callback,
+            error: None,
+        };
+        let result = self.extract_into_sink(document_text, &mut sink);
+        match sink.error {
+            Some(error) => Err(error),
+            None => result,
+        }
+    }
+
+    /// Create a ParsedSchema from a schema document that's already been parsed, for Rust callers
+    /// that parse schemas with `graphql_parser` themselves
+    pub fn from_schema_document(
+        schema_doc: schema::Document<'_, String>,
+        mut options: ExtractionOptions,
+    ) -> Result<Self> {
+        options.validate()?;
+
+        Self::from_schema_doc(schema_doc.into_static(), options)
+    }
+
+    /// Treat the types `predicate` accepts as leaf scalars too, on top of the built-in scalars
+    /// and those the schema defines, for Rust callers with their own scalar registries.
+    /// Selections on fields of these types are skipped like those on any other scalar.
+    pub fn with_scalar_predicate(
+        mut self,
+        predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        // Only copies the schema's state if extractors already share it
+        Arc::make_mut(&mut self.data).scalar_predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Extract the schema coordinates of a document that's already been parsed, for Rust callers
+    /// that parse documents with `graphql_parser` themselves
+    pub fn extract_from_query_document(
+        &self,
+        query_doc: &query::Document<'_, String>,
+    ) -> Result<HashSet<String>> {
+        let mut coordinates = HashSet::new();
+        Extraction::new(self, query_doc, &mut coordinates).extract_document()?;
+        Ok(coordinates
+            .into_iter()
+            .map(|coordinate| self.data.options.case_normalized(coordinate))
+            .collect())
+    }
+}
+
+/// Receives coordinates as they're extracted, for Rust callers streaming them elsewhere
+pub trait CoordinateSink {
+    fn emit(&mut self, coordinate: String);
+}
+
+impl CoordinateSink for HashSet<String> {
+    fn emit(&mut self, coordinate: String) {
+        self.insert(coordinate);
+    }
+}
+
+/// Keeps every emitted coordinate in the order they're emitted, including repeats
+impl CoordinateSink for Vec<String> {
+    fn emit(&mut self, coordinate: String) {
+        self.push(coordinate);
+    }
+}
+
+/// Normalizes the case of coordinates before passing them on, if configured
+struct CaseNormalizedSink<'s> {
+    options: &'s ExtractionOptions,
+    sink: &'s mut dyn CoordinateSink,
+}
+
+impl CoordinateSink for CaseNormalizedSink<'_> {
+    fn emit(&mut self, coordinate: String) {
+        self.sink.emit(self.options.case_normalized(coordinate));
+    }
+}
+
+/// Calls a callback with each coordinate the first time it's emitted, keeping the first error
+/// the callback returns and ignoring coordinates emitted after it
+struct UniqueCallbackSink<F> {
+    seen: HashSet<String>,
+    callback: F,
+    error: Option<Error>,
+}
+
+impl<F: FnMut(String) -> Result<()>> CoordinateSink for UniqueCallbackSink<F> {
+    fn emit(&mut self, coordinate: String) {
+        if self.error.is_some() || self.seen.contains(&coordinate) {
+            return;
+        }
+        self.seen.insert(coordinate.clone());
+        if let Err(error) = (self.callback)(coordinate) {
+            self.error = Some(error);
+        }
+    }
+}
+
+/// Extracts a document's coordinates off the JS thread for `ParsedSchema.extractStreaming`
+pub struct ExtractStreamingTask {
+    schema: ParsedSchema,
+    document_text: String,
+    callback: ThreadsafeFunction<String, (), String, Status, false>,
+}
+
+impl Task for ExtractStreamingTask {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(&mut self) -> Result<()> {
+        let callback = &self.callback;
+        self.schema
+            .extract_streaming_with(&self.document_text, |coordinate| {
+                // The callback runs on the JS thread, which reports how it went back over the
+                // channel so a throw stops the extraction
+                let (sender, receiver) = std::sync::mpsc::channel();
+                let status = callback.call_with_return_value(
+                    coordinate,
+                    ThreadsafeFunctionCallMode::Blocking,
+                    move |result, _env| {
+                        let _ = sender.send(result);
+                        Ok(())
+                    },
+                );
+                if status != Status::Ok {
+                    return Err(Error::new(status, "Failed to call the callback"));
+                }
+                receiver
+                    .recv()
+                    .map_err(|_| Error::from_reason("The callback was dropped before returning"))?
+            })
+    }
+
+    fn resolve(&mut self, _env: Env, _output: ()) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns an error for the unknown fields, type conditions and scalar subselections
+/// recorded by the strict options
+fn check_recorded_errors(
+    mut unknown_fields: Vec<String>,
+    mut unknown_type_conditions: Vec<String>,
+    mut scalar_subselections: Vec<String>,
+) -> Result<()> {
+    if !unknown_fields.is_empty() {
+        unknown_fields.sort_unstable();
+        unknown_fields.dedup();
+        return Err(Error::from_reason(format!(
+            "Unknown fields: {}",
+            unknown_fields.join(", ")
+        )));
+    }
+    if !unknown_type_conditions.is_empty() {
+        unknown_type_conditions.sort_unstable();
+        unknown_type_conditions.dedup();
+        return Err(Error::from_reason(format!(
+            "Unknown type conditions: {}",
+            unknown_type_conditions.join(", ")
+        )));
+    }
+    if !scalar_subselections.is_empty() {
+        scalar_subselections.sort_unstable();
+        scalar_subselections.dedup();
+        return Err(Error::from_reason(format!(
+            "Selections on scalar or enum fields: {}",
+            scalar_subselections.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// Hashes sorted coordinates with 64-bit FNV-1a, which unlike the standard library's hashers is
+/// stable across versions. Each coordinate is terminated by a newline so that different
+/// splits of the same text hash differently.
+fn coordinates_etag(sorted_coordinates: &[&String]) -> String {
+    let bytes = sorted_coordinates
+        .iter()
+        .flat_map(|coordinate| coordinate.as_bytes().iter().chain(b"\n"));
+    format!("{:016x}", fnv1a(bytes))
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break, doubling any quotes
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// Hashes bytes with 64-bit FNV-1a
+fn fnv1a<'b>(bytes: impl IntoIterator<Item = &'b u8>) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// The most bytes a gzipped input may decompress to, so a small gzip bomb can't exhaust memory
+#[cfg(feature = "flate2")]
+const MAX_DECOMPRESSED_SIZE: usize = 64 * 1024 * 1024;
+
+/// Decompresses gzipped UTF-8 text of at most `limit` bytes, naming what it is in errors so they
+/// can't be mistaken for parse errors
+#[cfg(feature = "flate2")]
+fn gunzip(bytes: &[u8], what: &str, limit: usize) -> Result<String> {
+    use std::io::Read;
+
+    let mut text = Vec::new();
+    // Reads one byte past the limit to tell inputs of exactly `limit` bytes from larger ones
+    flate2::read::GzDecoder::new(bytes)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut text)
+        .map_err(|e| Error::from_reason(format!("Failed to decompress {}: {}", what, e)))?;
+    if text.len() > limit {
+        return Err(Error::from_reason(format!(
+            "Failed to decompress {}: it's larger than {} bytes",
+            what, limit
+        )));
+    }
+    String::from_utf8(text)
+        .map_err(|e| Error::from_reason(format!("Failed to decompress {}: {}", what, e)))
+}
+
+/// Parses a document, ignoring a leading byte order mark and the trailing null or control bytes
+/// that some transports pad documents with. Documents left empty parse with no definitions
+fn parse_document(document_text: &str) -> Result<query::Document<'_, String>> {
+    let document_text = document_text
+        .strip_prefix('\u{feff}')
+        .unwrap_or(document_text)
+        .trim_end_matches(char::is_control);
+    if document_text.trim().is_empty() {
+        return Ok(query::Document {
+            definitions: Vec::new(),
+        });
+    }
+
+    query::parse_query::<String>(document_text).map_err(|e| {
+        if tolerant::looks_like_schema(document_text) {
+            Error::from_reason(format!(
+                "Failed to parse document: {}. The input looks like a schema (SDL) rather than \
+                 a query document",
+                e
+            ))
+        } else {
+            Error::from_reason(format!("Failed to parse document: {}", e))
+        }
+    })
+}
+
+/// Counters describing the work done while extracting coordinates from a document
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtractionStats {
+    /// Number of field selections visited, including fields inside fragments
+    pub fields_visited: u32,
+    /// Number of fragment spreads that were expanded
+    pub fragments_expanded: u32,
+    /// Time taken to parse the document and extract its coordinates, in microseconds
+    pub duration_micros: u32,
+    /// Number of fields, fragment spreads and inline fragments visited, including those inside
+    /// fragments
+    pub selections_visited: u32,
+    /// Number of selections in the widest selection set visited
+    pub max_fanout: u32,
+}
+
+/// Schema coordinates extracted from a document, along with stats about the extraction
+#[napi(object)]
+pub struct ExtractionWithStats {
+    pub coordinates: Vec<String>,
+    pub stats: ExtractionStats,
+}
+
+/// Schema coordinates extracted from a document, along with measures of how costly the
+/// document is to traverse, as returned by `ParsedSchema.extractSecurityReport`
+#[napi(object)]
+pub struct SecurityReport {
+    pub coordinates: Vec<String>,
+    /// Number of fields, fragment spreads and inline fragments visited, counting those inside
+    /// a fragment each time it's expanded
+    pub nodes_visited: u32,
+    /// Number of selections in the widest selection set
+    pub max_fanout: u32,
+    /// Number of fragment spreads that were expanded
+    pub fragment_expansions: u32,
+}
+
+/// A coordinate defined by the schema, as listed by `ParsedSchema.allCoordinatesDetailed`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coordinate {
+    pub coordinate: String,
+    pub kind: CoordinateKind,
+}
+
+/// The kind of schema element a coordinate refers to
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateKind {
+    /// An object, interface, custom scalar, enum or input object type, such as `Human`
+    Type,
+    /// A field of an object or interface type, such as `Human.name`
+    OutputField,
+    /// A field of an input object type, such as `VetDetailsInput.name`
+    InputField,
+    /// An argument of a field or directive, such as `Mutation.addCat(name:)`
+    Argument,
+    /// A value of an enum type, such as `Species.DOG`
+    EnumValue,
+    /// A directive defined by the schema, such as `@auth`
+    Directive,
+}
+
+/// A variable referenced by an operation, as listed by `ParsedSchema.extractUsedVariables`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsedVariable {
+    pub name: String,
+    /// The variable's declared type, such as `[ID!]!`
+    #[napi(js_name = "type")]
+    pub type_name: String,
+}
+
+/// The schema coordinates of one operation, as listed by `ParsedSchema.extractAttributed`
+#[napi(object)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributedCoordinates {
+    /// The operation's name, if it has one
+    pub operation: Option<String>,
+    /// Sorted coordinates produced by the operation's own selections and variables
+    pub direct: Vec<String>,
+    /// Sorted coordinates produced by each spread fragment, keyed by fragment name
+    pub fragments: HashMap<String, Vec<String>>,
+}
+
+/// A deprecated schema element used by a document, as listed by
+/// `ParsedSchema.extractDeprecatedUsages`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecatedUsage {
+    pub coordinate: String,
+    /// Whether the coordinate is an output field, an argument or an input field
+    pub kind: CoordinateKind,
+    /// The `reason` given by the `@deprecated` directive, if any
+    pub reason: Option<String>,
+}
+
+/// A coordinate used by a document, as listed by `ParsedSchema.extractWithDepths`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoordinateDepth {
+    pub coordinate: String,
+    /// The smallest selection depth the coordinate is used at. Coordinates used outside any
+    /// selection, such as variables' input types, are at depth 0
+    pub min_depth: u32,
+}
+
+/// How a document uses a coordinate, as listed by `ParsedSchema.extractWithOrigins`
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CoordinateOrigin {
+    /// A selected field, such as `Human.name`
+    Selection,
+    /// A variable's input type, or the input fields and enum values extracted for it, such as
+    /// `VetDetailsInput`
+    Variable,
+    /// A field argument, or the enum values and input fields passed to it, such as
+    /// `Root.pets(species:)`
+    Argument,
+    /// An applied directive, its arguments, or the input fields passed to them, such as `@auth`
+    Directive,
+}
+
+/// A coordinate used by a document, as listed by `ParsedSchema.extractWithOrigins`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoordinateOrigins {
+    pub coordinate: String,
+    /// Every way the document uses the coordinate
+    pub origins: Vec<CoordinateOrigin>,
+}
+
+/// Schema coordinates extracted from a document, as returned by `ParsedSchema.extractWithEtag`
+#[napi(object)]
+pub struct ExtractionWithEtag {
+    pub coordinates: Vec<String>,
+    /// A 64-bit FNV-1a hash of the sorted coordinates, as 16 hex digits. It's stable across
+    /// versions and platforms, but needs quoting for use as an HTTP `ETag` header.
+    pub etag: String,
+}
+
+/// Schema coordinates extracted from a document, as returned by
+/// `ParsedSchema.extractWithMaxDepth`
+#[napi(object)]
+pub struct ExtractionWithMaxDepth {
+    pub coordinates: Vec<String>,
+    /// The deepest selection depth of any field in the document, or 0 if it selects no fields
+    pub max_depth: u32,
+}
+
+/// The schema coordinates of one document in a blob, as listed by
+/// `ParsedSchema.extractSchemaCoordinatesMulti`
+#[napi(object)]
+pub struct PartExtraction {
+    /// The part's coordinates, or empty if it failed
+    pub coordinates: Vec<String>,
+    /// Why the part failed to parse or extract, if it did
+    pub error: Option<String>,
+}
+
+/// Schema coordinates extracted from a document, as returned by
+/// `ParsedSchema.extractWithUnresolved`
+#[napi(object)]
+pub struct ExtractionWithUnresolved {
+    pub coordinates: Vec<String>,
+    /// The sorted names of spread fragments that the document doesn't define
+    pub unresolved_fragments: Vec<String>,
+}
+
+/// A coordinate used by a document, as listed by `ParsedSchema.extractWithSpans`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoordinateSpans {
+    pub coordinate: String,
+    /// Where the field and argument names using the coordinate are in the document. Empty for
+    /// other coordinates, such as variables' input types
+    pub spans: Vec<SourceSpan>,
+}
+
+/// A range of bytes in a document's source text
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceSpan {
+    /// Offset of the first byte
+    pub start: u32,
+    /// Offset just past the last byte
+    pub end: u32,
+}
+
+/// A line and column in a document's source text, both starting at 1
+#[napi(object)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePosition {
+    pub line: u32,
+    pub column: u32,
+}
+
+/// The kind of problem found by `ParsedSchema.validateAndExtract`
+#[napi(string_enum)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// A field its parent type doesn't declare, such as `Human.wings`
+    UnknownField,
+    /// An argument its field doesn't declare, such as `Root.pets(limit:)`
+    UnknownArgument,
+    /// A spread of a fragment the document doesn't define
+    UnknownFragment,
+    /// A type condition or variable type the schema doesn't define
+    UnknownType,
+}
+
+/// A problem found in a document by `ParsedSchema.validateAndExtract`
+#[napi(object)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    pub message: String,
+    /// Where the field, fragment spread, fragment or variable with the problem starts. Argument
+    /// issues are at their field.
+    pub position: SourcePosition,
+}
+
+impl Issue {
+    fn new(kind: IssueKind, message: String, position: graphql_parser::Pos) -> Self {
+        Issue {
+            kind,
+            message,
+            position: SourcePosition {
+                line: position.line as u32,
+                column: position.column as u32,
+            },
+        }
+    }
+}
+
+/// Schema coordinates extracted from a document, with the problems found in it, as returned by
+/// `ParsedSchema.validateAndExtract`
+#[napi(object)]
+pub struct ValidatedExtraction {
+    pub coordinates: Vec<String>,
+    pub issues: Vec<Issue>,
+}
+
+/// Extracts schema coordinates one document at a time, created by `ParsedSchema.extractor`.
+/// The set coordinates are collected into is cleared and reused by each call.
+#[napi]
+pub struct Extractor {
+    schema: ParsedSchema,
+    coordinates: HashSet<String>,
+}
+
+#[napi]
+impl Extractor {
+    /// Extract schema coordinates from a document
+    #[napi]
+    pub fn extract(&mut self, document_text: String) -> Result<Vec<String>> {
+        self.coordinates.clear();
+
+        let result = self
+            .schema
+            .collect_coordinates(&document_text, &mut self.coordinates);
+        if let Err(e) = result {
+            self.coordinates.clear();
+            return Err(e);
+        }
+
+        Ok(self.schema.output(self.coordinates.iter().cloned()))
+    }
+}
+
+/// Extracts schema coordinates from successive versions of a document, such as one being edited
+/// live, created by `ParsedSchema.incrementalExtractor`. Each operation and fragment's own
+/// coordinates are cached by a hash of its content, so only definitions that changed since the
+/// previous call are traversed again.
+#[napi]
+pub struct IncrementalExtractor {
+    schema: ParsedSchema,
+    /// The definitions of the previous document, keyed by the hash of their formatted content
+    definitions: HashMap<u64, CachedDefinition>,
+    recomputed: u32,
+}
+
+/// The coordinates one operation or fragment uses directly, the fragments it spreads, and the
+/// problems recorded by the strict options, which are only reported once it's reachable
+struct CachedDefinition {
+    coordinates: HashSet<String>,
+    spreads: HashSet<String>,
+    unknown_fields: Vec<String>,
+    unknown_type_conditions: Vec<String>,
+    scalar_subselections: Vec<String>,
+}
+
+#[napi]
+impl IncrementalExtractor {
+    /// Extract schema coordinates from the latest version of a document. The result is the same
+    /// as `ParsedSchema.extractSchemaCoordinates`, but only operations and fragments that are
+    /// new or changed since the previous call are traversed, and fragments only once an
+    /// operation reaches them.
+    #[napi]
+    pub fn extract(&mut self, document_text: String) -> Result<Vec<String>> {
+        let query_doc = parse_document(&document_text)?;
+        self.schema.data.options.check_operation_count(&query_doc)?;
+
+        // Index the definitions by the hash of their content, without traversing them yet
+        let mut contents: HashMap<u64, &query::Definition<String>> = HashMap::new();
+        let mut operations = Vec::new();
+        let mut fragments: HashMap<&str, Vec<u64>> = HashMap::new();
+        for definition in &query_doc.definitions {
+            let key = fnv1a(definition.to_string().as_bytes());
+            if contents.insert(key, definition).is_some() {
+                continue;
+            }
+            match definition {
+                query::Definition::Operation(_) => operations.push(key),
+                query::Definition::Fragment(fragment) => {
+                    fragments.entry(&fragment.name).or_default().push(key)
+                }
+            }
+        }
+
+        // Keep the cached definitions that are still in the document, including those that
+        // aren't reached this time
+        let mut definitions: HashMap<u64, CachedDefinition> = std::mem::take(&mut self.definitions)
+            .into_iter()
+            .filter(|(key, _)| contents.contains_key(key))
+            .collect();
+        let mut recomputed = 0;
+
+        // Operations are traversed in document order, so the first failing one is reported like
+        // `extractSchemaCoordinates` does. Fragments are traversed once reached from one.
+        let single_subscription_root = self
+            .schema
+            .data
+            .options
+            .single_subscription_root
+            .unwrap_or(false);
+        for key in &operations {
+            match definitions.entry(*key) {
+                hash_map::Entry::Occupied(_) => {
+                    // The root fields of a cached subscription depend on the fragments it
+                    // spreads, which may have changed
+                    if let query::Definition::Operation(query::OperationDefinition::Subscription(
+                        s,
+                    )) = contents[key]
+                    {
+                        if single_subscription_root {
+                            let mut coordinates = HashSet::new();
+                            Extraction::new(&self.schema, &query_doc, &mut coordinates)
+                                .check_single_subscription_root(&s.selection_set.items)?;
+                        }
+                    }
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    recomputed += 1;
+                    entry.insert(self.schema.extract_definition(&query_doc, contents[key])?);
+                }
+            }
+        }
+
+        // Combine the operations' coordinates with those of every fragment they reach
+        let mut coordinates = HashSet::new();
+        let mut unknown_fields = Vec::new();
+        let mut unknown_type_conditions = Vec::new();
+        let mut scalar_subselections = Vec::new();
+        let mut pending: Vec<u64> = operations.clone();
+        let mut reached = HashSet::new();
+        while let Some(key) = pending.pop() {
+            let definition = match definitions.entry(key) {
+                hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                hash_map::Entry::Vacant(entry) => {
+                    recomputed += 1;
+                    entry.insert(self.schema.extract_definition(&query_doc, contents[&key])?)
+                }
+            };
+            coordinates.extend(definition.coordinates.iter().cloned());
+            unknown_fields.extend(definition.unknown_fields.iter().cloned());
+            unknown_type_conditions.extend(definition.unknown_type_conditions.iter().cloned());
+            scalar_subselections.extend(definition.scalar_subselections.iter().cloned());
+            for spread in &definition.spreads {
+                if reached.insert(spread.clone()) {
+                    pending.extend(fragments.get(spread.as_str()).into_iter().flatten());
+                }
+            }
+        }
+
+        self.definitions = definitions;
+        self.recomputed = recomputed;
+        check_recorded_errors(
+            unknown_fields,
+            unknown_type_conditions,
+            scalar_subselections,
+        )?;
+        Ok(self.schema.output(coordinates))
+    }
+
+    /// The number of operations and fragments traversed by the previous call to `extract`
+    #[napi(getter)]
+    pub fn recomputed(&self) -> u32 {
+        self.recomputed
+    }
+}
+
+/// Named schemas, such as the subgraphs of a federated graph, for finding which of them define
+/// the coordinates a document uses
+#[napi]
+#[derive(Default)]
+pub struct SchemaRegistry {
+    schemas: Vec<(String, ParsedSchema)>,
+}
+
+#[napi]
+impl SchemaRegistry {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse and register a schema under a name, replacing any schema registered under it
+    #[napi]
+    pub fn register(&mut self, name: String, schema_text: String) -> Result<()> {
+        let parsed_schema = ParsedSchema::new(schema_text)?;
+        match self
+            .schemas
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            Some((_, existing_schema)) => *existing_schema = parsed_schema,
+            None => self.schemas.push((name, parsed_schema)),
+        }
+        Ok(())
+    }
+
+    /// Map each schema coordinate a document uses to the names of the registered schemas that
+    /// define it, in registration order. The document is extracted against every schema, and
+    /// coordinates no schema defines map to `<unknown>`.
+    #[napi]
+    pub fn attribute_coordinates(
+        &self,
+        document_text: String,
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut coordinates = HashSet::new();
+        for (_, parsed_schema) in &self.schemas {
+            parsed_schema.collect_coordinates(&document_text, &mut coordinates)?;
+        }
+
+        Ok(coordinates
+            .into_iter()
+            .map(|coordinate| {
+                let mut schema_names: Vec<String> = self
+                    .schemas
+                    .iter()
+                    .filter(|(_, parsed_schema)| parsed_schema.defines_coordinate(&coordinate))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                if schema_names.is_empty() {
+                    schema_names.push(UNKNOWN_SCHEMA.to_string());
+                }
+                (coordinate, schema_names)
+            })
+            .collect())
+    }
+}
+
+/// Versions of a schema, such as a deployment's schema before and after a rollout, parsed once
+/// and kept so documents can be extracted against a specific version. Holds at most `capacity`
+/// versions, evicting the least recently used one to make room for another.
+#[napi]
+pub struct VersionedSchemaRegistry {
+    /// Stored versions, from least to most recently used
+    schemas: Vec<(String, ParsedSchema)>,
+    capacity: usize,
+}
+
+#[napi]
+impl VersionedSchemaRegistry {
+    /// Create a registry holding at most `capacity` schema versions
+    #[napi(constructor)]
+    pub fn new(capacity: u32) -> Result<Self> {
+        if capacity == 0 {
+            return Err(Error::from_reason(
+                "Versioned schema registry capacity must be at least 1",
+            ));
+        }
+        Ok(VersionedSchemaRegistry {
+            schemas: Vec::new(),
+            capacity: capacity as usize,
+        })
+    }
+
+    /// Parse and store a schema under a version, such as a hash of its text, replacing any
+    /// schema stored under it. Evicts the least recently used version when the registry is full.
+    #[napi]
+    pub fn put(&mut self, version: String, schema_text: String) -> Result<()> {
+        let parsed_schema = ParsedSchema::new(schema_text)?;
+        self.schemas.retain(|(existing, _)| *existing != version);
+        if self.schemas.len() == self.capacity {
+            self.schemas.remove(0);
+        }
+        self.schemas.push((version, parsed_schema));
+        Ok(())
+    }
+
+    /// Extract schema coordinates from a document against a stored schema version, marking it
+    /// as recently used. Throws if the version isn't stored, such as after being evicted.
+    #[napi]
+    pub fn extract(&mut self, version: String, document_text: String) -> Result<Vec<String>> {
+        let Some(index) = self
+            .schemas
+            .iter()
+            .position(|(existing, _)| *existing == version)
+        else {
+            return Err(Error::from_reason(format!(
+                "Unknown schema version: {}",
+                version
+            )));
+        };
+
+        // Move the version to the end, as the most recently used
+        self.schemas[index..].rotate_left(1);
+        let (_, parsed_schema) = &self.schemas[self.schemas.len() - 1];
+        parsed_schema.extract_schema_coordinates(document_text)
+    }
+
+    /// The stored versions, from least to most recently used
+    #[napi]
+    pub fn versions(&self) -> Vec<String> {
+        self.schemas
+            .iter()
+            .map(|(version, _)| version.clone())
+            .collect()
+    }
+}
+
+/// Fragments shared between documents, such as a client's fragments file, parsed once and
+/// resolved by `ParsedSchema.extractWithRegistry` when documents spread them
+#[napi]
+pub struct FragmentRegistry {
+    fragments: HashMap<String, query::FragmentDefinition<'static, String>>,
+}
+
+#[napi]
+impl FragmentRegistry {
+    /// Parse a document of fragment definitions. Throws if it contains operations.
+    #[napi(constructor)]
+    pub fn new(fragments_text: String) -> Result<Self> {
+        let fragments_doc = parse_document(&fragments_text)?.into_static();
+
+        let mut fragments = HashMap::new();
+        for definition in fragments_doc.definitions {
+            match definition {
+                query::Definition::Fragment(fragment) => {
+                    fragments.entry(fragment.name.clone()).or_insert(fragment);
+                }
+                query::Definition::Operation(_) => {
+                    return Err(Error::from_reason(
+                        "Fragment registries can only contain fragment definitions",
+                    ));
+                }
+            }
+        }
+        Ok(FragmentRegistry { fragments })
+    }
+
+    /// The number of fragments in the registry
+    #[napi(getter)]
+    pub fn length(&self) -> u32 {
+        self.fragments.len() as u32
+    }
+}
+
+/// A reusable set of coordinates, filled by `ParsedSchema.extractSchemaCoordinatesInto`.
+/// Reusing one buffer across extractions keeps its allocation alive between calls.
+#[napi]
+#[derive(Default)]
+pub struct CoordinateBuffer {
+    coordinates: HashSet<String>,
+}
+
+#[napi]
+impl CoordinateBuffer {
+    /// Create a new, empty CoordinateBuffer
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of coordinates currently held by the buffer
+    #[napi(getter)]
+    pub fn length(&self) -> u32 {
+        self.coordinates.len() as u32
+    }
+
+    /// Copy the coordinates currently held by the buffer into an array
+    #[napi]
+    pub fn to_array(&self) -> Vec<String> {
+        self.coordinates.iter().cloned().collect()
+    }
+
+    /// Remove all coordinates while keeping the allocated capacity
+    #[napi]
+    pub fn clear(&mut self) {
+        self.coordinates.clear();
+    }
+}
+
+fn build_type_map(
+    schema_doc: &schema::Document<'_, String>,
+    root_aliases: Vec<(String, String)>,
+) -> HashMap<String, TypeInfo> {
+    let mut type_map = HashMap::new();
+
+    // Build the type map
+    for (index, definition) in schema_doc.definitions.iter().enumerate() {
+        process_definition(definition, index, &mut type_map);
+    }
+
+    // Objects inherit the fields of their interfaces that they don't redeclare
+    for definition in &schema_doc.definitions {
+        if let Some(type_name) = definition_type_name(definition) {
+            for interface_name in implemented_interfaces(definition) {
+                inherit_interface_fields(&mut type_map, type_name, interface_name);
+            }
+        }
+    }
+
+    // Create aliases for Query, Mutation, Subscription and any custom roots to map to the actual
+    // schema types
+    for (standard_name, actual_name) in root_aliases {
+        create_root_type_alias(&mut type_map, &standard_name, &actual_name);
+    }
+
+    type_map
+}
+
+/// Pairs each root type alias with the name of the type it resolves to: the standard root
+/// operation names, followed by any aliases supplied in the options
+fn root_type_aliases(
+    schema_doc: &schema::Document<'_, String>,
+    options: &ExtractionOptions,
+) -> Vec<(String, String)> {
+    let mut root_aliases: Vec<(String, String)> = root_type_names(schema_doc)
+        .into_iter()
+        .map(|(standard_name, actual_name)| (standard_name.to_string(), actual_name))
+        .collect();
+    // Renamed types stay reachable under their old names
+    if let Some(type_renames) = &options.type_renames {
+        root_aliases.extend(
+            type_renames
+                .iter()
+                .map(|(old_name, new_name)| (old_name.clone(), new_name.clone())),
+        );
+    }
+    if let Some(custom_aliases) = &options.root_aliases {
+        root_aliases.extend(
+            custom_aliases
+                .iter()
+                .map(|(alias, actual_name)| (alias.clone(), actual_name.clone())),
+        );
+    }
+    root_aliases
+}
+
+/// Builds the directive definitions of a schema, keyed by name
+fn build_directive_map(
+    schema_doc: &schema::Document<'_, String>,
+) -> HashMap<String, DirectiveInfo> {
+    schema_doc
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            schema::Definition::DirectiveDefinition(directive) => {
+                let arguments = directive
+                    .arguments
+                    .iter()
+                    .map(|argument| {
+                        (
+                            argument.name.to_string(),
+                            get_field_type(&argument.value_type),
+                        )
+                    })
+                    .collect();
+                Some((directive.name.to_string(), DirectiveInfo { arguments }))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Builds the object types implementing each interface of a schema, keyed by interface name
+fn build_implementations_map(
+    schema_doc: &schema::Document<'_, String>,
+) -> HashMap<String, Vec<String>> {
+    let mut implementations: HashMap<String, Vec<String>> = HashMap::new();
+    for definition in &schema_doc.definitions {
+        if let Some(type_name) = definition_type_name(definition) {
+            for interface_name in implemented_interfaces(definition) {
+                implementations
+                    .entry(interface_name.to_string())
+                    .or_default()
+                    .push(type_name.to_string());
+            }
+        }
+    }
+    implementations
+}
+
+/// Builds the member types of each union of a schema, including members added by extensions,
+/// keyed by union name
+fn build_union_members_map(
+    schema_doc: &schema::Document<'_, String>,
+) -> HashMap<String, Vec<String>> {
+    let mut union_members: HashMap<String, Vec<String>> = HashMap::new();
+    for definition in &schema_doc.definitions {
+        let (name, types) = match definition {
+            schema::Definition::TypeDefinition(schema::TypeDefinition::Union(union)) => {
+                (&union.name, &union.types)
+            }
+            schema::Definition::TypeExtension(schema::TypeExtension::Union(union)) => {
+                (&union.name, &union.types)
+            }
+            _ => continue,
+        };
+        union_members
+            .entry(name.to_string())
+            .or_default()
+            .extend(types.iter().cloned());
+    }
+    union_members
+}
+
+/// Splits the `name(arg:)` form of argument coordinates into the name and the argument name
+fn parse_argument(coordinate: &str) -> Option<(&str, &str)> {
+    coordinate.strip_suffix(":)")?.split_once('(')
+}
+
+/// Formats a `@directive(arg:)` coordinate
+fn directive_argument_coordinate(directive_name: &str, argument_name: &str) -> String {
+    format!("@{}({}:)", directive_name, argument_name)
+}
+
+/// Finds the actual names of the root operation types, paired with their standard names
+fn root_type_names(schema_doc: &schema::Document<'_, String>) -> [(&'static str, String); 3] {
+    let mut query_type = "Query".to_string();
+    let mut mutation_type = "Mutation".to_string();
+    let mut subscription_type = "Subscription".to_string();
+
+    // Find the schema definition to get root operation types
+    for definition in &schema_doc.definitions {
+        if let schema::Definition::SchemaDefinition(schema_def) = definition {
+            if let Some(type_def) = &schema_def.query {
+                query_type = type_def.to_string();
+            }
+            if let Some(type_def) = &schema_def.mutation {
+                mutation_type = type_def.to_string();
+            }
+            if let Some(type_def) = &schema_def.subscription {
+                subscription_type = type_def.to_string();
+            }
+        }
+    }
+
+    [
+        ("Query", query_type),
+        ("Mutation", mutation_type),
+        ("Subscription", subscription_type),
+    ]
+}
+
+/// Adds the types and root fields that Apollo Federation injects into a subgraph schema,
+/// skipping any types the subgraph SDL already defines
+fn add_federation_definitions(schema_doc: &mut schema::Document<'static, String>) {
+    let [(_, query_type), ..] = root_type_names(schema_doc);
+    let federation_sdl = format!(
+        r#"
+        scalar _Any
+        union _Entity
+        type _Service {{
+            sdl: String
+        }}
+        extend type {} {{
+            _service: _Service!
+            _entities(representations: [_Any!]!): [_Entity]!
+        }}
+        "#,
+        query_type
+    );
+    let federation_doc = schema::parse_schema::<String>(&federation_sdl)
+        .expect("Federation definitions should parse")
+        .into_static();
+
+    let defined_types: HashSet<String> = schema_doc
+        .definitions
+        .iter()
+        .filter(|definition| matches!(definition, schema::Definition::TypeDefinition(_)))
+        .filter_map(definition_type_name)
+        .map(str::to_string)
+        .collect();
+
+    for definition in federation_doc.definitions {
+        let is_defined = matches!(definition, schema::Definition::TypeDefinition(_))
+            && definition_type_name(&definition).is_some_and(|name| defined_types.contains(name));
+        if !is_defined {
+            schema_doc.definitions.push(definition);
+        }
+    }
+}
+
+/// Renames types throughout a schema: their definitions and extensions, and every reference to
+/// them from fields, arguments, interfaces, unions and the schema definition
+fn rename_types(
+    schema_doc: &mut schema::Document<'static, String>,
+    type_renames: &HashMap<String, String>,
+) {
+    let rename = |name: &mut String| {
+        if let Some(new_name) = type_renames.get(name) {
+            *name = new_name.clone();
+        }
+    };
+    let rename_type = |mut value_type: &mut schema::Type<'static, String>| loop {
+        match value_type {
+            schema::Type::NamedType(name) => break rename(name),
+            schema::Type::ListType(inner) | schema::Type::NonNullType(inner) => {
+                value_type = inner;
+            }
+        }
+    };
+    let rename_input_values = |input_values: &mut Vec<schema::InputValue<'static, String>>| {
+        for input_value in input_values {
+            rename_type(&mut input_value.value_type);
+        }
+    };
+    let rename_fields = |fields: &mut Vec<schema::Field<'static, String>>| {
+        for field in fields {
+            rename_type(&mut field.field_type);
+            rename_input_values(&mut field.arguments);
+        }
+    };
+
+    for definition in &mut schema_doc.definitions {
+        match definition {
+            schema::Definition::SchemaDefinition(schema_def) => {
+                for root in [
+                    &mut schema_def.query,
+                    &mut schema_def.mutation,
+                    &mut schema_def.subscription,
+                ] {
+                    root.iter_mut().for_each(rename);
+                }
+            }
+            schema::Definition::TypeDefinition(type_def) => match type_def {
+                schema::TypeDefinition::Scalar(t) => rename(&mut t.name),
+                schema::TypeDefinition::Object(t) => {
+                    rename(&mut t.name);
+                    t.implements_interfaces.iter_mut().for_each(rename);
+                    rename_fields(&mut t.fields);
+                }
+                schema::TypeDefinition::Interface(t) => {
+                    rename(&mut t.name);
+                    t.implements_interfaces.iter_mut().for_each(rename);
+                    rename_fields(&mut t.fields);
+                }
+                schema::TypeDefinition::Union(t) => {
+                    rename(&mut t.name);
+                    t.types.iter_mut().for_each(rename);
+                }
+                schema::TypeDefinition::Enum(t) => rename(&mut t.name),
+                schema::TypeDefinition::InputObject(t) => {
+                    rename(&mut t.name);
+                    rename_input_values(&mut t.fields);
+                }
+            },
+            schema::Definition::TypeExtension(type_ext) => match type_ext {
+                schema::TypeExtension::Scalar(t) => rename(&mut t.name),
+                schema::TypeExtension::Object(t) => {
+                    rename(&mut t.name);
+                    t.implements_interfaces.iter_mut().for_each(rename);
+                    rename_fields(&mut t.fields);
+                }
+                schema::TypeExtension::Interface(t) => {
+                    rename(&mut t.name);
+                    t.implements_interfaces.iter_mut().for_each(rename);
+                    rename_fields(&mut t.fields);
+                }
+                schema::TypeExtension::Union(t) => {
+                    rename(&mut t.name);
+                    t.types.iter_mut().for_each(rename);
+                }
+                schema::TypeExtension::Enum(t) => rename(&mut t.name),
+                schema::TypeExtension::InputObject(t) => {
+                    rename(&mut t.name);
+                    rename_input_values(&mut t.fields);
+                }
+            },
+            schema::Definition::DirectiveDefinition(directive_def) => {
+                rename_input_values(&mut directive_def.arguments);
+            }
+        }
+    }
+}
+
+fn process_definition(
+    definition: &schema::Definition<'_, String>,
+    definition_index: usize,
+    type_map: &mut HashMap<String, TypeInfo>,
+) {
+    match definition {
+        schema::Definition::TypeDefinition(type_def) => {
+            process_type_definition(type_def, definition_index, type_map);
+        }
+        schema::Definition::TypeExtension(type_ext) => {
+            process_type_extension(type_ext, definition_index, type_map);
+        }
+        _ => {}
+    }
+}
+
+/// The types of a schema keyed by name, built either up front or on first lookup
+enum TypeMap {
+    Eager(HashMap<String, TypeInfo>),
+    Lazy(LazyTypeMap),
+}
+
+impl TypeMap {
+    fn get(&self, name: &str) -> Option<&TypeInfo> {
+        match self {
+            TypeMap::Eager(types) => types.get(name),
+            TypeMap::Lazy(types) => types.get(name),
+        }
+    }
+
+    fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Iterates over every type, including root type aliases. In lazy mode this resolves all types.
+    fn iter(&self) -> Box<dyn Iterator<Item = (&String, &TypeInfo)> + '_> {
+        match self {
+            TypeMap::Eager(types) => Box::new(types.iter()),
+            TypeMap::Lazy(types) => Box::new(
+                types
+                    .entries
+                    .keys()
+                    .filter_map(|name| types.get(name).map(|info| (name, info))),
+            ),
+        }
+    }
+}
+
+/// A type map that keeps the parsed schema around and builds each type's info the first time
+/// it's looked up, using the same processing as `build_type_map` on just that type's definitions
+struct LazyTypeMap {
+    schema_doc: schema::Document<'static, String>,
+    entries: HashMap<String, LazyType>,
+}
+
+struct LazyType {
+    /// The name the type is defined with, which differs from its key for root type aliases
+    name: String,
+    /// Indices of the type's definition and extensions within the schema document
+    definitions: Vec<usize>,
+    info: OnceLock<Option<TypeInfo>>,
+}
+
+impl LazyTypeMap {
+    fn new(
+        schema_doc: schema::Document<'static, String>,
+        root_aliases: Vec<(String, String)>,
+    ) -> Self {
+        let mut entries: HashMap<String, LazyType> = HashMap::new();
+
+        // Index the definitions of each type by name, without processing them
+        for (index, definition) in schema_doc.definitions.iter().enumerate() {
+            if let Some(name) = definition_type_name(definition) {
+                entries
+                    .entry(name.to_string())
+                    .or_insert_with(|| LazyType {
+                        name: name.to_string(),
+                        definitions: Vec::new(),
+                        info: OnceLock::new(),
+                    })
+                    .definitions
+                    .push(index);
+            }
+        }
+
+        // Root type aliases resolve from the actual type's definitions
+        for (standard_name, actual_name) in root_aliases {
+            if standard_name != actual_name {
+                let definitions = entries
+                    .get(&actual_name)
+                    .map(|entry| entry.definitions.clone())
+                    .unwrap_or_default();
+                entries.insert(
+                    standard_name,
+                    LazyType {
+                        name: actual_name,
+                        definitions,
+                        info: OnceLock::new(),
+                    },
+                );
+            }
+        }
+
+        LazyTypeMap {
+            schema_doc,
+            entries,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&TypeInfo> {
+        let entry = self.entries.get(name)?;
+        entry
+            .info
+            .get_or_init(|| self.resolve(name, entry))
+            .as_ref()
+    }
+
+    fn resolve(&self, key: &str, entry: &LazyType) -> Option<TypeInfo> {
+        let mut type_map = HashMap::new();
+        for &index in &entry.definitions {
+            process_definition(&self.schema_doc.definitions[index], index, &mut type_map);
+        }
+        for &index in &entry.definitions {
+            for interface_name in implemented_interfaces(&self.schema_doc.definitions[index]) {
+                if let Some(interface_entry) = self.entries.get(interface_name) {
+                    for &interface_index in &interface_entry.definitions {
+                        let definition = &self.schema_doc.definitions[interface_index];
+                        process_definition(definition, interface_index, &mut type_map);
+                    }
+                }
+                inherit_interface_fields(&mut type_map, &entry.name, interface_name);
+            }
+        }
+        create_root_type_alias(&mut type_map, key, &entry.name);
+
+        type_map.remove(key)
+    }
+}
+
+/// The name of the type a definition or extension applies to, if any
+fn definition_type_name<'d>(definition: &'d schema::Definition<'_, String>) -> Option<&'d str> {
+    let name = match definition {
+        schema::Definition::TypeDefinition(type_def) => match type_def {
+            schema::TypeDefinition::Scalar(t) => &t.name,
+            schema::TypeDefinition::Object(t) => &t.name,
+            schema::TypeDefinition::Interface(t) => &t.name,
+            schema::TypeDefinition::Union(t) => &t.name,
+            schema::TypeDefinition::Enum(t) => &t.name,
+            schema::TypeDefinition::InputObject(t) => &t.name,
+        },
+        schema::Definition::TypeExtension(type_ext) => match type_ext {
+            schema::TypeExtension::Scalar(t) => &t.name,
+            schema::TypeExtension::Object(t) => &t.name,
+            schema::TypeExtension::Interface(t) => &t.name,
+            schema::TypeExtension::Union(t) => &t.name,
+            schema::TypeExtension::Enum(t) => &t.name,
+            schema::TypeExtension::InputObject(t) => &t.name,
+        },
+        _ => return None,
+    };
+
+    Some(name.as_str())
+}
+
+/// The interfaces an object type definition or extension declares that it implements
+fn implemented_interfaces<'d>(definition: &'d schema::Definition<'_, String>) -> &'d [String] {
+    match definition {
+        schema::Definition::TypeDefinition(schema::TypeDefinition::Object(obj)) => {
+            &obj.implements_interfaces
+        }
+        schema::Definition::TypeExtension(schema::TypeExtension::Object(obj)) => {
+            &obj.implements_interfaces
+        }
+        _ => &[],
+    }
+}
+
+/// Copies the fields of an interface that a type doesn't declare itself onto the type, so
+/// selections of inherited fields resolve their return types
+fn inherit_interface_fields(
+    type_map: &mut HashMap<String, TypeInfo>,
+    type_name: &str,
+    interface_name: &str,
+) {
+    let Some(interface_fields) = type_map
+        .get(interface_name)
+        .map(|interface| interface.fields.clone())
+    else {
+        return;
+    };
+    if let Some(type_info) = type_map.get_mut(type_name) {
+        for (field_name, field_info) in interface_fields {
+            type_info.fields.entry(field_name).or_insert(field_info);
+        }
+    }
+}
+
+/// Creates an alias for a root operation type if it differs from the standard name
+fn create_root_type_alias(
+    type_map: &mut HashMap<String, TypeInfo>,
+    standard_name: &str,
+    actual_name: &str,
+) {
+    if standard_name != actual_name {
+        let (kind, fields, definition_index, directives) = type_map
+            .get(actual_name)
+            .map(|t| {
+                (
+                    t.kind,
+                    t.fields.clone(),
+                    t.definition_index,
+                    t.directives.clone(),
+                )
+            })
+            .unwrap_or((TypeKind::Object, HashMap::new(), usize::MAX, Vec::new()));
+        type_map.insert(
+            standard_name.to_string(),
+            TypeInfo {
+                name: actual_name.to_string(),
+                kind,
+                fields,
+                values: Vec::new(),
+                definition_index,
+                directives,
+            },
+        );
+    }
+}
+
+fn process_type_definition(
+    type_def: &schema::TypeDefinition<'_, String>,
+    definition_index: usize,
+    type_map: &mut HashMap<String, TypeInfo>,
+) {
+    match type_def {
+        schema::TypeDefinition::Object(obj) => {
+            let fields = extract_fields_from_definition(&obj.fields);
+            insert_extensible_type(
+                type_map,
+                TypeInfo {
+                    name: obj.name.to_string(),
+                    kind: TypeKind::Object,
+                    fields,
+                    values: Vec::new(),
+                    definition_index,
+                    directives: directive_names(&obj.directives),
+                },
+            );
+        }
+        schema::TypeDefinition::Interface(iface) => {
+            let fields = extract_fields_from_definition(&iface.fields);
+            insert_extensible_type(
+                type_map,
+                TypeInfo {
+                    name: iface.name.to_string(),
+                    kind: TypeKind::Interface,
+                    fields,
+                    values: Vec::new(),
+                    definition_index,
+                    directives: directive_names(&iface.directives),
+                },
+            );
+        }
+        schema::TypeDefinition::Union(union) => {
+            insert_extensible_type(
+                type_map,
+                TypeInfo {
+                    name: union.name.to_string(),
+                    kind: TypeKind::Union,
+                    fields: HashMap::new(),
+                    values: Vec::new(),
+                    definition_index,
+                    directives: directive_names(&union.directives),
+                },
+            );
+        }
+        schema::TypeDefinition::Scalar(scalar) => {
+            insert_extensible_type(
+                type_map,
+                TypeInfo {
+                    name: scalar.name.to_string(),
+                    kind: TypeKind::Scalar,
+                    fields: HashMap::new(),
+                    values: Vec::new(),
+                    definition_index,
+                    directives: directive_names(&scalar.directives),
+                },
+            );
+        }
+        schema::TypeDefinition::Enum(enum_type) => {
+            insert_extensible_type(
+                type_map,
+                TypeInfo {
+                    name: enum_type.name.to_string(),
+                    kind: TypeKind::Enum,
+                    fields: HashMap::new(),
+                    values: enum_type
+                        .values
+                        .iter()
+                        .map(|value| value.name.to_string())
+                        .collect(),
+                    definition_index,
+                    directives: directive_names(&enum_type.directives),
+                },
+            );
+        }
+        schema::TypeDefinition::InputObject(input) => {
+            let fields = input
+                .fields
+                .iter()
+                .map(|field| (field.name.to_string(), input_field_info(field)))
+                .collect();
+            insert_extensible_type(
+                type_map,
+                TypeInfo {
+                    name: input.name.to_string(),
+                    kind: TypeKind::InputObject,
+                    fields,
+                    values: Vec::new(),
+                    definition_index,
+                    directives: directive_names(&input.directives),
+                },
+            );
+        }
+    }
+}
+
+/// Extracts field names and their types from a list of field definitions
+fn extract_fields_from_definition(fields: &[schema::Field<String>]) -> HashMap<String, FieldInfo> {
+    fields
+        .iter()
+        .map(|field| (field.name.to_string(), field_info(field)))
+        .collect()
+}
+
+fn field_info(field: &schema::Field<String>) -> FieldInfo {
+    FieldInfo {
+        type_name: get_field_type(&field.field_type),
+        arguments: field
+            .arguments
+            .iter()
+            .map(|argument| {
+                (
+                    argument.name.to_string(),
+                    get_field_type(&argument.value_type),
+                )
+            })
+            .collect(),
+        default_enum_values: Vec::new(),
+        deprecation: deprecation(&field.directives),
+        deprecated_arguments: field
+            .arguments
+            .iter()
+            .filter_map(|argument| {
+                let deprecation = deprecation(&argument.directives)?;
+                Some((argument.name.to_string(), deprecation))
+            })
+            .collect(),
+        requires: federation_field_set(&field.directives, "requires"),
+        provides: federation_field_set(&field.directives, "provides"),
+    }
+}
+
+fn input_field_info(field: &schema::InputValue<String>) -> FieldInfo {
+    let mut default_enum_values = Vec::new();
+    if let Some(default_value) = &field.default_value {
+        collect_enum_literals(default_value, &mut default_enum_values);
+    }
+    FieldInfo {
+        type_name: get_field_type(&field.value_type),
+        arguments: HashMap::new(),
+        default_enum_values,
+        deprecation: deprecation(&field.directives),
+        deprecated_arguments: HashMap::new(),
+        requires: Vec::new(),
+        provides: Vec::new(),
+    }
+}
+
+/// Parses the selections of a federation directive's `fields` argument, such as `id name` in
+/// `@requires(fields: "id name")`. Missing or unparseable field sets have no selections.
+fn federation_field_set(
+    directives: &[schema::Directive<String>],
+    directive_name: &str,
+) -> Vec<query::Selection<'static, String>> {
+    let field_set = directives
+        .iter()
+        .filter(|directive| directive.name == directive_name)
+        .flat_map(|directive| &directive.arguments)
+        .find_map(|(name, value)| match value {
+            schema::Value::String(field_set) if name == "fields" => Some(field_set),
+            _ => None,
+        });
+    let Some(field_set) = field_set else {
+        return Vec::new();
+    };
+
+    let field_set_text = format!("{{ {} }}", field_set);
+    let Ok(field_set_doc) = query::parse_query::<String>(&field_set_text) else {
+        return Vec::new();
+    };
+    match field_set_doc.into_static().definitions.pop() {
+        Some(query::Definition::Operation(query::OperationDefinition::SelectionSet(
+            selection_set,
+        ))) => selection_set.items,
+        _ => Vec::new(),
+    }
+}
+
+/// The `@deprecated` directive a definition is marked with, if any, and the reason it gives
+fn deprecation(directives: &[schema::Directive<String>]) -> Option<Deprecation> {
+    let directive = directives
+        .iter()
+        .find(|directive| directive.name == "deprecated")?;
+    let reason = directive
+        .arguments
+        .iter()
+        .find_map(|(name, value)| match value {
+            schema::Value::String(reason) if name == "reason" => Some(reason.clone()),
+            _ => None,
+        });
+    Some(Deprecation { reason })
+}
+
+/// The names of the directives applied to a definition
+fn directive_names(directives: &[schema::Directive<String>]) -> Vec<String> {
+    directives
+        .iter()
+        .map(|directive| directive.name.to_string())
+        .collect()
+}
+
+fn process_type_extension(
+    type_ext: &schema::TypeExtension<'_, String>,
+    definition_index: usize,
+    type_map: &mut HashMap<String, TypeInfo>,
+) {
+    let (name, kind, directives) = match type_ext {
+        schema::TypeExtension::Object(obj) => (&obj.name, TypeKind::Object, &obj.directives),
+        schema::TypeExtension::Interface(iface) => {
+            (&iface.name, TypeKind::Interface, &iface.directives)
+        }
+        schema::TypeExtension::InputObject(input) => {
+            (&input.name, TypeKind::InputObject, &input.directives)
+        }
+        schema::TypeExtension::Enum(enum_type) => {
+            (&enum_type.name, TypeKind::Enum, &enum_type.directives)
+        }
+        // Union members are tracked by `build_union_members_map`
+        schema::TypeExtension::Union(union) => (&union.name, TypeKind::Union, &union.directives),
+        schema::TypeExtension::Scalar(scalar) => {
+            (&scalar.name, TypeKind::Scalar, &scalar.directives)
+        }
+    };
+
+    let entry = type_map
+        .entry(name.to_string())
+        .or_insert_with(|| TypeInfo {
+            name: name.to_string(),
+            kind,
+            fields: HashMap::new(),
+            values: Vec::new(),
+            definition_index,
+            directives: Vec::new(),
+        });
+    entry.directives.extend(directive_names(directives));
+    match type_ext {
+        schema::TypeExtension::Object(schema::ObjectTypeExtension { fields, .. })
+        | schema::TypeExtension::Interface(schema::InterfaceTypeExtension { fields, .. }) => {
+            for field in fields {
+                entry
+                    .fields
+                    .insert(field.name.to_string(), field_info(field));
+            }
+        }
+        schema::TypeExtension::InputObject(input) => {
+            for field in &input.fields {
+                entry
+                    .fields
+                    .insert(field.name.to_string(), input_field_info(field));
+            }
+        }
+        schema::TypeExtension::Enum(enum_type) => {
+            entry
+                .values
+                .extend(enum_type.values.iter().map(|value| value.name.to_string()));
+        }
+        schema::TypeExtension::Union(_) | schema::TypeExtension::Scalar(_) => {}
+    }
+}
+
+/// Inserts a type's definition, keeping the fields, enum values and directives added by any
+/// extensions of it that come before the definition, such as in an earlier schema file
+fn insert_extensible_type(type_map: &mut HashMap<String, TypeInfo>, mut type_info: TypeInfo) {
+    if let Some(extended) = type_map.remove(&type_info.name) {
+        type_info.fields.extend(extended.fields);
+        type_info.values.extend(extended.values);
+        type_info.directives.extend(extended.directives);
+    }
+    type_map.insert(type_info.name.clone(), type_info);
+}
+
+fn get_field_type(field_type: &schema::Type<'_, String>) -> String {
+    match field_type {
+        schema::Type::NamedType(name) => name.to_string(),
+        schema::Type::NonNullType(inner) => get_field_type(inner),
+        schema::Type::ListType(inner) => get_field_type(inner),
+    }
+}
+
+/// A selection of a field in a document, for locating it in the document's source text
+struct FieldOccurrence {
+    /// The canonical name of the field's parent type
+    type_name: String,
+    field_name: String,
+    alias: Option<String>,
+    /// Where the selection starts, at its alias if it has one
+    position: graphql_parser::Pos,
+}
+
+/// State for extracting coordinates from a single parsed document
+struct Extraction<'a, 'd> {
+    type_map: &'a TypeMap,
+    options: &'a ExtractionOptions,
+    directives: &'a HashMap<String, DirectiveInfo>,
+    implementations: &'a HashMap<String, Vec<String>>,
+    union_members: &'a HashMap<String, Vec<String>>,
+    excluded_coordinates: &'a HashSet<String>,
+    scalar_predicate: Option<&'a ScalarPredicate>,
+    query_doc: &'a query::Document<'d, String>,
+    coordinates: &'a mut dyn CoordinateSink,
+    /// Variables referenced by the current operation, recorded when `only_used_variables` is set
+    /// or the variables were requested
+    used_variables: HashSet<String>,
+    /// Counters recorded only when stats were requested, keeping the default path uninstrumented
+    stats: Option<ExtractionStats>,
+    /// Variables referenced by each operation, recorded only when they were requested
+    variables: Option<Vec<UsedVariable>>,
+    /// Deprecated fields, arguments and input fields used, recorded only when requested
+    deprecated_usages: Option<Vec<DeprecatedUsage>>,
+    /// Coordinates attributed to each operation and its fragments, recorded only when requested
+    attributed: Option<Vec<AttributedCoordinates>>,
+    /// The smallest depth each coordinate is used at, recorded only when requested
+    min_depths: Option<HashMap<String, u32>>,
+    /// The deepest depth a field is selected at, recorded only when requested
+    max_depth: Option<u32>,
+    /// How the document uses each coordinate, recorded only when requested
+    origins: Option<HashMap<String, BTreeSet<CoordinateOrigin>>>,
+    /// How coordinates currently being extracted are used
+    origin: CoordinateOrigin,
+    /// Unknown fields, arguments, fragments and type conditions, recorded only when requested
+    issues: Option<Vec<Issue>>,
+    /// The names of spread fragments, which are recorded instead of expanded when requested
+    spreads: Option<HashSet<String>>,
+    /// The names of spread fragments without a definition, recorded only when requested
+    unresolved_fragments: Option<BTreeSet<String>>,
+    /// Coordinates of selected fields returning an interface or union, recorded only when
+    /// requested
+    abstract_returning_fields: Option<HashSet<String>>,
+    /// Coordinates of selected root fields, recorded only when requested. Their selections
+    /// aren't traversed.
+    root_fields: Option<HashSet<String>>,
+    /// Every selection of a field whose coordinate is extracted, recorded only when requested
+    field_occurrences: Option<Vec<FieldOccurrence>>,
+    /// The selection depth coordinates are currently being extracted at
+    depth: u32,
+    /// The fragment currently being traversed, to attribute coordinates to
+    current_fragment: Option<&'a str>,
+    /// Fragments currently being expanded, innermost last. Spreading one of them again would
+    /// expand a fragment cycle forever, so those spreads are skipped.
+    fragment_path: Vec<&'a str>,
+    /// Whether a spread was skipped for completing a fragment cycle, which leaves the
+    /// coordinates recorded for the fragments being expanded incomplete
+    found_fragment_cycle: bool,
+    /// The coordinates each fragment emitted when expanded under a parent type in the current
+    /// operation, replayed into the sink when it's spread there again
+    fragment_coordinates: HashMap<(&'a str, String), Vec<String>>,
+    /// The coordinates emitted so far by each fragment being expanded, innermost last, recorded
+    /// only while fragment coordinates can be replayed
+    fragment_recordings: Vec<Vec<String>>,
+    /// Whether fragments spread again may replay their recorded coordinates. Streaming into a
+    /// sink turns this off so no copies of the coordinates are kept while traversing.
+    replay_fragments: bool,
+    /// The types and names of the fields whose federation field sets are being expanded
+    expanding_field_sets: HashSet<(String, String)>,
+    /// Shared fragments that spreads not defined in the document resolve to
+    fragment_registry: Option<&'a FragmentRegistry>,
+    /// The values of the variables the document is executed with, whose input fields are
+    /// extracted when given
+    variable_values: Option<&'a BTreeMap<String, query::Value<'static, String>>>,
+    /// Coordinates of selected fields missing from their parent type, recorded when
+    /// `error_on_unknown_fields` is set
+    unknown_fields: Vec<String>,
+    /// Types of fragment type conditions missing from the schema, recorded when
+    /// `error_on_unknown_type_conditions` is set
+    unknown_type_conditions: Vec<String>,
+    /// Coordinates of scalar and enum fields with selection sets, recorded when
+    /// `error_on_subselection_of_scalar` is set
+    scalar_subselections: Vec<String>,
+}
+
+impl<'a, 'd> Extraction<'a, 'd> {
+    fn new(
+        schema: &'a ParsedSchema,
+        query_doc: &'a query::Document<'d, String>,
+        coordinates: &'a mut dyn CoordinateSink,
+    ) -> Self {
+        Extraction {
+            type_map: &schema.data.type_map,
+            options: &schema.data.options,
+            directives: &schema.data.directives,
+            implementations: &schema.data.implementations,
+            union_members: &schema.data.union_members,
+            excluded_coordinates: &schema.data.excluded_coordinates,
+            scalar_predicate: schema.data.scalar_predicate.as_ref(),
+            query_doc,
+            coordinates,
+            used_variables: HashSet::new(),
+            stats: None,
+            variables: None,
+            deprecated_usages: None,
+            attributed: None,
+            min_depths: None,
+            max_depth: None,
+            origins: None,
+            origin: CoordinateOrigin::Selection,
+            issues: None,
+            spreads: None,
+            unresolved_fragments: None,
+            abstract_returning_fields: None,
+            root_fields: None,
+            field_occurrences: None,
+            depth: 0,
+            current_fragment: None,
+            fragment_path: Vec::new(),
+            found_fragment_cycle: false,
+            fragment_coordinates: HashMap::new(),
+            fragment_recordings: Vec::new(),
+            replay_fragments: true,
+            expanding_field_sets: HashSet::new(),
+            fragment_registry: None,
+            variable_values: None,
+            unknown_fields: Vec::new(),
+            unknown_type_conditions: Vec::new(),
+            scalar_subselections: Vec::new(),
+        }
+    }
+
+    fn extract_document(&mut self) -> Result<()> {
+        let query_doc = self.query_doc;
+        self.options.check_operation_count(query_doc)?;
+
+        // Extract coordinates from the document
+        for definition in &query_doc.definitions {
+            match definition {
+                query::Definition::Operation(operation) => {
+                    self.extract_from_operation(operation)?;
+                }
+                query::Definition::Fragment(_fragment) => {
+                    // Fragments are processed when referenced in operations
+                }
+            }
+        }
+
+        self.check_recorded_errors()
+    }
+
+    /// Returns an error for the unknown fields, type conditions and scalar subselections
+    /// recorded by the strict options
+    fn check_recorded_errors(&mut self) -> Result<()> {
+        check_recorded_errors(
+            std::mem::take(&mut self.unknown_fields),
+            std::mem::take(&mut self.unknown_type_conditions),
+            std::mem::take(&mut self.scalar_subselections),
+        )
+    }
+
+    fn extract_from_operation(
+        &mut self,
+        operation: &query::OperationDefinition<String>,
+    ) -> Result<()> {
+        let empty_variables = Vec::new();
+        let empty_directives = Vec::new();
+
+        if let Some(attributed) = &mut self.attributed {
+            let operation_name = match operation {
+                query::OperationDefinition::Query(q) => q.name.clone(),
+                query::OperationDefinition::Mutation(m) => m.name.clone(),
+                query::OperationDefinition::Subscription(s) => s.name.clone(),
+                query::OperationDefinition::SelectionSet(_) => None,
+            };
+            attributed.push(AttributedCoordinates {
+                operation: operation_name,
+                ..Default::default()
+            });
+        }
+
+        let (root_type, selection_set, variable_defs, directives) = match operation {
+            query::OperationDefinition::Query(q) => (
+                "Query",
+                &q.selection_set,
+                &q.variable_definitions,
+                &q.directives,
+            ),
+            query::OperationDefinition::Mutation(m) => (
+                "Mutation",
+                &m.selection_set,
+                &m.variable_definitions,
+                &m.directives,
+            ),
+            query::OperationDefinition::Subscription(s) => {
+                // Only schemas that define a subscription root type can execute subscriptions
+                if !self.type_map.contains_key("Subscription") {
+                    return Err(Error::from_reason(SUBSCRIPTION_NOT_CONFIGURED));
+                }
+                if self.options.single_subscription_root.unwrap_or(false) {
+                    self.check_single_subscription_root(&s.selection_set.items)?;
+                }
+                (
+                    "Subscription",
+                    &s.selection_set,
+                    &s.variable_definitions,
+                    &s.directives,
+                )
+            }
+            query::OperationDefinition::SelectionSet(ss) => {
+                ("Query", ss, &empty_variables, &empty_directives)
+            }
+        };
+
+        self.used_variables.clear();
+        self.fragment_coordinates.clear();
+        self.depth = 0;
+        self.extract_directives(directives);
+
+        // Extract coordinates from selection set
+        self.extract_from_selection_set(&selection_set.items, root_type, 1);
+
+        if let Some(variables) = &mut self.variables {
+            for var_def in variable_defs {
+                let variable = UsedVariable {
+                    name: var_def.name.to_string(),
+                    type_name: var_def.var_type.to_string(),
+                };
+                if self.used_variables.contains(&var_def.name) && !variables.contains(&variable) {
+                    variables.push(variable);
+                }
+            }
+        }
+
+        // Extract input types from variable definitions, once usages are known
+        self.depth = 0;
+        self.origin = CoordinateOrigin::Variable;
+        let only_used_variables = self.options.only_used_variables.unwrap_or(false);
+        for var_def in variable_defs {
+            if !only_used_variables || self.used_variables.contains(&var_def.name) {
+                self.extract_input_types(&var_def.var_type);
+                if self.options.all_input_fields_from_variables() {
+                    let type_name = get_field_type(&var_def.var_type);
+                    self.extract_all_input_fields(&type_name, &mut HashSet::new());
+                }
+                let variable_value = self
+                    .variable_values
+                    .and_then(|variable_values| variable_values.get(&var_def.name));
+                if let Some(value) = variable_value {
+                    self.extract_input_fields(&get_field_type(&var_def.var_type), value);
+                }
+            }
+        }
+        self.origin = CoordinateOrigin::Selection;
+
+        Ok(())
+    }
+
+    /// Finds the root field coordinate of the document's first subscription, if it has one
+    fn subscription_entry_point(&self) -> Result<Option<String>> {
+        for definition in &self.query_doc.definitions {
+            if let query::Definition::Operation(query::OperationDefinition::Subscription(s)) =
+                definition
+            {
+                let Some(root_type) = self.type_map.get("Subscription") else {
+                    return Err(Error::from_reason(SUBSCRIPTION_NOT_CONFIGURED));
+                };
+                self.check_single_subscription_root(&s.selection_set.items)?;
+
+                let entry_point = self
+                    .first_field_name(&s.selection_set.items)
+                    .map(|field_name| {
+                        let coordinate = self.options.field_coordinate(&root_type.name, field_name);
+                        self.options
+                            .case_normalized(self.options.prefixed(coordinate))
+                    });
+                return Ok(entry_point);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The name of the first field in a selection set, looking through fragments
+    fn first_field_name<'s>(&self, selection_set: &'s [query::Selection<String>]) -> Option<&'s str>
+    where
+        'a: 's,
+    {
+        selection_set.iter().find_map(|selection| match selection {
+            query::Selection::Field(field) => Some(field.name.as_str()),
+            query::Selection::FragmentSpread(spread) => {
+                self.query_doc
+                    .definitions
+                    .iter()
+                    .find_map(|definition| match definition {
+                        query::Definition::Fragment(fragment)
+                            if fragment.name == spread.fragment_name =>
+                        {
+                            self.first_field_name(&fragment.selection_set.items)
+                        }
+                        _ => None,
+                    })
+            }
+            query::Selection::InlineFragment(inline) => {
+                self.first_field_name(&inline.selection_set.items)
+            }
+        })
+    }
+
+    fn check_single_subscription_root(
+        &self,
+        selection_set: &[query::Selection<String>],
+    ) -> Result<()> {
+        let mut response_keys = HashSet::new();
+        self.collect_response_keys(selection_set, &mut response_keys);
+
+        if response_keys.len() > 1 {
+            let mut response_keys: Vec<&str> = response_keys.into_iter().collect();
+            response_keys.sort_unstable();
+            return Err(Error::from_reason(format!(
+                "Subscription must select only one root field, but selects: {}",
+                response_keys.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Collects the response keys (alias or name) of the fields in a selection set, looking
+    /// through fragments
+    fn collect_response_keys<'s>(
+        &self,
+        selection_set: &'s [query::Selection<String>],
+        response_keys: &mut HashSet<&'s str>,
+    ) where
+        'a: 's,
+    {
+        for selection in selection_set {
+            match selection {
+                query::Selection::Field(field) => {
+                    response_keys.insert(field.alias.as_deref().unwrap_or(&field.name));
+                }
+                query::Selection::FragmentSpread(spread) => {
+                    let mut is_defined = false;
+                    for definition in &self.query_doc.definitions {
+                        if let query::Definition::Fragment(fragment) = definition {
+                            if fragment.name == spread.fragment_name {
+                                is_defined = true;
+                                self.collect_response_keys(
+                                    &fragment.selection_set.items,
+                                    response_keys,
+                                );
+                            }
+                        }
+                    }
+                    if !is_defined {
+                        let registry_fragment = self
+                            .fragment_registry
+                            .and_then(|registry| registry.fragments.get(&spread.fragment_name));
+                        if let Some(fragment) = registry_fragment {
+                            self.collect_response_keys(
+                                &fragment.selection_set.items,
+                                response_keys,
+                            );
+                        }
+                    }
+                }
+                query::Selection::InlineFragment(inline) => {
+                    self.collect_response_keys(&inline.selection_set.items, response_keys);
+                }
+            }
+        }
+    }
+
+    /// Records an issue for a field its known parent type doesn't declare, or for arguments the
+    /// field doesn't declare
+    fn record_field_issues(
+        &mut self,
+        parent_type: &str,
+        canonical_parent_type: &str,
+        field: &query::Field<String>,
+    ) {
+        let Some(type_info) = self.type_map.get(parent_type) else {
+            return;
+        };
+        let Some(issues) = &mut self.issues else {
+            return;
+        };
+
+        let Some(field_info) = type_info.fields.get(&field.name) else {
+            if !field.name.starts_with("__") {
+                let coordinate = self
+                    .options
+                    .field_coordinate(canonical_parent_type, &field.name);
+                issues.push(Issue::new(
+                    IssueKind::UnknownField,
+                    format!("Unknown field \"{}\"", coordinate),
+                    field.position,
+                ));
+            }
+            return;
+        };
+        for (argument_name, _) in &field.arguments {
+            if !field_info.arguments.contains_key(argument_name) {
+                let coordinate = self.options.argument_coordinate(
+                    canonical_parent_type,
+                    &field.name,
+                    argument_name,
+                );
+                issues.push(Issue::new(
+                    IssueKind::UnknownArgument,
+                    format!("Unknown argument \"{}\"", coordinate),
+                    field.position,
+                ));
+            }
+        }
+    }
+
+    /// Records a spread of a fragment that has no definition, where requested
+    fn record_unresolved_fragment(&mut self, spread: &query::FragmentSpread<String>) {
+        if let Some(issues) = &mut self.issues {
+            issues.push(Issue::new(
+                IssueKind::UnknownFragment,
+                format!("Unknown fragment \"{}\"", spread.fragment_name),
+                spread.position,
+            ));
+        }
+        if let Some(unresolved_fragments) = &mut self.unresolved_fragments {
+            unresolved_fragments.insert(spread.fragment_name.clone());
+        }
+    }
+
+    /// Records an issue if the schema doesn't define the named type
+    fn record_unknown_type(&mut self, type_name: &str, position: graphql_parser::Pos) {
+        let is_unknown = !self.is_scalar(type_name) && !self.type_map.contains_key(type_name);
+        if let (true, Some(issues)) = (is_unknown, &mut self.issues) {
+            issues.push(Issue::new(
+                IssueKind::UnknownType,
+                format!("Unknown type \"{}\"", type_name),
+                position,
+            ));
+        }
+    }
+
+    /// Records a fragment's type condition if `error_on_unknown_type_conditions` is set and the
+    /// schema doesn't define the type
+    fn record_unknown_type_condition(&mut self, type_name: &str) {
+        if self
+            .options
+            .error_on_unknown_type_conditions
+            .unwrap_or(false)
+            && !self.type_map.contains_key(type_name)
+        {
+            self.unknown_type_conditions.push(type_name.to_string());
+        }
+    }
+
+    /// Records the field's coordinate if its parent type is known but doesn't declare it.
+    /// Introspection meta-fields such as `__typename` are never unknown.
+    fn record_unknown_field(
+        &mut self,
+        parent_type: &str,
+        canonical_parent_type: &str,
+        field_name: &str,
+    ) {
+        if field_name.starts_with("__") {
+            return;
+        }
+
+        let is_unknown = self
+            .type_map
+            .get(parent_type)
+            .is_some_and(|info| !info.fields.contains_key(field_name));
+        if is_unknown {
+            let coordinate = self
+                .options
+                .field_coordinate(canonical_parent_type, field_name);
+            self.unknown_fields.push(coordinate);
+        }
+    }
+
+    /// Records the field, the supplied arguments and the input fields of object values passed to
+    /// them, where they're deprecated
+    fn record_deprecated_usages(
+        &mut self,
+        parent_type: &str,
+        canonical_parent_type: &str,
+        field: &query::Field<String>,
+    ) {
+        let type_map = self.type_map;
+        let Some(field_info) = type_map
+            .get(parent_type)
+            .and_then(|info| info.fields.get(&field.name))
+        else {
+            return;
+        };
+
+        if let Some(deprecation) = &field_info.deprecation {
+            let coordinate = self
+                .options
+                .field_coordinate(canonical_parent_type, &field.name);
+            self.record_deprecated_usage(coordinate, CoordinateKind::OutputField, deprecation);
+        }
+
+        for (argument_name, value) in &field.arguments {
+            if let Some(deprecation) = field_info.deprecated_arguments.get(argument_name) {
+                let coordinate = self.options.argument_coordinate(
+                    canonical_parent_type,
+                    &field.name,
+                    argument_name,
+                );
+                self.record_deprecated_usage(coordinate, CoordinateKind::Argument, deprecation);
+            }
+            if let Some(argument_type) = field_info.arguments.get(argument_name) {
+                self.record_deprecated_input_fields(argument_type, value);
+            }
+        }
+    }
+
+    /// Records the deprecated input fields set in an object value of an input type, recursing
+    /// into nested objects and lists
+    fn record_deprecated_input_fields(&mut self, type_name: &str, value: &query::Value<String>) {
+        match value {
+            query::Value::Object(fields) => {
+                let type_map = self.type_map;
+                let Some(type_info) = type_map.get(type_name) else {
+                    return;
+                };
+                for (field_name, field_value) in fields {
+                    if let Some(field_info) = type_info.fields.get(field_name) {
+                        if let Some(deprecation) = &field_info.deprecation {
+                            let coordinate = self.options.field_coordinate(type_name, field_name);
+                            self.record_deprecated_usage(
+                                coordinate,
+                                CoordinateKind::InputField,
+                                deprecation,
+                            );
+                        }
+                        self.record_deprecated_input_fields(&field_info.type_name, field_value);
+                    }
+                }
+            }
+            query::Value::List(items) => {
+                for item in items {
+                    self.record_deprecated_input_fields(type_name, item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn record_deprecated_usage(
+        &mut self,
+        coordinate: String,
+        kind: CoordinateKind,
+        deprecation: &Deprecation,
+    ) {
+        let coordinate = self.options.prefixed(coordinate);
+        if let Some(usages) = &mut self.deprecated_usages {
+            if !usages.iter().any(|usage| usage.coordinate == coordinate) {
+                usages.push(DeprecatedUsage {
+                    coordinate,
+                    kind,
+                    reason: deprecation.reason.clone(),
+                });
+            }
+        }
+    }
+
+    /// Adds a coordinate, attributing it to the current operation or fragment when requested
+    fn add_coordinate(&mut self, coordinate: String) {
+        if self.excluded_coordinates.contains(&coordinate) {
+            return;
+        }
+        let coordinate = self.options.prefixed(coordinate);
+        if let Some(operation) = self
+            .attributed
+            .as_mut()
+            .and_then(|attributed| attributed.last_mut())
+        {
+            match self.current_fragment {
+                Some(fragment_name) => operation
+                    .fragments
+                    .entry(fragment_name.to_string())
+                    .or_default()
+                    .push(coordinate.clone()),
+                None => operation.direct.push(coordinate.clone()),
+            }
+        }
+        if let Some(min_depths) = &mut self.min_depths {
+            min_depths
+                .entry(coordinate.clone())
+                .and_modify(|min_depth| *min_depth = (*min_depth).min(self.depth))
+                .or_insert(self.depth);
+        }
+        if let Some(origins) = &mut self.origins {
+            origins
+                .entry(coordinate.clone())
+                .or_default()
+                .insert(self.origin);
+        }
+        for recording in &mut self.fragment_recordings {
+            recording.push(coordinate.clone());
+        }
+        self.coordinates.emit(coordinate);
+    }
+
+    /// Whether a fragment spread again under the same parent type can replay the coordinates it
+    /// emitted the first time instead of being traversed again. Depth windows and collectors
+    /// that observe each expansion, such as stats and min depths, need the traversal.
+    fn can_replay_fragments(&self) -> bool {
+        self.replay_fragments
+            && self.options.min_depth.is_none()
+            && self.options.max_depth.is_none()
+            && self.stats.is_none()
+            && self.attributed.is_none()
+            && self.min_depths.is_none()
+            && self.max_depth.is_none()
+            && self.origins.is_none()
+            && self.issues.is_none()
+            && self.deprecated_usages.is_none()
+            && self.field_occurrences.is_none()
+    }
+
+    /// Adds a field's coordinate under each interface of its type that declares the field, or
+    /// as `coordinate` if none do
+    fn add_collapsed_field_coordinate(
+        &mut self,
+        type_name: &str,
+        field_name: &str,
+        coordinate: String,
+    ) {
+        let type_map = self.type_map;
+        let interfaces: Vec<&str> = self
+            .implementations
+            .iter()
+            .filter(|(_, members)| members.iter().any(|member| member == type_name))
+            .map(|(interface_name, _)| interface_name.as_str())
+            .filter(|interface_name| {
+                type_map
+                    .get(interface_name)
+                    .is_some_and(|info| info.fields.contains_key(field_name))
+            })
+            .collect();
+
+        if interfaces.is_empty() {
+            self.add_coordinate(coordinate);
+        }
+        for interface_name in interfaces {
+            let coordinate = self.options.field_coordinate(interface_name, field_name);
+            self.add_coordinate(coordinate);
+        }
+    }
+
+    /// The implementations of an interface that declare a field the interface itself doesn't.
+    /// Empty if the type isn't an interface or declares the field.
+    fn members_declaring_field(&self, type_name: &str, field_name: &str) -> Vec<&'a str> {
+        let type_map = self.type_map;
+        let Some(type_info) = type_map.get(type_name) else {
+            return Vec::new();
+        };
+        if type_info.kind != TypeKind::Interface || type_info.fields.contains_key(field_name) {
+            return Vec::new();
+        }
+
+        self.implementations
+            .get(&type_info.name)
+            .into_iter()
+            .flatten()
+            .filter(|member| {
+                type_map
+                    .get(member)
+                    .is_some_and(|info| info.fields.contains_key(field_name))
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// The object types an interface or union can resolve to. Empty for other kinds of type.
+    fn possible_types(&self, type_name: &str) -> Vec<&'a str> {
+        possible_object_types(
+            self.type_map,
+            self.implementations,
+            self.union_members,
+            type_name,
+        )
+    }
+
+    /// Records a field's coordinate if the field returns an interface or union type
+    fn record_abstract_returning_field(
+        &mut self,
+        parent_type: &str,
+        coordinate: &str,
+        field: &query::Field<String>,
+    ) {
+        let returns_abstract_type = self
+            .type_map
+            .get(parent_type)
+            .and_then(|type_info| type_info.fields.get(&field.name))
+            .and_then(|field_info| self.type_map.get(&field_info.type_name))
+            .is_some_and(|info| matches!(info.kind, TypeKind::Interface | TypeKind::Union));
+        if let (true, Some(fields)) = (returns_abstract_type, &mut self.abstract_returning_fields) {
+            fields.insert(coordinate.to_string());
+        }
+    }
+
+    /// Whether a type is a built-in scalar or accepted by the scalar predicate
+    fn is_scalar(&self, type_name: &str) -> bool {
+        is_scalar(type_name)
+            || self
+                .scalar_predicate
+                .is_some_and(|predicate| predicate(type_name))
+    }
+
+    /// Whether a type is a scalar or an enum, which can't have selections
+    fn is_leaf_type(&self, type_name: &str) -> bool {
+        self.is_scalar(type_name)
+            || self
+                .type_map
+                .get(type_name)
+                .is_some_and(|info| matches!(info.kind, TypeKind::Scalar | TypeKind::Enum))
+    }
+
+    fn extract_input_types(&mut self, var_type: &query::Type<String>) {
+        match var_type {
+            query::Type::NamedType(name) => {
+                // Only add if it's an input type (not a scalar or enum)
+                let is_input_object = self
+                    .type_map
+                    .get(name)
+                    .is_some_and(|info| info.kind == TypeKind::InputObject);
+                if is_input_object {
+                    self.add_coordinate(name.to_string());
+                }
+            }
+            query::Type::NonNullType(inner) => {
+                self.extract_input_types(inner);
+            }
+            query::Type::ListType(inner) => {
+                self.extract_input_types(inner);
+            }
+        }
+    }
+
+    /// Extracts the coordinate of every field of an input type, recursing into nested input types,
+    /// along with the enum types and values that fields default to
+    fn extract_all_input_fields(&mut self, type_name: &str, visited: &mut HashSet<String>) {
+        let type_map = self.type_map;
+        let Some(type_info) = type_map.get(type_name) else {
+            return;
+        };
+        if type_info.kind != TypeKind::InputObject || !visited.insert(type_name.to_string()) {
+            return;
+        }
+
+        for (field_name, field_info) in &type_info.fields {
+            let coordinate = self.options.field_coordinate(type_name, field_name);
+            self.add_coordinate(coordinate);
+
+            for enum_value in &field_info.default_enum_values {
+                self.add_coordinate(field_info.type_name.clone());
+                let coordinate = self
+                    .options
+                    .field_coordinate(&field_info.type_name, enum_value);
+                self.add_coordinate(coordinate);
+            }
+
+            let is_input_object = type_map
+                .get(&field_info.type_name)
+                .is_some_and(|info| info.kind == TypeKind::InputObject);
+            if is_input_object {
+                self.add_coordinate(field_info.type_name.clone());
+                self.extract_all_input_fields(&field_info.type_name, visited);
+            }
+        }
+    }
+
+    /// Extracts argument coordinates, and the enum types and values passed to them, for the
+    /// arguments supplied to a field
+    fn extract_arguments(
+        &mut self,
+        parent_type: &str,
+        canonical_parent_type: &str,
+        field: &query::Field<String>,
+    ) {
+        let include_arguments = self.options.include_arguments.unwrap_or(false);
+        let include_enum_values = self.options.include_enum_values.unwrap_or(false);
+        let include_input_fields = self.options.include_input_fields.unwrap_or(false);
+        if !include_arguments && !include_enum_values && !include_input_fields {
+            return;
+        }
+
+        let type_map = self.type_map;
+        let field_info = type_map
+            .get(parent_type)
+            .and_then(|info| info.fields.get(&field.name));
+
+        let parent_origin = std::mem::replace(&mut self.origin, CoordinateOrigin::Argument);
+        for (argument_name, value) in &field.arguments {
+            if include_arguments {
+                let coordinate = self.options.argument_coordinate(
+                    canonical_parent_type,
+                    &field.name,
+                    argument_name,
+                );
+                self.add_coordinate(coordinate);
+            }
+
+            let argument_type = field_info.and_then(|info| info.arguments.get(argument_name));
+            if let Some(argument_type) = argument_type {
+                if include_enum_values {
+                    self.extract_enum_values(argument_type, value);
+                }
+                if include_input_fields {
+                    self.extract_input_fields(argument_type, value);
+                }
+            }
+        }
+        self.origin = parent_origin;
+    }
+
+    /// Extracts the coordinates of the input object fields set in a value of an input type,
+    /// recursing into lists and nested objects
+    fn extract_input_fields(&mut self, type_name: &str, value: &query::Value<String>) {
+        match value {
+            query::Value::Object(fields) => {
+                let type_map = self.type_map;
+                let Some(type_info) = type_map
+                    .get(type_name)
+                    .filter(|info| info.kind == TypeKind::InputObject)
+                else {
+                    return;
+                };
+                for (field_name, field_value) in fields {
+                    let coordinate = self.options.field_coordinate(type_name, field_name);
+                    self.add_coordinate(coordinate);
+                    if let Some(field_info) = type_info.fields.get(field_name) {
+                        self.extract_input_fields(&field_info.type_name, field_value);
+                    }
+                }
+            }
+            query::Value::List(items) => {
+                for item in items {
+                    self.extract_input_fields(type_name, item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Extracts the enum type and literal enum values of a value, if its type is an enum
+    fn extract_enum_values(&mut self, type_name: &str, value: &query::Value<String>) {
+        let is_enum = self
+            .type_map
+            .get(type_name)
+            .is_some_and(|info| info.kind == TypeKind::Enum);
+        if !is_enum {
+            return;
+        }
+
+        self.add_coordinate(type_name.to_string());
+        match value {
+            query::Value::Enum(enum_value) => {
+                let coordinate = self.options.field_coordinate(type_name, enum_value);
+                self.add_coordinate(coordinate);
+            }
+            query::Value::List(items) => {
+                for item in items {
+                    self.extract_enum_values(type_name, item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn extract_directives(&mut self, directives: &[query::Directive<String>]) {
+        let include_directives = self.options.include_directives.unwrap_or(false);
+
+        let include_directive_arguments = self.options.include_directive_arguments.unwrap_or(false);
+        let include_input_fields = self.options.include_input_fields.unwrap_or(false);
+
+        let parent_origin = std::mem::replace(&mut self.origin, CoordinateOrigin::Directive);
+        for directive in directives {
+            if include_directives {
+                self.add_coordinate(format!("@{}", directive.name));
+            }
+
+            let directive_info = self.directives.get(&directive.name);
+            for (argument_name, value) in &directive.arguments {
+                if include_directive_arguments {
+                    self.add_coordinate(directive_argument_coordinate(
+                        &directive.name,
+                        argument_name,
+                    ));
+                }
+                if include_input_fields {
+                    let argument_type =
+                        directive_info.and_then(|info| info.arguments.get(argument_name));
+                    if let Some(argument_type) = argument_type {
+                        self.extract_input_fields(argument_type, value);
+                    }
+                }
+            }
+
+            self.record_variables(&directive.arguments);
+        }
+        self.origin = parent_origin;
+    }
+
+    /// Records the variables referenced by `arguments`, when `only_used_variables` or the
+    /// requested variables need them
+    fn record_variables(&mut self, arguments: &[(String, query::Value<String>)]) {
+        if !self.options.only_used_variables.unwrap_or(false) && self.variables.is_none() {
+            return;
+        }
+
+        for (_, value) in arguments {
+            collect_variables(value, &mut self.used_variables);
+        }
+    }
+
+    /// Extracts the fields named by a field's `@requires` field set, as siblings of the field,
+    /// and by its `@provides` field set, as selections of the field. Field sets that lead back to
+    /// a field whose field sets are already being expanded aren't expanded again.
+    fn extract_federation_field_sets(&mut self, parent_type: &str, field_name: &str, depth: u32) {
+        let type_map = self.type_map;
+        let Some(field_info) = type_map
+            .get(parent_type)
+            .and_then(|type_info| type_info.fields.get(field_name))
+        else {
+            return;
+        };
+        let field = (parent_type.to_string(), field_name.to_string());
+        if !self.expanding_field_sets.insert(field.clone()) {
+            return;
+        }
+
+        if !field_info.requires.is_empty() {
+            self.extract_from_selection_set(&field_info.requires, parent_type, depth);
+        }
+        if !field_info.provides.is_empty() {
+            self.extract_from_selection_set(&field_info.provides, &field_info.type_name, depth + 1);
+        }
+        self.expanding_field_sets.remove(&field);
+    }
+
+    fn extract_from_selection_set(
+        &mut self,
+        selection_set: &[query::Selection<String>],
+        parent_type: &str,
+        depth: u32,
+    ) {
+        let evaluate_static_conditions = self.options.evaluate_static_conditions.unwrap_or(false);
+        if let Some(stats) = &mut self.stats {
+            stats.max_fanout = stats.max_fanout.max(selection_set.len() as u32);
+        }
+
+        for selection in selection_set {
+            self.depth = depth;
+            if evaluate_static_conditions && is_statically_excluded(selection_directives(selection))
+            {
+                continue;
+            }
+
+            match selection {
+                query::Selection::Field(field) => {
+                    if self
+                        .options
+                        .attribute_interface_fields_to_members
+                        .unwrap_or(false)
+                    {
+                        let members = self.members_declaring_field(parent_type, &field.name);
+                        if !members.is_empty() {
+                            for member in members {
+                                self.extract_from_selection_set(
+                                    std::slice::from_ref(selection),
+                                    member,
+                                    depth,
+                                );
+                            }
+                            continue;
+                        }
+                    }
+
+                    if field.name == "__typename"
+                        && self.options.typename_attribution == Some(TypenameAttribution::Concrete)
+                    {
+                        let possible_types = self.possible_types(parent_type);
+                        if !possible_types.is_empty() {
+                            for possible_type in possible_types {
+                                self.extract_from_selection_set(
+                                    std::slice::from_ref(selection),
+                                    possible_type,
+                                    depth,
+                                );
+                            }
+                            continue;
+                        }
+                    }
+
+                    if let Some(stats) = &mut self.stats {
+                        stats.fields_visited += 1;
+                        stats.selections_visited += 1;
+                    }
+                    if let Some(max_depth) = &mut self.max_depth {
+                        *max_depth = (*max_depth).max(depth);
+                    }
+
+                    // Resolve the canonical type name from the schema (e.g., Query -> Root for custom root types)
+                    let canonical_parent_type = self
+                        .type_map
+                        .get(parent_type)
+                        .map(|info| info.name.as_str())
+                        .unwrap_or(parent_type);
+
+                    if let Some(root_fields) = &mut self.root_fields {
+                        let coordinate = self
+                            .options
+                            .field_coordinate(canonical_parent_type, &field.name);
+                        if !self.excluded_coordinates.contains(&coordinate) {
+                            root_fields.insert(self.options.prefixed(coordinate));
+                        }
+                        continue;
+                    }
+
+                    if self.options.error_on_unknown_fields.unwrap_or(false) {
+                        self.record_unknown_field(parent_type, canonical_parent_type, &field.name);
+                    }
+                    if self.issues.is_some() {
+                        self.record_field_issues(parent_type, canonical_parent_type, field);
+                    }
+
+                    // Add the coordinate using the canonical type name
+                    if self.options.is_within_depth(depth) {
+                        let coordinate = self
+                            .options
+                            .field_coordinate(canonical_parent_type, &field.name);
+                        if self.abstract_returning_fields.is_some() {
+                            self.record_abstract_returning_field(parent_type, &coordinate, field);
+                        }
+                        if let Some(field_occurrences) = &mut self.field_occurrences {
+                            field_occurrences.push(FieldOccurrence {
+                                type_name: canonical_parent_type.to_string(),
+                                field_name: field.name.clone(),
+                                alias: field.alias.clone(),
+                                position: field.position,
+                            });
+                        }
+                        if self.options.collapse_to_interface.unwrap_or(false) {
+                            self.add_collapsed_field_coordinate(
+                                canonical_parent_type,
+                                &field.name,
+                                coordinate,
+                            );
+                        } else {
+                            self.add_coordinate(coordinate);
+                        }
+
+                        self.extract_arguments(parent_type, canonical_parent_type, field);
+                    }
+
+                    if self.deprecated_usages.is_some() {
+                        self.record_deprecated_usages(parent_type, canonical_parent_type, field);
+                    }
+
+                    self.record_variables(&field.arguments);
+                    self.extract_directives(&field.directives);
+
+                    if self.options.expand_federation_field_sets.unwrap_or(false) {
+                        self.extract_federation_field_sets(parent_type, &field.name, depth);
+                    }
+
+                    let skip_children = self.options.skip_children_of_excluded.unwrap_or(false)
+                        && !self.excluded_coordinates.is_empty()
+                        && self.excluded_coordinates.contains(
+                            &self
+                                .options
+                                .field_coordinate(canonical_parent_type, &field.name),
+                        );
+
+                    // If field has selections, traverse them with the field's type
+                    if !field.selection_set.items.is_empty() && !skip_children {
+                        // Look up the field's return type from the schema
+                        let type_map = self.type_map;
+                        if let Some(type_info) = type_map.get(parent_type) {
+                            if let Some(field_info) = type_info.fields.get(&field.name) {
+                                if !self.is_leaf_type(&field_info.type_name) {
+                                    self.extract_from_selection_set(
+                                        &field.selection_set.items,
+                                        &field_info.type_name,
+                                        depth + 1,
+                                    );
+                                } else if self
+                                    .options
+                                    .error_on_subselection_of_scalar
+                                    .unwrap_or(false)
+                                {
+                                    let coordinate = self
+                                        .options
+                                        .field_coordinate(canonical_parent_type, &field.name);
+                                    self.scalar_subselections.push(coordinate);
+                                }
+                                // Selections on scalar and enum fields are invalid, so they're
+                                // skipped rather than extracted as fields of the scalar
+                            }
+                            // If field doesn't exist in schema, skip traversing its children
+                            // to avoid processing invalid nested selections
+                        }
+                    }
+                }
+                query::Selection::FragmentSpread(spread) => {
+                    if let Some(stats) = &mut self.stats {
+                        stats.selections_visited += 1;
+                    }
+                    self.extract_directives(&spread.directives);
+                    if let Some(spreads) = &mut self.spreads {
+                        spreads.insert(spread.fragment_name.clone());
+                        continue;
+                    }
+
+                    // Find the fragment definition, falling back to the fragment registry
+                    let query_doc = self.query_doc;
+                    let mut is_defined = false;
+                    for definition in &query_doc.definitions {
+                        if let query::Definition::Fragment(fragment) = definition {
+                            if fragment.name == spread.fragment_name {
+                                is_defined = true;
+                                self.extract_from_fragment(fragment, parent_type, depth);
+                            }
+                        }
+                    }
+                    if !is_defined {
+                        let registry_fragment = self
+                            .fragment_registry
+                            .and_then(|registry| registry.fragments.get(&spread.fragment_name));
+                        match registry_fragment {
+                            Some(fragment) => {
+                                self.extract_from_fragment(fragment, parent_type, depth)
+                            }
+                            None => self.record_unresolved_fragment(spread),
+                        }
+                    }
+                }
+                query::Selection::InlineFragment(inline) => {
+                    if let Some(stats) = &mut self.stats {
+                        stats.selections_visited += 1;
+                    }
+                    self.extract_directives(&inline.directives);
+
+                    let fragment_type = match &inline.type_condition {
+                        Some(query::TypeCondition::On(type_name)) => type_name.as_str(),
+                        None => parent_type,
+                    };
+                    if inline.type_condition.is_some() {
+                        self.record_unknown_type_condition(fragment_type);
+                        if self.issues.is_some() {
+                            self.record_unknown_type(fragment_type, inline.position);
+                        }
+                    }
+                    self.extract_from_selection_set(
+                        &inline.selection_set.items,
+                        fragment_type,
+                        depth,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Extracts the coordinates of a fragment spread under `parent_type`, unless the spread
+    /// completes a fragment cycle. Spreading it under the same parent type again replays the
+    /// coordinates from the first expansion when nothing else observes the traversal.
+    fn extract_from_fragment(
+        &mut self,
+        fragment: &'a query::FragmentDefinition<'_, String>,
+        parent_type: &str,
+        depth: u32,
+    ) {
+        if self.fragment_path.contains(&fragment.name.as_str()) {
+            self.found_fragment_cycle = true;
+            return;
+        }
+        if let Some(stats) = &mut self.stats {
+            stats.fragments_expanded += 1;
+        }
+
+        let replay = self.can_replay_fragments();
+        let key = (fragment.name.as_str(), parent_type.to_string());
+        if replay {
+            if let Some(coordinates) = self.fragment_coordinates.get(&key) {
+                for coordinate in coordinates {
+                    for recording in &mut self.fragment_recordings {
+                        recording.push(coordinate.clone());
+                    }
+                    self.coordinates.emit(coordinate.clone());
+                }
+                return;
+            }
+            self.fragment_recordings.push(Vec::new());
+        }
+
+        let fragment_type = match &fragment.type_condition {
+            query::TypeCondition::On(type_name) => type_name.as_str(),
+        };
+        self.record_unknown_type_condition(fragment_type);
+        let parent_fragment = self.current_fragment.replace(&fragment.name);
+        self.fragment_path.push(&fragment.name);
+        self.extract_directives(&fragment.directives);
+        self.extract_from_selection_set(&fragment.selection_set.items, fragment_type, depth);
+        self.fragment_path.pop();
+        self.current_fragment = parent_fragment;
+
+        if replay {
+            let coordinates = self.fragment_recordings.pop().unwrap_or_default();
+            if !self.found_fragment_cycle {
+                self.fragment_coordinates.insert(key, coordinates);
+            }
+        }
+    }
+}
+
+/// The directives applied to a field, fragment spread or inline fragment
+fn selection_directives<'s, 'a>(
+    selection: &'s query::Selection<'a, String>,
+) -> &'s [query::Directive<'a, String>] {
+    match selection {
+        query::Selection::Field(field) => &field.directives,
+        query::Selection::FragmentSpread(spread) => &spread.directives,
+        query::Selection::InlineFragment(inline) => &inline.directives,
+    }
+}
+
+/// Whether `@skip(if: true)` or `@include(if: false)` excludes a selection. Conditions on
+/// variables can't be evaluated statically, so they never exclude it
+fn is_statically_excluded(directives: &[query::Directive<String>]) -> bool {
+    directives.iter().any(|directive| {
+        let excluded_when = match directive.name.as_str() {
+            "skip" => true,
+            "include" => false,
+            _ => return false,
+        };
+        directive
+            .arguments
+            .iter()
+            .any(|(name, value)| name == "if" && *value == query::Value::Boolean(excluded_when))
+    })
+}
+
+/// Whether every field a selection set selects, looking through fragments, is an introspection
+/// meta-field. `expanded_fragments` guards against fragment cycles.
+fn selects_only_meta_fields<'d>(
+    selection_set: &'d [query::Selection<String>],
+    query_doc: &'d query::Document<String>,
+    expanded_fragments: &mut HashSet<&'d str>,
+) -> bool {
+    selection_set.iter().all(|selection| match selection {
+        query::Selection::Field(field) => field.name.starts_with("__"),
+        query::Selection::FragmentSpread(spread) => {
+            if !expanded_fragments.insert(&spread.fragment_name) {
+                return true;
+            }
+            query_doc
+                .definitions
+                .iter()
+                .all(|definition| match definition {
+                    query::Definition::Fragment(fragment)
+                        if fragment.name == spread.fragment_name =>
+                    {
+                        selects_only_meta_fields(
+                            &fragment.selection_set.items,
+                            query_doc,
+                            expanded_fragments,
+                        )
+                    }
+                    _ => true,
+                })
+        }
+        query::Selection::InlineFragment(inline) => {
+            selects_only_meta_fields(&inline.selection_set.items, query_doc, expanded_fragments)
+        }
+    })
+}
+
+/// Appends the names of the directives applied within a selection set to `directive_names`, in
+/// document order
+fn collect_selection_directive_names<'d>(
+    selection_set: &'d [query::Selection<String>],
+    directive_names: &mut Vec<&'d str>,
+) {
+    for selection in selection_set {
+        collect_directive_names(selection_directives(selection), directive_names);
+        match selection {
+            query::Selection::Field(field) => {
+                collect_selection_directive_names(&field.selection_set.items, directive_names);
+            }
+            query::Selection::InlineFragment(inline) => {
+                collect_selection_directive_names(&inline.selection_set.items, directive_names);
+            }
+            query::Selection::FragmentSpread(_) => {}
+        }
+    }
+}
+
+/// Appends the names of `directives` to `directive_names`
+fn collect_directive_names<'d>(
+    directives: &'d [query::Directive<String>],
+    directive_names: &mut Vec<&'d str>,
+) {
+    directive_names.extend(directives.iter().map(|directive| directive.name.as_str()));
+}
+
+/// Adds the names of all variables referenced within `value` to `variables`
+fn collect_variables(value: &query::Value<String>, variables: &mut HashSet<String>) {
+    match value {
+        query::Value::Variable(name) => {
+            variables.insert(name.to_string());
+        }
+        query::Value::List(items) => {
+            for item in items {
+                collect_variables(item, variables);
+            }
+        }
+        query::Value::Object(fields) => {
+            for field_value in fields.values() {
+                collect_variables(field_value, variables);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The name operations without one are listed under by `extract_coordinate_to_operations`
+const ANONYMOUS_OPERATION: &str = "<anonymous>";
+
+/// The schema name coordinates no registered schema defines are attributed to by
+/// `SchemaRegistry.attribute_coordinates`
+const UNKNOWN_SCHEMA: &str = "<unknown>";
+
+/// Error for subscription operations against a schema without a subscription root type
+const SUBSCRIPTION_NOT_CONFIGURED: &str = "Schema is not configured to execute subscription";
+
+/// Built-in GraphQL scalar types that should not be included in schema coordinates
+const BUILTIN_SCALARS: &[&str] = &["String", "Int", "Float", "Boolean", "ID"];
+
+/// Collects the enum literals in a value, including those inside lists
+fn collect_enum_literals(value: &schema::Value<String>, enum_values: &mut Vec<String>) {
+    match value {
+        schema::Value::Enum(enum_value) => enum_values.push(enum_value.clone()),
+        schema::Value::List(items) => {
+            for item in items {
+                collect_enum_literals(item, enum_values);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_scalar(type_name: &str) -> bool {
+    BUILTIN_SCALARS.contains(&type_name)
+}
+
+/// The object types an interface or union can resolve to, sorted: the object types implementing
+/// an interface, or the members of a union. Interfaces implementing an interface are skipped,
+/// since values are never of an interface type. Empty for other and unknown types.
+fn possible_object_types<'t>(
+    type_map: &'t TypeMap,
+    implementations: &'t HashMap<String, Vec<String>>,
+    union_members: &'t HashMap<String, Vec<String>>,
+    type_name: &str,
+) -> Vec<&'t str> {
+    let Some(type_info) = type_map.get(type_name) else {
+        return Vec::new();
+    };
+    let possible_types = match type_info.kind {
+        TypeKind::Interface => implementations.get(&type_info.name),
+        TypeKind::Union => union_members.get(&type_info.name),
+        _ => None,
+    };
+
+    let mut result: Vec<&str> = possible_types
+        .into_iter()
+        .flatten()
+        .filter(|type_name| {
+            type_map
+                .get(type_name)
+                .is_some_and(|info| info.kind == TypeKind::Object)
+        })
+        .map(String::as_str)
+        .collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+#[derive(Debug, Clone)]
+struct TypeInfo {
+    name: String,
+    kind: TypeKind,
+    fields: HashMap<String, FieldInfo>,
+    /// The values of an enum type, empty for other kinds
+    values: Vec<String>,
+    /// The index of the type's first definition or extension within the schema document, for
+    /// ordering coordinates the way the schema does
+    definition_index: usize,
+    /// The names of the directives applied to the type's definition and extensions
+    directives: Vec<String>,
+}
+
+/// A definition's `@deprecated` directive
+#[derive(Debug, Clone)]
+struct Deprecation {
+    /// The directive's `reason` argument, if given
+    reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct FieldInfo {
+    /// The field's named type, without list and non-null wrappers
+    type_name: String,
+    /// The field's argument names, mapped to their named types
+    arguments: HashMap<String, String>,
+    /// The enum literals in an input field's default value
+    default_enum_values: Vec<String>,
+    /// The field or input field's deprecation, if it's marked `@deprecated`
+    deprecation: Option<Deprecation>,
+    /// The deprecations of the field's arguments that are marked `@deprecated`, keyed by name
+    deprecated_arguments: HashMap<String, Deprecation>,
+    /// The selections of the field's `@requires` field set, made on its parent type
+    requires: Vec<query::Selection<'static, String>>,
+    /// The selections of the field's `@provides` field set, made on its return type
+    provides: Vec<query::Selection<'static, String>>,
+}
+
+#[derive(Debug, Clone)]
+struct DirectiveInfo {
+    /// The directive's argument names, mapped to their named types
+    arguments: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TypeKind {
+    Object,
+    Interface,
+    Union,
+    Scalar,
+    Enum,
+    InputObject,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::LazyLock;
+
+    const PETS_SCHEMA: &str = include_str!("../testing/pets.schema.graphql");
+
+    static PARSED_SCHEMA: LazyLock<ParsedSchema> = LazyLock::new(|| {
+        ParsedSchema::new(PETS_SCHEMA.to_string()).expect("Should parse schema")
+    });
+
+    fn extract_and_sort_with_options(document: &str, options: ExtractionOptions) -> Vec<String> {
+        let parsed_schema = ParsedSchema::with_options(PETS_SCHEMA.to_string(), options)
+            .expect("Should parse schema");
+        let mut result = parsed_schema
+            .extract_schema_coordinates(document.to_string())
+            .expect("Should extract schema coordinates");
+        result.sort();
+        result
+    }
+
+    mod extract_schema_coordinates_tests {
+        use super::*;
+
+        fn extract_and_sort(document: &str) -> Vec<String> {
+            let mut result = PARSED_SCHEMA
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract schema coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_basic_query() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "ContactDetails.email",
+                "Human.contactDetails",
+                "Human.name",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_basic_mutation() {
+        let document = r#"
+            mutation {
+                addCat(name: "Palmerston") {
+                    name
+                    favoriteMilkBrand
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec!["Cat.favoriteMilkBrand", "Cat.name", "Mutation.addCat"]
+        );
+    }
+
+    #[test]
+    fn test_extended_types() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                    contactDetails {
+                        email
+                        address {
+                            zip
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Address.zip",
+                "ContactDetails.address",
+                "ContactDetails.email",
+                "Human.contactDetails",
+                "Human.name",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_operations() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                }
+            }
+            {
+                animalOwner {
+                    contactDetails {
+                        email
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "ContactDetails.email",
+                "Human.contactDetails",
+                "Human.name",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_includes_non_existent_fields_as_leaf_nodes() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                    I_DONT_EXIST
+                    contactDetails {
+                        email
+                        I_DONT_EXIST
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "ContactDetails.I_DONT_EXIST",
+                "ContactDetails.email",
+                "Human.I_DONT_EXIST",
+                "Human.contactDetails",
+                "Human.name",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_includes_non_existent_fields_as_non_leaf_nodes() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                    contactDetails {
+                        email
+                        I_DONT_EXIST {
+                            foo
+                            bar
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "ContactDetails.I_DONT_EXIST",
+                "ContactDetails.email",
+                "Human.contactDetails",
+                "Human.name",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragments() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                }
+                allSpecies {
+                    ...doggoDetails
+                    ...catFacts
+                }
+                pets {
+                    ...parrotParticulars
+                }
+            }
+
+            fragment doggoDetails on Dog {
+                breed
+            }
+
+            fragment catFacts on Cat {
+                favoriteMilkBrand
+                name
+            }
+
+            fragment parrotParticulars on Parrot {
+                wingSpan
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Cat.favoriteMilkBrand",
+                "Cat.name",
+                "Dog.breed",
+                "Human.name",
+                "Parrot.wingSpan",
+                "Root.allSpecies",
+                "Root.animalOwner",
+                "Root.pets",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragment_spreads_on_union_members() {
+        let document = r#"
+            {
+                pets {
+                    __typename
+                    ...doggoDetails
+                    ...catFacts
+                    ...petDetails
+                }
+            }
+
+            fragment doggoDetails on Dog {
+                breed
+            }
+
+            fragment catFacts on Cat {
+                name
+            }
+
+            fragment petDetails on Pet {
+                ... on Parrot {
+                    ...parrotParticulars
+                }
+            }
+
+            fragment parrotParticulars on Parrot {
+                wingSpan
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Cat.name",
+                "Dog.breed",
+                "Parrot.wingSpan",
+                "Pet.__typename",
+                "Root.pets",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fragments_with_interface_fields() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                }
+                allSpecies {
+                    name
+                    ...doggoDetails
+                }
+            }
+
+            fragment doggoDetails on Dog {
+                breed
+                name
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Animal.name",
+                "Dog.breed",
+                "Dog.name",
+                "Human.name",
+                "Root.allSpecies",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_fragments() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                }
+                allSpecies {
+                    ... on Dog {
+                        breed
+                    }
+                    ... on Cat {
+                        favoriteMilkBrand
+                        name
+                    }
+                }
+                pets {
+                    ... on Parrot {
+                        wingSpan
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Cat.favoriteMilkBrand",
+                "Cat.name",
+                "Dog.breed",
+                "Human.name",
+                "Parrot.wingSpan",
+                "Root.allSpecies",
+                "Root.animalOwner",
+                "Root.pets",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_fragments_with_interface_fields() {
+        let document = r#"
+            {
+                animalOwner {
+                    name
+                }
+                allSpecies {
+                    name
+                    ... on Dog {
+                        breed
+                        name
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Animal.name",
+                "Dog.breed",
+                "Dog.name",
+                "Human.name",
+                "Root.allSpecies",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_fragments_without_type_condition() {
+        let document = r#"
+            query Foo($expandedInfo: Boolean) {
+                allSpecies {
+                    ... @include(if: $expandedInfo) {
+                        name
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(result, vec!["Animal.name", "Root.allSpecies"]);
+    }
+
+        #[test]
+        fn test_nested_inline_fragments_without_type_condition() {
+            let document = r#"
+                query Foo($expandedInfo: Boolean, $contact: Boolean) {
+                    animalOwner {
+                        ... @include(if: $expandedInfo) {
+                            age
+                            ... @include(if: $contact) {
+                                contactDetails {
+                                    ... {
+                                        email
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            "#;
+
+            let result = extract_and_sort(document);
+            assert_eq!(
+                result,
+                vec![
+                    "ContactDetails.email",
+                    "Human.age",
+                    "Human.contactDetails",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_nested_interface_fields_selected_directly() {
+            let parsed_schema = ParsedSchema::new(
+                r#"
+                type Query {
+                    animals: [Animal]
+                }
+
+                interface Animal {
+                    name: String
+                    age: Int
+                    owner: Person
+                }
+
+                type Person {
+                    name: String
+                    address: Address
+                }
+
+                type Address {
+                    zip: String
+                }
+                "#
+                .to_string(),
+            )
+            .expect("Should parse schema");
+            let document = r#"
+                {
+                    animals {
+                        name
+                        age
+                        owner {
+                            name
+                            address {
+                                zip
+                            }
+                        }
+                    }
+                }
+            "#;
+
+            let mut result = parsed_schema
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract schema coordinates");
+            result.sort();
+            assert_eq!(
+                result,
+                vec![
+                    "Address.zip",
+                    "Animal.age",
+                    "Animal.name",
+                    "Animal.owner",
+                    "Person.address",
+                    "Person.name",
+                    "Query.animals",
+                ]
+            );
+        }
+
+    #[test]
+    fn test_copes_with_types_that_dont_exist_in_schema() {
+        let document = r#"
+            {
+                allSpecies {
+                    name
+                    ... on Snake {
+                        skin {
+                            color
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(result, vec!["Animal.name", "Root.allSpecies", "Snake.skin"]);
+    }
+
+    #[test]
+    fn test_redundant_interface_fragments_match_direct_selections() {
+        let direct = "{ allSpecies { name } }";
+        let wrapped = r#"
+            {
+                allSpecies {
+                    ... on Animal {
+                        name
+                    }
+                    ...animalFields
+                }
+            }
+
+            fragment animalFields on Animal {
+                name
+            }
+        "#;
+
+        assert_eq!(extract_and_sort(direct), vec!["Animal.name", "Root.allSpecies"]);
+        assert_eq!(extract_and_sort(wrapped), extract_and_sort(direct));
+    }
+
+    #[test]
+    fn test_leaf_selection_does_not_suppress_expanded_selection() {
+        let document = r#"
+            query {
+                ...ownerLeaf
+                ...ownerDetails
+                ...ownerLeaf
+            }
+
+            fragment ownerLeaf on Root {
+                animalOwner
+            }
+
+            fragment ownerDetails on Root {
+                animalOwner {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+            }
+        "#;
+
+        assert_eq!(
+            extract_and_sort(document),
+            vec![
+                "ContactDetails.email",
+                "Human.contactDetails",
+                "Human.name",
+                "Root.animalOwner",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_canonicalizes_root_types_around_unknown_types() {
+        let document = r#"
+            query {
+                mystery {
+                    clue
+                }
+                animalOwner {
+                    name
+                    ... on Snake {
+                        skin
+                        ... on Query {
+                            animalOwner {
+                                age
+                            }
+                        }
+                    }
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(
+            result,
+            vec![
+                "Human.age",
+                "Human.name",
+                "Root.animalOwner",
+                "Root.mystery",
+                "Snake.skin",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shows_inputs() {
+        let document = r#"
+            mutation AddVet($vetInfo: VetDetailsInput!, $somethingElse: String!) {
+                addVet(details: $vetInfo)
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(result, vec!["Mutation.addVet", "VetDetailsInput"]);
+    }
+
+    #[test]
+    fn test_mutation_with_arguments() {
+        let document = r#"
+            mutation AddCat($name: String) {
+                addCat(name: $name) {
+                    name
+                }
+            }
+        "#;
+
+        let result = extract_and_sort(document);
+        assert_eq!(result, vec!["Cat.name", "Mutation.addCat"]);
+    }
+
+        #[test]
+        fn test_self_referencing_types() {
+            let document = r#"
+                {
+                    animalOwner {
+                        age
+                        bestFriend {
+                            bestFriend {
+                                bestFriend {
+                                    name
+                                    contactDetails {
+                                        email
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            "#;
+
+            let result = extract_and_sort(document);
+            assert_eq!(
+                result,
+                vec![
+                    "ContactDetails.email",
+                    "Human.age",
+                    "Human.bestFriend",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_fragment_definition_directives() {
+            let document = r#"
+                {
+                    allSpecies {
+                        ...doggoDetails
+                    }
+                }
+
+                fragment doggoDetails on Dog @someDir {
+                    breed
+                }
+            "#;
+
+            let result = extract_and_sort(document);
+            assert_eq!(result, vec!["Dog.breed", "Root.allSpecies"]);
+
+            let result = extract_and_sort_with_options(
+                document,
+                ExtractionOptions {
+                    include_directives: Some(true),
+                    ..Default::default()
+                },
+            );
+            assert_eq!(result, vec!["@someDir", "Dog.breed", "Root.allSpecies"]);
+        }
+
+        #[test]
+        fn test_operation_directives() {
+            let document = r#"
+                query Owner @cached {
+                    animalOwner {
+                        name
+                    }
+                }
+
+                mutation AddCat @audited {
+                    addCat(name: "Tom") {
+                        name
+                    }
+                }
+            "#;
+
+            let result = extract_and_sort(document);
+            assert_eq!(
+                result,
+                vec![
+                    "Cat.name",
+                    "Human.name",
+                    "Mutation.addCat",
+                    "Root.animalOwner"
+                ]
+            );
+
+            let result = extract_and_sort_with_options(
+                document,
+                ExtractionOptions {
+                    include_directives: Some(true),
+                    ..Default::default()
+                },
+            );
+            assert_eq!(
+                result,
+                vec![
+                    "@audited",
+                    "@cached",
+                    "Cat.name",
+                    "Human.name",
+                    "Mutation.addCat",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        #[should_panic(expected = "Schema is not configured to execute subscription")]
+        fn test_throws_error_on_unsupported_operation_types() {
+            let document = r#"
+            subscription Foo {
+                bar
+            }
+        "#;
+
+            let _ = extract_and_sort(document);
+        }
+
+        #[test]
+        fn test_anonymous_query_with_variables() {
+            let document = r#"
+                query ($details: VetDetailsInput!, $species: Species) {
+                    pets(species: $species) {
+                        ... on Dog {
+                            breed
+                        }
+                    }
+                }
+            "#;
+
+            let result = extract_and_sort(document);
+            assert_eq!(result, vec!["Dog.breed", "Root.pets", "VetDetailsInput"]);
+
+            let variables = PARSED_SCHEMA
+                .extract_used_variables(document.to_string())
+                .expect("Should extract variables");
+            assert_eq!(
+                variables,
+                vec![UsedVariable {
+                    name: "species".to_string(),
+                    type_name: "Species".to_string(),
+                }]
+            );
+        }
+    }
+
+    mod inherited_interface_field_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            type Query {
+                dogs: [Dog]
+            }
+
+            interface Animal {
+                name: String
+                owner: Owner
+            }
+
+            type Dog implements Animal {
+                name: String
+                breed: String
+            }
+
+            type Owner {
+                name: String
+                contact: Contact
+            }
+
+            type Contact {
+                email: String
+            }
+        "#;
+
+        #[test]
+        fn test_selects_inherited_field_with_subselections() {
+            let document = r#"
+                {
+                    dogs {
+                        breed
+                        owner {
+                            name
+                            contact {
+                                email
+                            }
+                        }
+                    }
+                }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                let parsed_schema = ParsedSchema::with_options(
+                    SCHEMA.to_string(),
+                    ExtractionOptions {
+                        lazy_type_map: Some(lazy_type_map),
+                        ..Default::default()
+                    },
+                )
+                .expect("Should parse schema");
+                let mut result = parsed_schema
+                    .extract_schema_coordinates(document.to_string())
+                    .expect("Should extract coordinates");
+                result.sort();
+
+                assert_eq!(
+                    result,
+                    vec![
+                        "Contact.email",
+                        "Dog.breed",
+                        "Dog.owner",
+                        "Owner.contact",
+                        "Owner.name",
+                        "Query.dogs",
+                    ]
+                );
+                assert!(parsed_schema.has_field("Dog.owner".to_string()).unwrap());
+            }
+        }
+    }
+
+    mod max_operations_tests {
+        use super::*;
+
+        const THREE_OPERATIONS: &str = r#"
+            query A { animalOwner { name } }
+            query B { pets { __typename } }
+            mutation C { addCat(name: "Tom") { name } }
+            fragment unused on Human { age }
+        "#;
+
+        fn parse_schema(max_operations: u32) -> ParsedSchema {
+            ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    max_operations: Some(max_operations),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+        }
+
+        #[test]
+        fn test_exceeding_operation_limit() {
+            let error = parse_schema(2)
+                .extract_schema_coordinates(THREE_OPERATIONS.to_string())
+                .expect_err("Should reject document");
+            assert_eq!(
+                error.reason,
+                "Document contains 3 operations, exceeding the limit of 2"
+            );
+        }
+
+        #[test]
+        fn test_within_operation_limit() {
+            let result = parse_schema(3).extract_schema_coordinates(THREE_OPERATIONS.to_string());
+            assert_eq!(result.map(|coordinates| coordinates.len()).ok(), Some(6));
+        }
+    }
+
+    mod interface_member_field_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            {
+                allSpecies {
+                    ...animalFields
+                }
+            }
+
+            fragment animalFields on Animal {
+                name
+                breed
+                wingSpan
+            }
+        "#;
+
+        #[test]
+        fn test_member_only_fields_are_phantom_by_default() {
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, ExtractionOptions::default()),
+                vec![
+                    "Animal.breed",
+                    "Animal.name",
+                    "Animal.wingSpan",
+                    "Root.allSpecies",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_attributes_member_only_fields_to_members() {
+            let options = ExtractionOptions {
+                attribute_interface_fields_to_members: Some(true),
+                ..Default::default()
+            };
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, options),
+                vec![
+                    "Animal.name",
+                    "Dog.breed",
+                    "Parrot.wingSpan",
+                    "Root.allSpecies"
+                ]
+            );
+        }
+
+        #[test]
+        fn test_member_only_fields_rejected_in_strict_mode() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    error_on_unknown_fields: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let error = parsed_schema
+                .extract_schema_coordinates(DOCUMENT.to_string())
+                .expect_err("Should reject member-only fields");
+            assert_eq!(
+                error.reason,
+                "Unknown fields: Animal.breed, Animal.wingSpan"
+            );
+        }
+    }
+
+    mod interface_extension_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            type Query {
+                animals: [Animal]
+                dog: Dog
+                cat: Cat
+            }
+
+            extend interface Animal {
+                age: Int
+            }
+
+            interface Animal {
+                name: String
+            }
+
+            type Dog implements Animal {
+                name: String
+                age: Int
+            }
+
+            type Cat {
+                name: String
+                age: Int
+            }
+
+            extend type Cat implements Animal
+        "#;
+
+        fn extract(document: &str, options: ExtractionOptions) -> Vec<String> {
+            let mut result = ParsedSchema::with_options(SCHEMA.to_string(), options)
+                .expect("Should parse schema")
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_extended_interface_fields_resolve_on_implementations() {
+            let document = "{ dog { age } animals { age ... on Cat { age } } }";
+
+            for lazy_type_map in [false, true] {
+                assert_eq!(
+                    extract(
+                        document,
+                        ExtractionOptions {
+                            error_on_unknown_fields: Some(true),
+                            lazy_type_map: Some(lazy_type_map),
+                            ..Default::default()
+                        }
+                    ),
+                    vec![
+                        "Animal.age",
+                        "Cat.age",
+                        "Dog.age",
+                        "Query.animals",
+                        "Query.dog"
+                    ]
+                );
+            }
+        }
+
+        #[test]
+        fn test_extended_interface_fields_collapse_to_interface() {
+            let document = "{ dog { name age } cat { age } }";
+
+            assert_eq!(
+                extract(
+                    document,
+                    ExtractionOptions {
+                        collapse_to_interface: Some(true),
+                        ..Default::default()
+                    }
+                ),
+                vec!["Animal.age", "Animal.name", "Query.cat", "Query.dog"]
+            );
+        }
+    }
+
+    mod collapse_to_interface_tests {
+        use super::*;
+
+        fn collapsing() -> ExtractionOptions {
+            ExtractionOptions {
+                collapse_to_interface: Some(true),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_inherited_fields_collapse_to_interface() {
+            let document = r#"
+                {
+                    pets {
+                        ... on Dog {
+                            name
+                            breed
+                        }
+                        ... on Cat {
+                            name
+                        }
+                    }
+                    animalOwner {
+                        name
+                        age
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, collapsing()),
+                vec![
+                    "Animal.name",
+                    "Dog.breed",
+                    "Human.age",
+                    "Root.animalOwner",
+                    "Root.pets",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_collapses_to_every_declaring_interface() {
+            let parsed_schema = ParsedSchema::with_options(
+                r#"
+                type Query {
+                    dog: Dog
+                }
+
+                interface Node {
+                    id: ID
+                }
+
+                interface Animal {
+                    id: ID
+                    name: String
+                }
+
+                type Dog implements Node & Animal {
+                    id: ID
+                    name: String
+                }
+                "#
+                .to_string(),
+                collapsing(),
+            )
+            .expect("Should parse schema");
+            let mut result = parsed_schema
+                .extract_schema_coordinates("{ dog { id name } }".to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+
+            assert_eq!(
+                result,
+                vec!["Animal.id", "Animal.name", "Node.id", "Query.dog"]
+            );
+        }
+    }
+
+    mod typename_attribution_tests {
+        use super::*;
+
+        fn attributing(typename_attribution: TypenameAttribution) -> ExtractionOptions {
+            ExtractionOptions {
+                typename_attribution: Some(typename_attribution),
+                ..Default::default()
+            }
+        }
+
+        const DOCUMENT: &str = "{ allSpecies { __typename name } }";
+
+        #[test]
+        fn test_abstract_attribution() {
+            let expected = vec!["Animal.__typename", "Animal.name", "Root.allSpecies"];
+
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, ExtractionOptions::default()),
+                expected
+            );
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, attributing(TypenameAttribution::Abstract)),
+                expected
+            );
+        }
+
+        #[test]
+        fn test_concrete_attribution() {
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, attributing(TypenameAttribution::Concrete)),
+                vec![
+                    "Animal.name",
+                    "Cat.__typename",
+                    "Dog.__typename",
+                    "Human.__typename",
+                    "Parrot.__typename",
+                    "Root.allSpecies",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_concrete_attribution_on_union_and_object_types() {
+            assert_eq!(
+                extract_and_sort_with_options(
+                    "{ __typename pets { __typename } }",
+                    attributing(TypenameAttribution::Concrete)
+                ),
+                vec![
+                    "Cat.__typename",
+                    "Dog.__typename",
+                    "Parrot.__typename",
+                    "Root.__typename",
+                    "Root.pets",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_concrete_attribution_skips_implementing_interfaces() {
+            let parsed_schema = ParsedSchema::with_options(
+                r#"
+                type Query { node: Node }
+                interface Node { id: ID! }
+                interface Resource implements Node { id: ID! url: String }
+                type Image implements Resource & Node { id: ID! url: String }
+                "#
+                .to_string(),
+                attributing(TypenameAttribution::Concrete),
+            )
+            .expect("Should parse schema");
+
+            let mut result = parsed_schema
+                .extract_schema_coordinates("{ node { __typename } }".to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            assert_eq!(result, vec!["Image.__typename", "Query.node"]);
+        }
+    }
+
+    mod exclude_coordinates_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            {
+                __typename
+                animalOwner {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+            }
+        "#;
+
+        fn excluding(coordinates: &[&str], skip_children_of_excluded: bool) -> ExtractionOptions {
+            ExtractionOptions {
+                exclude_coordinates: Some(coordinates.iter().map(|c| c.to_string()).collect()),
+                skip_children_of_excluded: Some(skip_children_of_excluded),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_excludes_leaf_coordinate() {
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, excluding(&["Root.__typename"], false)),
+                vec![
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_excludes_non_leaf_coordinate_but_keeps_children() {
+            assert_eq!(
+                extract_and_sort_with_options(
+                    DOCUMENT,
+                    excluding(&["Human.contactDetails"], false)
+                ),
+                vec![
+                    "ContactDetails.email",
+                    "Human.name",
+                    "Root.__typename",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_skips_children_of_excluded_coordinate() {
+            assert_eq!(
+                extract_and_sort_with_options(
+                    DOCUMENT,
+                    excluding(&["Human.contactDetails", "Root.__typename"], true)
+                ),
+                vec!["Human.name", "Root.animalOwner"]
+            );
+        }
+    }
+
+    mod sorted_tests {
+        use super::*;
+
+        #[test]
+        fn test_sorted_output() {
+            let document = r#"
+                query Owner($details: VetDetailsInput!) {
+                    animalOwner {
+                        name
+                        age
+                        bestFriend {
+                            name
+                        }
+                        contactDetails {
+                            email
+                            phone
+                            address {
+                                zip
+                                streetNumber
+                            }
+                        }
+                    }
+                    pets {
+                        ... on Parrot {
+                            wingSpan
+                        }
+                        ... on Dog {
+                            breed
+                        }
+                    }
+                    allSpecies {
+                        name
+                    }
+                }
+            "#;
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    sorted: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let expected = vec![
+                "Address.streetNumber",
+                "Address.zip",
+                "Animal.name",
+                "ContactDetails.address",
+                "ContactDetails.email",
+                "ContactDetails.phone",
+                "Dog.breed",
+                "Human.age",
+                "Human.bestFriend",
+                "Human.contactDetails",
+                "Human.name",
+                "Parrot.wingSpan",
+                "Root.allSpecies",
+                "Root.animalOwner",
+                "Root.pets",
+                "VetDetailsInput",
+            ];
+            assert_eq!(
+                parsed_schema
+                    .extract_schema_coordinates(document.to_string())
+                    .expect("Should extract coordinates"),
+                expected
+            );
+            assert_eq!(
+                parsed_schema
+                    .extract_schema_coordinates_union(vec![document.to_string()])
+                    .expect("Should extract coordinates"),
+                expected
+            );
+            assert_eq!(
+                parsed_schema.extract_tolerant(document.to_string()),
+                expected
+            );
+        }
+    }
+
+    mod order_tests {
+        use super::*;
+
+        fn extract_in_order(
+            order: CoordinateOrder,
+            lazy_type_map: bool,
+            document: &str,
+        ) -> Vec<String> {
+            ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    order: Some(order),
+                    lazy_type_map: Some(lazy_type_map),
+                    include_directives: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+            .extract_schema_coordinates(document.to_string())
+            .expect("Should extract coordinates")
+        }
+
+        const DOCUMENT: &str = r#"
+            query Owner($details: VetDetailsInput!) {
+                pets {
+                    ... on Dog {
+                        breed
+                    }
+                }
+                animalOwner @cached {
+                    contactDetails {
+                        address {
+                            zip
+                        }
+                        email
+                    }
+                    name
+                }
+                allSpecies {
+                    name
+                }
+            }
+        "#;
+
+        #[test]
+        fn test_schema_order() {
+            for lazy_type_map in [false, true] {
+                assert_eq!(
+                    extract_in_order(CoordinateOrder::Schema, lazy_type_map, DOCUMENT),
+                    vec![
+                        "Root.allSpecies",
+                        "Root.animalOwner",
+                        "Root.pets",
+                        "VetDetailsInput",
+                        "Animal.name",
+                        "Dog.breed",
+                        "Human.contactDetails",
+                        "Human.name",
+                        "ContactDetails.address",
+                        "ContactDetails.email",
+                        "Address.zip",
+                        "@cached",
+                    ]
+                );
+            }
+        }
+
+        #[test]
+        fn test_document_order() {
+            assert_eq!(
+                extract_in_order(CoordinateOrder::Document, false, DOCUMENT),
+                vec![
+                    "Root.pets",
+                    "Dog.breed",
+                    "Root.animalOwner",
+                    "@cached",
+                    "Human.contactDetails",
+                    "ContactDetails.address",
+                    "Address.zip",
+                    "ContactDetails.email",
+                    "Human.name",
+                    "Root.allSpecies",
+                    "Animal.name",
+                    "VetDetailsInput",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_alphabetical_order() {
+            let mut expected = extract_in_order(CoordinateOrder::Document, false, DOCUMENT);
+            expected.sort();
+
+            assert_eq!(
+                extract_in_order(CoordinateOrder::Alphabetical, false, DOCUMENT),
+                expected
+            );
+        }
+
+        #[test]
+        fn test_rejects_order_with_sorted() {
+            let error = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    order: Some(CoordinateOrder::Schema),
+                    sorted: Some(true),
+                    ..Default::default()
+                },
+            )
+            .err()
+            .expect("Should reject combining order and sorted");
+
+            assert_eq!(
+                error.reason,
+                "order and sorted can't both be set. Use order: \"alphabetical\" instead of sorted"
+            );
+        }
+    }
+
+    mod extract_for_types_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            mutation AddVet($details: VetDetailsInput!) {
+                addVet(details: $details)
+            }
+
+            query Owner {
+                animalOwner {
+                    name
+                    bestFriend {
+                        age
+                        contactDetails {
+                            email
+                            address {
+                                zip
+                            }
+                        }
+                    }
+                }
+            }
+        "#;
+
+        fn extract_for_types(parsed_schema: &ParsedSchema, types: &[&str]) -> Vec<String> {
+            let mut result = parsed_schema
+                .extract_for_types(
+                    DOCUMENT.to_string(),
+                    types.iter().map(|t| t.to_string()).collect(),
+                )
+                .expect("Should extract coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_partial_allowlist_reaches_nested_types() {
+            assert_eq!(
+                extract_for_types(&PARSED_SCHEMA, &["ContactDetails", "Address"]),
+                vec![
+                    "Address.zip",
+                    "ContactDetails.address",
+                    "ContactDetails.email",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_allowlist_canonicalizes_root_aliases() {
+            assert_eq!(
+                extract_for_types(&PARSED_SCHEMA, &["Query", "VetDetailsInput"]),
+                vec!["Root.animalOwner", "VetDetailsInput"]
+            );
+        }
+
+        #[test]
+        fn test_allowlist_with_separator_and_prefix() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    separator: Some("::".to_string()),
+                    coordinate_prefix: Some("pets".to_string()),
+                    include_directives: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert_eq!(
+                extract_for_types(&parsed_schema, &["Human"]),
+                vec![
+                    "pets:Human::age",
+                    "pets:Human::bestFriend",
+                    "pets:Human::contactDetails",
+                    "pets:Human::name",
+                ]
+            );
+        }
+    }
+
+    mod empty_selection_set_tests {
+        use super::*;
+
+        #[test]
+        fn test_parser_rejects_empty_selection_set() {
+            for document in ["query Foo { }", "{ }", "{ animalOwner { } }"] {
+                let error = PARSED_SCHEMA
+                    .extract_schema_coordinates(document.to_string())
+                    .expect_err("Should reject empty selection set");
+                assert!(error.reason.starts_with("Failed to parse document"));
+            }
+        }
+
+        #[test]
+        fn test_tolerant_extraction_skips_empty_selection_set() {
+            let document = "query Foo { } query Bar { animalOwner { name } }";
+            let mut result = PARSED_SCHEMA.extract_tolerant(document.to_string());
+            result.sort();
+            assert_eq!(result, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_empty_selection_items_emit_nothing() {
+            let query_doc = parse_document("{ animalOwner { name } }").expect("Should parse");
+            let mut coordinates = HashSet::new();
+            Extraction::new(&PARSED_SCHEMA, &query_doc, &mut coordinates)
+                .extract_from_selection_set(&[], "Query", 1);
+            assert!(coordinates.is_empty());
+        }
+    }
+
+    mod repeated_fragment_tests {
+        use super::*;
+
+        #[test]
+        fn test_same_fragment_spread_twice() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ownerFields
+                        ...ownerFields
+                        bestFriend {
+                            ...ownerFields
+                        }
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec![
+                    "ContactDetails.email",
+                    "Human.bestFriend",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_replayed_fragment_emits_every_use() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ownerFields
+                        bestFriend {
+                            ...ownerFields
+                            ...ownerFields
+                        }
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+            "#;
+
+            let mut coordinates = Vec::new();
+            PARSED_SCHEMA
+                .extract_into_sink(document, &mut coordinates)
+                .expect("Should extract schema coordinates");
+            coordinates.sort();
+
+            assert_eq!(
+                coordinates,
+                vec![
+                    "ContactDetails.email",
+                    "ContactDetails.email",
+                    "ContactDetails.email",
+                    "Human.bestFriend",
+                    "Human.contactDetails",
+                    "Human.contactDetails",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Human.name",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_fragment_cycle_through_field_is_cut() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...friendFields
+                    }
+                }
+
+                fragment friendFields on Human {
+                    name
+                    bestFriend {
+                        ...friendFields
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec!["Human.bestFriend", "Human.name", "Root.animalOwner"]
+            );
+        }
+
+        #[test]
+        fn test_repeated_fragment_respects_depth_window() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ownerFields
+                        bestFriend {
+                            ...ownerFields
+                        }
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    age
+                }
+            "#;
+            let options = ExtractionOptions {
+                min_depth: Some(3),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract_and_sort_with_options(document, options),
+                vec!["Human.age"]
+            );
+        }
+
+        #[test]
+        fn test_repeated_fragment_in_each_operation() {
+            let document = r#"
+                query A($includeName: Boolean!) {
+                    animalOwner {
+                        ...ownerFields
+                    }
+                }
+
+                query B($includeName: Boolean!) {
+                    animalOwner {
+                        ...ownerFields
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name @include(if: $includeName)
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_used_variables(document.to_string())
+                .expect("Should extract variables");
+            assert_eq!(
+                result,
+                vec![UsedVariable {
+                    name: "includeName".to_string(),
+                    type_name: "Boolean!".to_string(),
+                }]
+            );
+
+            let attributed = PARSED_SCHEMA
+                .extract_attributed(document.to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(attributed.len(), 2);
+            assert_eq!(attributed[1].fragments["ownerFields"], vec!["Human.name"]);
+        }
+    }
+
+    mod same_type_condition_fragments_tests {
+        use super::*;
+
+        const FRAGMENTS: &str = r#"
+            fragment a on Dog {
+                breed
+            }
+
+            fragment b on Dog {
+                name
+            }
+        "#;
+
+        #[test]
+        fn test_fragments_on_same_type_accumulate() {
+            let document = format!("{{ pets {{ ...a ...b }} }}{}", FRAGMENTS);
+
+            assert_eq!(
+                extract_and_sort_with_options(&document, ExtractionOptions::default()),
+                vec!["Dog.breed", "Dog.name", "Root.pets"]
+            );
+        }
+
+        #[test]
+        fn test_fragments_are_looked_up_by_name() {
+            let document = format!("{{ pets {{ ...b }} }}{}", FRAGMENTS);
+
+            assert_eq!(
+                extract_and_sort_with_options(&document, ExtractionOptions::default()),
+                vec!["Dog.name", "Root.pets"]
+            );
+        }
+    }
+
+    mod persisted_query_tests {
+        use super::*;
+
+        const HASH: &str = "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38";
+
+        #[test]
+        fn test_register_then_lookup() {
+            let parsed_schema =
+                ParsedSchema::new(PETS_SCHEMA.to_string()).expect("Should parse schema");
+
+            let mut registered = parsed_schema
+                .register_and_extract(HASH.to_string(), "{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+            registered.sort();
+            assert_eq!(registered, vec!["Human.name", "Root.animalOwner"]);
+
+            let mut looked_up = parsed_schema
+                .extract_by_hash(HASH.to_string())
+                .expect("Should find registered hash");
+            looked_up.sort();
+            assert_eq!(looked_up, registered);
+        }
+
+        #[test]
+        fn test_registered_hash_skips_parsing() {
+            let parsed_schema =
+                ParsedSchema::new(PETS_SCHEMA.to_string()).expect("Should parse schema");
+            parsed_schema
+                .register_and_extract(HASH.to_string(), "{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+
+            // The cached result is returned, so the unparseable body is never read
+            let result = parsed_schema
+                .register_and_extract(HASH.to_string(), "{ animalOwner {".to_string())
+                .expect("Should return cached coordinates");
+            assert_eq!(result.len(), 2);
+        }
+
+        #[test]
+        fn test_cache_miss() {
+            let parsed_schema =
+                ParsedSchema::new(PETS_SCHEMA.to_string()).expect("Should parse schema");
+            assert_eq!(parsed_schema.extract_by_hash(HASH.to_string()), None);
+
+            let result =
+                parsed_schema.register_and_extract(HASH.to_string(), "{ animalOwner {".to_string());
+            assert!(result.is_err());
+            assert_eq!(parsed_schema.extract_by_hash(HASH.to_string()), None);
+        }
+
+        #[test]
+        fn test_evicts_least_recently_used_query() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    persisted_query_capacity: Some(2),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            for hash in ["a", "b"] {
+                parsed_schema
+                    .register_and_extract(hash.to_string(), "{ animalOwner { name } }".to_string())
+                    .expect("Should extract coordinates");
+            }
+
+            // Looking up `a` makes `b` the least recently used query
+            assert!(parsed_schema.extract_by_hash("a".to_string()).is_some());
+            parsed_schema
+                .register_and_extract("c".to_string(), "{ animalOwner { age } }".to_string())
+                .expect("Should extract coordinates");
+
+            assert!(parsed_schema.extract_by_hash("a".to_string()).is_some());
+            assert_eq!(parsed_schema.extract_by_hash("b".to_string()), None);
+            assert!(parsed_schema.extract_by_hash("c".to_string()).is_some());
+        }
+
+        #[test]
+        fn test_rejects_zero_capacity() {
+            let error = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    persisted_query_capacity: Some(0),
+                    ..Default::default()
+                },
+            )
+            .err()
+            .expect("Should reject the capacity");
+            assert_eq!(error.reason, "Persisted query capacity must be at least 1");
+        }
+    }
+
+    mod extract_tolerant_tests {
+        use super::*;
+
+        fn extract_tolerant_and_sort(document: &str) -> Vec<String> {
+            let mut result = PARSED_SCHEMA.extract_tolerant(document.to_string());
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_trailing_fragment_interpolation() {
+            let document = r#"
+                query GetOwner {
+                    animalOwner {
+                        ...OwnerFields
+                    }
+                }
+                ${OWNER_FIELDS}
+            "#;
+
+            let result = extract_tolerant_and_sort(document);
+            assert_eq!(result, vec!["Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_interpolated_fragment_definitions_are_used() {
+            let document = r#"
+                query GetOwner {
+                    animalOwner {
+                        ...OwnerFields
+                    }
+                }
+                ${OWNER_FIELDS}
+                fragment OwnerFields on Human {
+                    name
+                    ${extraFields}
+                }
+            "#;
+
+            let result = extract_tolerant_and_sort(document);
+            assert_eq!(result, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_interpolated_spread() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...${OwnerFields}
+                        name
+                    }
+                }
+            "#;
+
+            let result = extract_tolerant_and_sort(document);
+            assert_eq!(result, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_skips_unparseable_definitions() {
+            let document = r#"
+                query Broken {
+                    animalOwner {
+                        name(
+                    }
+                }
+                query Fine {
+                    pets {
+                        ... on Dog {
+                            breed
+                        }
+                    }
+                }
+            "#;
+
+            let result = extract_tolerant_and_sort(document);
+            assert_eq!(result, vec!["Dog.breed", "Root.pets"]);
+        }
+
+        #[test]
+        fn test_skips_unsupported_operations() {
+            let document = r#"
+                subscription Foo { bar }
+                { animalOwner { name } }
+            "#;
+
+            let result = extract_tolerant_and_sort(document);
+            assert_eq!(result, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_garbage_returns_empty() {
+            assert!(extract_tolerant_and_sort("not { a graphql document").is_empty());
+        }
+    }
+
+    mod separator_tests {
+        use super::*;
+
+        fn options_with_separator(separator: &str) -> ExtractionOptions {
+            ExtractionOptions {
+                separator: Some(separator.to_string()),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_custom_separator() {
+            let document = r#"
+                mutation AddVet($vetInfo: VetDetailsInput!) {
+                    addVet(details: $vetInfo)
+                }
+                {
+                    animalOwner {
+                        name
+                    }
+                }
+            "#;
+
+            let result = extract_and_sort_with_options(document, options_with_separator("/"));
+            assert_eq!(
+                result,
+                vec![
+                    "Human/name",
+                    "Mutation/addVet",
+                    "Root/animalOwner",
+                    "VetDetailsInput",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_custom_separator_round_trips() {
+            let parsed_schema =
+                ParsedSchema::with_options(PETS_SCHEMA.to_string(), options_with_separator("#"))
+                    .expect("Should parse schema");
+
+            assert!(parsed_schema.has_field("Cat#name".to_string()).unwrap());
+            assert!(!parsed_schema.has_field("Cat.name".to_string()).unwrap());
+            assert!(parsed_schema
+                .all_input_coordinates()
+                .contains(&"VetDetailsInput#name".to_string()));
+        }
+
+        #[test]
+        fn test_empty_separator_is_rejected() {
+            let result =
+                ParsedSchema::with_options(PETS_SCHEMA.to_string(), options_with_separator(""));
+            assert!(result.is_err());
+        }
+    }
+
+    mod normalize_case_tests {
+        use super::*;
+
+        #[test]
+        fn test_lowercases_type_and_field_names() {
+            let document = r#"
+                query Owner($vetInfo: VetDetailsInput!) {
+                    animalOwner @include(if: true) {
+                        contactDetails {
+                            email
+                        }
+                    }
+                    pets(species: DOG) {
+                        name
+                    }
+                }
+            "#;
+            let options = ExtractionOptions {
+                normalize_case: Some(true),
+                include_arguments: Some(true),
+                include_directives: Some(true),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract_and_sort_with_options(document, options),
+                vec![
+                    "@include",
+                    "contactdetails.email",
+                    "human.contactdetails",
+                    "pet.name",
+                    "root.animalowner",
+                    "root.pets",
+                    "root.pets(species:)",
+                    "vetdetailsinput",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_composes_with_separator_and_prefix() {
+            let options = ExtractionOptions {
+                normalize_case: Some(true),
+                separator: Some("::".to_string()),
+                coordinate_prefix: Some("Pets".to_string()),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract_and_sort_with_options("{ animalOwner { name } }", options),
+                vec!["Pets:human::name", "Pets:root::animalowner"]
+            );
+        }
+
+        fn normalizing_schema() -> ParsedSchema {
+            ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    normalize_case: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+        }
+
+        #[test]
+        fn test_filters_by_original_type_names() {
+            let document = "{ animalOwner { name contactDetails { email } } }";
+            let mut coordinates = normalizing_schema()
+                .extract_for_types(document.to_string(), vec!["Human".to_string()])
+                .expect("Should extract coordinates");
+            coordinates.sort();
+            assert_eq!(coordinates, vec!["human.contactdetails", "human.name"]);
+        }
+
+        #[test]
+        fn test_orders_by_schema_before_normalizing() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    normalize_case: Some(true),
+                    order: Some(CoordinateOrder::Schema),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert_eq!(
+                parsed_schema
+                    .extract_schema_coordinates(
+                        "{ animalOwner { contactDetails { email } name } }".to_string()
+                    )
+                    .expect("Should extract coordinates"),
+                vec![
+                    "root.animalowner",
+                    "human.contactdetails",
+                    "human.name",
+                    "contactdetails.email",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_has_coordinate_accepts_normalized_coordinates() {
+            let parsed_schema = normalizing_schema();
+            assert!(parsed_schema.has_coordinate("root.animalowner".to_string()));
+            assert!(parsed_schema.has_coordinate("Root.animalOwner".to_string()));
+            assert!(!parsed_schema.has_coordinate("root.bogus".to_string()));
+        }
+
+        #[test]
+        fn test_bitset_keeps_coordinates_differing_in_case() {
+            let parsed_schema = ParsedSchema::with_options(
+                "type Query { foo: Foo, Foo: Foo } type Foo { bar: Int, Bar: Int }".to_string(),
+                ExtractionOptions {
+                    normalize_case: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let bitset = parsed_schema
+                .extract_coordinate_bitset("{ foo { bar } }".to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(bitset.iter().map(|byte| byte.count_ones()).sum::<u32>(), 2);
+            assert_eq!(
+                parsed_schema.decode_coordinate_bitset(bitset),
+                vec!["foo.bar", "query.foo"]
+            );
+        }
+    }
+
+    mod only_used_variables_tests {
+        use super::*;
+
+        fn extract_only_used(document: &str) -> Vec<String> {
+            extract_and_sort_with_options(
+                document,
+                ExtractionOptions {
+                    only_used_variables: Some(true),
+                    ..Default::default()
+                },
+            )
+        }
+
+        #[test]
+        fn test_unused_input_variable_is_skipped() {
+            let document = r#"
+                mutation AddCat($name: String!, $unused: VetDetailsInput) {
+                    addCat(name: $name) {
+                        name
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec!["Cat.name", "Mutation.addCat", "VetDetailsInput"]
+            );
+            assert_eq!(
+                extract_only_used(document),
+                vec!["Cat.name", "Mutation.addCat"]
+            );
+        }
+
+        #[test]
+        fn test_used_input_variable_is_kept() {
+            let document = r#"
+                mutation AddVet($vetInfo: VetDetailsInput!) {
+                    addVet(details: $vetInfo)
+                }
+            "#;
+
+            assert_eq!(
+                extract_only_used(document),
+                vec!["Mutation.addVet", "VetDetailsInput"]
+            );
+        }
+
+        #[test]
+        fn test_variables_used_in_nested_values_and_fragments() {
+            let document = r#"
+                mutation AddVet($vetInfo: VetDetailsInput!, $show: Boolean!) {
+                    ...addVetFields
+                }
+
+                fragment addVetFields on Mutation {
+                    ... @include(if: $show) {
+                        addVet(details: { nested: [$vetInfo] })
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_only_used(document),
+                vec!["Mutation.addVet", "VetDetailsInput"]
+            );
+        }
+    }
+
+    mod extract_with_stats_tests {
+        use super::*;
+
+        #[test]
+        fn test_stats_for_basic_query() {
+            let document = r#"
+                {
+                    animalOwner {
+                        name
+                        contactDetails {
+                            email
+                        }
+                    }
+                }
+            "#;
+
+            let mut result = PARSED_SCHEMA
+                .extract_with_stats(document.to_string())
+                .expect("Should extract schema coordinates");
+            result.coordinates.sort();
+
+            assert_eq!(
+                result.coordinates,
+                vec![
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+            assert_eq!(result.stats.fields_visited, 4);
+            assert_eq!(result.stats.fragments_expanded, 0);
+        }
+
+        #[test]
+        fn test_stats_count_fragment_expansions() {
+            let document = r#"
+                {
+                    allSpecies {
+                        ...doggoDetails
+                        ... on Cat {
+                            name
+                        }
+                    }
+                    pets {
+                        ...doggoDetails
+                    }
+                }
+
+                fragment doggoDetails on Dog {
+                    breed
+                    name
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_with_stats(document.to_string())
+                .expect("Should extract schema coordinates");
+
+            assert_eq!(result.stats.fields_visited, 7);
+            assert_eq!(result.stats.fragments_expanded, 2);
+        }
+
+        #[test]
+        fn test_stats_expand_fragment_again_at_new_depth() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ownerFields
+                        bestFriend {
+                            ...ownerFields
+                        }
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_with_stats(document.to_string())
+                .expect("Should extract schema coordinates");
+
+            assert_eq!(result.stats.fields_visited, 4);
+            assert_eq!(result.stats.fragments_expanded, 2);
+        }
+    }
+
+    mod root_fields_tests {
+        use super::*;
+
+        #[test]
+        fn test_extracts_root_fields_of_every_operation() {
+            let document = r#"
+                query Owner {
+                    owner: animalOwner {
+                        name
+                        bestFriend {
+                            name
+                        }
+                    }
+                    ...petFields
+                }
+
+                mutation AddCat {
+                    addCat(name: "Tom") {
+                        name
+                    }
+                }
+
+                fragment petFields on Query {
+                    pets(species: CAT) {
+                        ... on Cat {
+                            name
+                        }
+                    }
+                    ... on Root {
+                        animalOwner {
+                            age
+                        }
+                    }
+                }
+            "#;
+
+            let mut result = PARSED_SCHEMA
+                .extract_root_fields(document.to_string())
+                .expect("Should extract root fields");
+            result.sort();
+
+            assert_eq!(
+                result,
+                vec!["Mutation.addCat", "Root.animalOwner", "Root.pets"]
+            );
+        }
+    }
+
+    mod security_report_tests {
+        use super::*;
+
+        #[test]
+        fn test_reports_traversal_metrics() {
+            let document = r#"
+                {
+                    animalOwner {
+                        name
+                        age
+                        contactDetails {
+                            email
+                        }
+                        bestFriend {
+                            ...ownerFields
+                        }
+                    }
+                    pets {
+                        ...petFields
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                }
+
+                fragment petFields on Pet {
+                    name
+                    ... on Dog {
+                        breed
+                    }
+                }
+            "#;
+
+            let mut report = PARSED_SCHEMA
+                .extract_security_report(document.to_string())
+                .expect("Should extract schema coordinates");
+            report.coordinates.sort();
+
+            assert_eq!(
+                report.coordinates,
+                vec![
+                    "ContactDetails.email",
+                    "Dog.breed",
+                    "Human.age",
+                    "Human.bestFriend",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Pet.name",
+                    "Root.animalOwner",
+                    "Root.pets",
+                ]
+            );
+            // 10 fields in the operation and fragments, plus 2 spreads and 1 inline fragment
+            assert_eq!(report.nodes_visited, 13);
+            assert_eq!(report.max_fanout, 4);
+            assert_eq!(report.fragment_expansions, 2);
+        }
+
+        #[test]
+        fn test_counts_repeated_spreads_at_same_depth() {
+            let document = r#"
+                {
+                    a: pets {
+                        ...petFields
+                    }
+                    b: pets {
+                        ...petFields
+                    }
+                    c: pets {
+                        ...petFields
+                    }
+                }
+
+                fragment petFields on Pet {
+                    ... on Dog {
+                        name
+                        breed
+                    }
+                }
+            "#;
+
+            let mut report = PARSED_SCHEMA
+                .extract_security_report(document.to_string())
+                .expect("Should extract schema coordinates");
+            report.coordinates.sort();
+
+            assert_eq!(
+                report.coordinates,
+                vec!["Dog.breed", "Dog.name", "Root.pets"]
+            );
+            // Each aliased field, its spread, and the fragment's inline fragment and 2 fields
+            assert_eq!(report.nodes_visited, 3 * 5);
+            assert_eq!(report.max_fanout, 3);
+            assert_eq!(report.fragment_expansions, 3);
+        }
+    }
+
+    mod lazy_type_map_tests {
+        use super::*;
+
+        static LAZY_PARSED_SCHEMA: LazyLock<ParsedSchema> = LazyLock::new(|| {
+            ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    lazy_type_map: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+        });
+
+        fn extract_and_sort(parsed_schema: &ParsedSchema, document: &str) -> Vec<String> {
+            let mut result = parsed_schema
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract schema coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_lazy_matches_eager_extraction() {
+            let documents = [
+                "{ animalOwner { name contactDetails { email address { zip } } } }",
+                "mutation AddVet($vetInfo: VetDetailsInput!) { addVet(details: $vetInfo) }",
+                "mutation { addCat(name: \"Tom\") { name favoriteMilkBrand } }",
+                "{ allSpecies { name ... on Dog { breed } ...catFacts } } fragment catFacts on Cat { name }",
+                "{ pets { ... on Parrot { wingSpan } } animalOwner { I_DONT_EXIST { foo } } }",
+                "{ allSpecies { ... on Snake { skin { color } } } }",
+            ];
+
+            for document in documents {
+                assert_eq!(
+                    extract_and_sort(&LAZY_PARSED_SCHEMA, document),
+                    extract_and_sort(&PARSED_SCHEMA, document),
+                    "{}",
+                    document
+                );
+            }
+        }
+
+        #[test]
+        fn test_lazy_matches_eager_lookups() {
+            for coordinate in [
+                "Cat.name",
+                "Root.pets",
+                "Query.animalOwner",
+                "Mutation.addVet",
+                "ContactDetails.address",
+                "VetDetailsInput.phone",
+                "Pet.name",
+                "Snake.name",
+            ] {
+                assert_eq!(
+                    LAZY_PARSED_SCHEMA
+                        .has_field(coordinate.to_string())
+                        .unwrap(),
+                    PARSED_SCHEMA.has_field(coordinate.to_string()).unwrap(),
+                    "{}",
+                    coordinate
+                );
+            }
+            assert_eq!(
+                LAZY_PARSED_SCHEMA.all_input_coordinates(),
+                PARSED_SCHEMA.all_input_coordinates()
+            );
+        }
+
+        #[test]
+        fn test_types_are_resolved_on_first_lookup() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    lazy_type_map: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let TypeMap::Lazy(lazy) = parsed_schema.data.type_map.as_ref() else {
+                panic!("Expected a lazy type map");
+            };
+            let is_resolved = |name: &str| lazy.entries[name].info.get().is_some();
+
+            extract_and_sort(&parsed_schema, "{ animalOwner { name } }");
+
+            assert!(is_resolved("Query"));
+            assert!(is_resolved("Human"));
+            assert!(!is_resolved("Cat"));
+            assert!(!is_resolved("ContactDetails"));
+        }
+    }
+
+    mod subscription_tests {
+        use super::*;
+
+        const MESSAGES_SCHEMA: &str = include_str!("../testing/messages.schema.graphql");
+
+        fn extract_and_sort(document: &str, options: ExtractionOptions) -> Result<Vec<String>> {
+            let parsed_schema = ParsedSchema::with_options(MESSAGES_SCHEMA.to_string(), options)
+                .expect("Should parse schema");
+            let mut result = parsed_schema.extract_schema_coordinates(document.to_string())?;
+            result.sort();
+            Ok(result)
+        }
+
+        #[test]
+        fn test_subscription_root_typename() {
+            let result =
+                extract_and_sort("subscription { __typename }", ExtractionOptions::default())
+                    .expect("Should extract coordinates");
+            assert_eq!(result, vec!["Subscription.__typename"]);
+        }
+
+        fn entry_point(document: &str) -> Result<Option<String>> {
+            ParsedSchema::new(MESSAGES_SCHEMA.to_string())
+                .expect("Should parse schema")
+                .subscription_entry_point(document.to_string())
+        }
+
+        #[test]
+        fn test_subscription_entry_point() {
+            let document = r#"
+                query Messages {
+                    messages {
+                        body
+                    }
+                }
+
+                subscription OnMessage {
+                    ...rootFields
+                }
+
+                fragment rootFields on Subscription {
+                    added: messageAdded {
+                        body
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                entry_point(document).expect("Should find entry point"),
+                Some("Subscription.messageAdded".to_string())
+            );
+        }
+
+        #[test]
+        fn test_subscription_entry_point_without_subscription() {
+            assert_eq!(
+                entry_point("{ messages { body } }").expect("Should parse document"),
+                None
+            );
+        }
+
+        #[test]
+        fn test_subscription_entry_point_errors() {
+            assert!(entry_point(TWO_ROOT_FIELDS).is_err());
+            assert!(PARSED_SCHEMA
+                .subscription_entry_point("subscription { messageAdded }".to_string())
+                .is_err());
+        }
+
+        const TWO_ROOT_FIELDS: &str = r#"
+            subscription OnMessage {
+                messageAdded {
+                    body
+                    author {
+                        name
+                    }
+                }
+                ... on Subscription {
+                    messageDeleted
+                }
+            }
+        "#;
+
+        #[test]
+        fn test_subscription() {
+            let document = r#"
+                subscription OnMessage {
+                    messageAdded {
+                        body
+                    }
+                }
+            "#;
+
+            let result = extract_and_sort(document, ExtractionOptions::default()).unwrap();
+            assert_eq!(result, vec!["Message.body", "Subscription.messageAdded"]);
+        }
+
+        #[test]
+        fn test_custom_subscription_root_type() {
+            let parsed_schema = ParsedSchema::new(
+                r#"
+                schema {
+                    query: Query
+                    subscription: Events
+                }
+                type Query { a: Int }
+                type Events { tick: Int }
+                "#
+                .to_string(),
+            )
+            .expect("Should parse schema");
+
+            let result = parsed_schema
+                .extract_schema_coordinates("subscription { tick }".to_string())
+                .expect("Should extract schema coordinates");
+            assert_eq!(result, vec!["Events.tick"]);
+        }
+
+        #[test]
+        fn test_multiple_root_fields_are_extracted_by_default() {
+            let result = extract_and_sort(TWO_ROOT_FIELDS, ExtractionOptions::default()).unwrap();
+            assert_eq!(
+                result,
+                vec![
+                    "Message.author",
+                    "Message.body",
+                    "Subscription.messageAdded",
+                    "Subscription.messageDeleted",
+                    "User.name",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_single_subscription_root() {
+            let options = ExtractionOptions {
+                single_subscription_root: Some(true),
+                ..Default::default()
+            };
+
+            let error = extract_and_sort(TWO_ROOT_FIELDS, options.clone()).unwrap_err();
+            assert_eq!(
+                error.reason,
+                "Subscription must select only one root field, but selects: messageAdded, messageDeleted"
+            );
+
+            let document = r#"
+                subscription OnMessage {
+                    added: messageAdded { id }
+                    added: messageAdded { body }
+                }
+            "#;
+            assert_eq!(
+                extract_and_sort(document, options).unwrap(),
+                vec!["Message.body", "Message.id", "Subscription.messageAdded"]
+            );
+        }
+    }
+
+    mod extract_schema_coordinates_union_tests {
+        use super::*;
+
+        fn extract_union_and_sort(documents: &[&str]) -> Result<Vec<String>> {
+            let mut result = PARSED_SCHEMA.extract_schema_coordinates_union(
+                documents
+                    .iter()
+                    .map(|document| document.to_string())
+                    .collect(),
+            )?;
+            result.sort();
+            Ok(result)
+        }
+
+        #[test]
+        fn test_union_of_documents() {
+            let result = extract_union_and_sort(&[
+                "{ animalOwner { name } }",
+                "mutation { addCat(name: \"Tom\") { name } }",
+            ])
+            .unwrap();
+
+            assert_eq!(
+                result,
+                vec![
+                    "Cat.name",
+                    "Human.name",
+                    "Mutation.addCat",
+                    "Root.animalOwner"
+                ]
+            );
+        }
+
+        #[test]
+        fn test_same_named_fragments_resolve_per_document() {
+            let result = extract_union_and_sort(&[
+                r#"
+                { allSpecies { ...details } }
+                fragment details on Dog { breed }
+                "#,
+                r#"
+                { pets { ...details } }
+                fragment details on Cat { favoriteMilkBrand }
+                "#,
+            ])
+            .unwrap();
+
+            assert_eq!(
+                result,
+                vec![
+                    "Cat.favoriteMilkBrand",
+                    "Dog.breed",
+                    "Root.allSpecies",
+                    "Root.pets"
+                ]
+            );
+        }
+
+        #[test]
+        fn test_fragments_are_not_shared_between_documents() {
+            let result = extract_union_and_sort(&[
+                "{ allSpecies { ...details } }",
+                "{ animalOwner { name } } fragment details on Dog { breed }",
+            ])
+            .unwrap();
+
+            assert_eq!(
+                result,
+                vec!["Human.name", "Root.allSpecies", "Root.animalOwner"]
+            );
+        }
+
+        #[test]
+        fn test_reports_index_of_failing_document() {
+            let error = extract_union_and_sort(&["{ pets { name } }", "{ pets {"]).unwrap_err();
+            assert!(error
+                .reason
+                .starts_with("Document 1: Failed to parse document"));
+        }
+    }
+
+    mod depth_tests {
+        use super::*;
+
+        const NESTED_QUERY: &str = r#"
+            {
+                animalOwner {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+                allSpecies {
+                    ... on Dog {
+                        breed
+                    }
+                }
+            }
+        "#;
+
+        fn extract_with_depths(min_depth: Option<u32>, max_depth: Option<u32>) -> Vec<String> {
+            extract_and_sort_with_options(
+                NESTED_QUERY,
+                ExtractionOptions {
+                    min_depth,
+                    max_depth,
+                    ..Default::default()
+                },
+            )
+        }
+
+        #[test]
+        fn test_only_depth_one() {
+            assert_eq!(
+                extract_with_depths(None, Some(1)),
+                vec!["Root.allSpecies", "Root.animalOwner"]
+            );
+        }
+
+        #[test]
+        fn test_only_depth_two() {
+            assert_eq!(
+                extract_with_depths(Some(2), Some(2)),
+                vec!["Dog.breed", "Human.contactDetails", "Human.name"]
+            );
+        }
+
+        #[test]
+        fn test_min_depth_only() {
+            assert_eq!(
+                extract_with_depths(Some(3), None),
+                vec!["ContactDetails.email"]
+            );
+        }
+    }
+
+    mod argument_tests {
+        use super::*;
+
+        fn extract_with_arguments(document: &str) -> Vec<String> {
+            extract_and_sort_with_options(
+                document,
+                ExtractionOptions {
+                    include_arguments: Some(true),
+                    ..Default::default()
+                },
+            )
+        }
+
+        fn extract_with_enum_values(document: &str) -> Vec<String> {
+            extract_and_sort_with_options(
+                document,
+                ExtractionOptions {
+                    include_enum_values: Some(true),
+                    ..Default::default()
+                },
+            )
+        }
+
+        #[test]
+        fn test_argument_coordinates() {
+            let document = r#"
+                mutation AddCat($name: String!) {
+                    addCat(name: $name) {
+                        name
+                    }
+                }
+                {
+                    pets(species: DOG, first: 10) {
+                        ... on Dog {
+                            breed
+                        }
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_with_arguments(document),
+                vec![
+                    "Cat.name",
+                    "Dog.breed",
+                    "Mutation.addCat",
+                    "Mutation.addCat(name:)",
+                    "Root.pets",
+                    "Root.pets(first:)",
+                    "Root.pets(species:)",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_arguments_accumulate_across_calls_of_a_field() {
+            let document = r#"
+                query First {
+                    pets(first: 10) {
+                        __typename
+                    }
+                }
+
+                query Last {
+                    pets(last: 5) {
+                        __typename
+                    }
+                    again: pets(first: 1) {
+                        __typename
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_with_arguments(document),
+                vec![
+                    "Pet.__typename",
+                    "Root.pets",
+                    "Root.pets(first:)",
+                    "Root.pets(last:)",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_enum_argument_via_variable() {
+            let document = r#"
+                query Pets($species: Species) {
+                    pets(species: $species) {
+                        ... on Dog {
+                            breed
+                        }
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec!["Dog.breed", "Root.pets"]
+            );
+            assert_eq!(
+                extract_with_enum_values(document),
+                vec!["Dog.breed", "Root.pets", "Species"]
+            );
+        }
+
+        #[test]
+        fn test_enum_argument_literal() {
+            let document = r#"
+                {
+                    pets(species: PARROT) {
+                        ... on Parrot {
+                            wingSpan
+                        }
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_with_enum_values(document),
+                vec!["Parrot.wingSpan", "Root.pets", "Species", "Species.PARROT"]
+            );
+        }
+    }
+
+    mod input_fields_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            directive @batch(items: [BatchItem!]!, label: String) on FIELD
+            type Query {
+                search(filters: [Filter!], page: Page): [String]
+            }
+            input BatchItem { id: ID, tags: [Tag] }
+            input Tag { name: String }
+            input Filter { field: String, value: String }
+            input Page { size: Int }
+        "#;
+
+        fn extract(document: &str, options: ExtractionOptions) -> Vec<String> {
+            let mut result = ParsedSchema::with_options(SCHEMA.to_string(), options)
+                .expect("Should parse schema")
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_list_of_input_directive_argument() {
+            let document = r#"
+                {
+                    search @batch(items: [{ id: 1 }, { id: 2, tags: [{ name: "a" }] }], label: "x")
+                }
+            "#;
+            let options = ExtractionOptions {
+                include_directives: Some(true),
+                include_directive_arguments: Some(true),
+                include_input_fields: Some(true),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract(document, options),
+                vec![
+                    "@batch",
+                    "@batch(items:)",
+                    "@batch(label:)",
+                    "BatchItem.id",
+                    "BatchItem.tags",
+                    "Query.search",
+                    "Tag.name",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_field_argument_input_fields() {
+            let document = r#"
+                {
+                    search(filters: [{ field: "name", value: "Rex" }], page: { size: 10 })
+                }
+            "#;
+            let options = ExtractionOptions {
+                include_input_fields: Some(true),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract(document, options),
+                vec!["Filter.field", "Filter.value", "Page.size", "Query.search"]
+            );
+        }
+
+        #[test]
+        fn test_input_fields_not_extracted_by_default() {
+            let document = r#"
+                {
+                    search(page: { size: 10 }) @batch(items: [{ id: 1 }])
+                }
+            "#;
+
+            assert_eq!(
+                extract(document, ExtractionOptions::default()),
+                vec!["Query.search"]
+            );
+        }
+    }
+
+    mod output_format_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            directive @cached(ttl: Int) on FIELD
+            type Query {
+                pets(species: Species, filter: PetFilter): [Pet]
+            }
+            type Pet { name: String }
+            enum Species { DOG CAT }
+            input PetFilter { name: String }
+        "#;
+
+        const DOCUMENT: &str = r#"
+            query Pets($filter: PetFilter) {
+                pets(species: DOG, filter: { name: "Rex" }) @cached(ttl: 60) {
+                    name
+                }
+                other: pets(filter: $filter) {
+                    name
+                }
+            }
+        "#;
+
+        fn extract(options: ExtractionOptions) -> Result<Vec<String>> {
+            let mut result = ParsedSchema::with_options(SCHEMA.to_string(), options)?
+                .extract_schema_coordinates(DOCUMENT.to_string())?;
+            result.sort();
+            Ok(result)
+        }
+
+        fn format(output_format: OutputFormat) -> ExtractionOptions {
+            ExtractionOptions {
+                output_format: Some(output_format),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_rfc_format_emits_every_grammar_form() {
+            assert_eq!(
+                extract(format(OutputFormat::Rfc)).expect("Should extract coordinates"),
+                vec![
+                    "@cached",
+                    "@cached(ttl:)",
+                    "Pet.name",
+                    "PetFilter",
+                    "PetFilter.name",
+                    "Query.pets",
+                    "Query.pets(filter:)",
+                    "Query.pets(species:)",
+                    "Species",
+                    "Species.DOG",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_legacy_format_is_default() {
+            let legacy = extract(format(OutputFormat::Legacy)).expect("Should extract coordinates");
+            assert_eq!(legacy, vec!["Pet.name", "PetFilter", "Query.pets"]);
+            assert_eq!(
+                extract(ExtractionOptions::default()).expect("Should extract coordinates"),
+                legacy
+            );
+        }
+
+        #[test]
+        fn test_rfc_format_forms_can_be_turned_off() {
+            let options = ExtractionOptions {
+                include_directives: Some(false),
+                include_directive_arguments: Some(false),
+                ..format(OutputFormat::Rfc)
+            };
+            let result = extract(options).expect("Should extract coordinates");
+            assert!(!result.iter().any(|coordinate| coordinate.starts_with('@')));
+            assert!(result.contains(&"Species.DOG".to_string()));
+        }
+
+        #[test]
+        fn test_rfc_format_rejects_non_spec_options() {
+            let with_separator = ExtractionOptions {
+                separator: Some("::".to_string()),
+                ..format(OutputFormat::Rfc)
+            };
+            assert!(extract(with_separator).is_err());
+
+            let with_prefix = ExtractionOptions {
+                coordinate_prefix: Some("pets".to_string()),
+                ..format(OutputFormat::Rfc)
+            };
+            assert!(extract(with_prefix).is_err());
+        }
+    }
+
+    mod federation_tests {
+        use super::*;
+
+        fn extract_from_subgraph(document: &str) -> Vec<String> {
+            extract_and_sort_with_options(
+                document,
+                ExtractionOptions {
+                    from_subgraph: Some(true),
+                    ..Default::default()
+                },
+            )
+        }
+
+        #[test]
+        fn test_service_query() {
+            let document = r#"
+                query {
+                    _service {
+                        sdl
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec!["Root._service"]
+            );
+            assert_eq!(
+                extract_from_subgraph(document),
+                vec!["Root._service", "_Service.sdl"]
+            );
+        }
+
+        #[test]
+        fn test_entities_query() {
+            let document = r#"
+                query Entities($representations: [_Any!]!) {
+                    _entities(representations: $representations) {
+                        ... on Dog {
+                            name
+                            breed
+                        }
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract_from_subgraph(document),
+                vec!["Dog.breed", "Dog.name", "Root._entities"]
+            );
+        }
+
+        #[test]
+        fn test_subgraph_defined_types_are_kept() {
+            let parsed_schema = ParsedSchema::with_options(
+                r#"
+                type Query { a: Int }
+                type _Service { sdl: String version: String }
+                "#
+                .to_string(),
+                ExtractionOptions {
+                    from_subgraph: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert!(parsed_schema
+                .has_field("_Service.version".to_string())
+                .unwrap());
+            assert!(parsed_schema
+                .has_field("Query._entities".to_string())
+                .unwrap());
+        }
+    }
+
+    mod federation_field_set_tests {
+        use super::*;
+
+        const SUBGRAPH_SCHEMA: &str = r#"
+            type Query {
+                product: Product
+                review: Review
+            }
+
+            type Product @key(fields: "id") {
+                id: ID!
+                name: String
+                weight: Int @external
+                dimensions: Dimensions @external
+                shippingEstimate: Int @requires(fields: "id name")
+                packagingCost: Int @requires(fields: "dimensions { height }")
+                insuranceCost: Int @requires(fields: "taxCost")
+                taxCost: Int @requires(fields: "insuranceCost")
+            }
+
+            type Dimensions {
+                height: Int
+                width: Int
+            }
+
+            type Review {
+                body: String
+                product: Product @provides(fields: "name")
+            }
+        "#;
+
+        fn extract(document: &str, expand_federation_field_sets: bool) -> Vec<String> {
+            let parsed_schema = ParsedSchema::with_options(
+                SUBGRAPH_SCHEMA.to_string(),
+                ExtractionOptions {
+                    expand_federation_field_sets: Some(expand_federation_field_sets),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let mut result = parsed_schema
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_requires_field_set() {
+            let document = "{ product { shippingEstimate } }";
+
+            assert_eq!(
+                extract(document, true),
+                vec![
+                    "Product.id",
+                    "Product.name",
+                    "Product.shippingEstimate",
+                    "Query.product",
+                ]
+            );
+            assert_eq!(
+                extract(document, false),
+                vec!["Product.shippingEstimate", "Query.product"]
+            );
+        }
+
+        #[test]
+        fn test_nested_requires_field_set() {
+            assert_eq!(
+                extract("{ product { packagingCost } }", true),
+                vec![
+                    "Dimensions.height",
+                    "Product.dimensions",
+                    "Product.packagingCost",
+                    "Query.product",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_mutually_requiring_fields() {
+            assert_eq!(
+                extract("{ product { insuranceCost } }", true),
+                vec!["Product.insuranceCost", "Product.taxCost", "Query.product"]
+            );
+        }
+
+        #[test]
+        fn test_provides_field_set() {
+            assert_eq!(
+                extract("{ review { product { id } } }", true),
+                vec![
+                    "Product.id",
+                    "Product.name",
+                    "Query.review",
+                    "Review.product",
+                ]
+            );
+        }
+    }
+
+    mod one_of_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            directive @oneOf on INPUT_OBJECT | ARGUMENT_DEFINITION | FIELD
+
+            type Query {
+                pets(filter: PetFilter): [Pet]
+            }
+
+            input PetFilter @oneOf {
+                name: String
+                owner: OwnerFilter
+            }
+
+            input OwnerFilter {
+                email: String
+            }
+
+            type Pet {
+                name: String
+            }
+        "#;
+
+        fn parse_schema(options: ExtractionOptions) -> ParsedSchema {
+            ParsedSchema::with_options(SCHEMA.to_string(), options).expect("Should parse schema")
+        }
+
+        #[test]
+        fn test_one_of_input_object_in_schema() {
+            let parsed_schema = parse_schema(ExtractionOptions::default());
+
+            assert_eq!(parsed_schema.all_directive_coordinates(), vec!["@oneOf"]);
+            assert!(parsed_schema.has_coordinate("PetFilter.owner".to_string()));
+            assert!(parsed_schema
+                .all_coordinates_detailed()
+                .contains(&Coordinate {
+                    coordinate: "PetFilter.name".to_string(),
+                    kind: CoordinateKind::InputField,
+                }));
+        }
+
+        #[test]
+        fn test_documents_using_one_of_inputs() {
+            let parsed_schema = parse_schema(ExtractionOptions {
+                include_arguments: Some(true),
+                include_input_fields: Some(true),
+                include_directives: Some(true),
+                ..Default::default()
+            });
+            let document = r#"
+                query Pets($filter: PetFilter) {
+                    byOwner: pets(filter: { owner: { email: "a@b.c" } }) @oneOf {
+                        name
+                    }
+                    byVariable: pets(filter: $filter) {
+                        name
+                    }
+                }
+            "#;
+
+            let mut result = parsed_schema
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+
+            assert_eq!(
+                result,
+                vec![
+                    "@oneOf",
+                    "OwnerFilter.email",
+                    "Pet.name",
+                    "PetFilter",
+                    "PetFilter.owner",
+                    "Query.pets",
+                    "Query.pets(filter:)",
+                ]
+            );
+        }
+    }
+
+    mod unknown_type_conditions_tests {
+        use super::*;
+
+        fn strict_schema() -> ParsedSchema {
+            ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    error_on_unknown_type_conditions: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+        }
+
+        #[test]
+        fn test_errors_naming_unknown_type_conditions() {
+            let document = r#"
+                {
+                    allSpecies {
+                        name
+                        ... on Snake {
+                            skin {
+                                color
+                            }
+                        }
+                        ...lizardFields
+                    }
+                }
+
+                fragment lizardFields on Lizard {
+                    tail
+                }
+            "#;
+
+            let error = strict_schema()
+                .extract_schema_coordinates(document.to_string())
+                .expect_err("Should reject unknown type conditions");
+            assert_eq!(error.reason, "Unknown type conditions: Lizard, Snake");
+        }
+
+        #[test]
+        fn test_allows_known_type_conditions() {
+            let result = strict_schema()
+                .extract_schema_coordinates(
+                    "{ allSpecies { ... on Dog { breed } ... { name } } }".to_string(),
+                )
+                .expect("Should extract coordinates");
+
+            assert_eq!(result.len(), 3);
+        }
+    }
+
+    mod unknown_fields_tests {
+        use super::*;
+
+        fn strict_schema() -> ParsedSchema {
+            ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    error_on_unknown_fields: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+        }
+
+        #[test]
+        fn test_errors_naming_unknown_fields() {
+            let document = r#"
+                {
+                    animalOwner {
+                        name
+                        I_DONT_EXIST
+                        contactDetails {
+                            email
+                            I_DONT_EXIST {
+                                foo
+                            }
+                        }
+                    }
+                }
+            "#;
+
+            let error = strict_schema()
+                .extract_schema_coordinates(document.to_string())
+                .expect_err("Should reject unknown fields");
+            assert_eq!(
+                error.reason,
+                "Unknown fields: ContactDetails.I_DONT_EXIST, Human.I_DONT_EXIST"
+            );
+        }
+
+        #[test]
+        fn test_names_root_fields_by_canonical_type() {
+            let error = strict_schema()
+                .extract_schema_coordinates("{ owner { name } }".to_string())
+                .expect_err("Should reject unknown fields");
+            assert_eq!(error.reason, "Unknown fields: Root.owner");
+        }
+
+        #[test]
+        fn test_known_fields_and_meta_fields_are_allowed() {
+            let document = r#"
+                {
+                    __typename
+                    animalOwner {
+                        __typename
+                        name
+                    }
+                }
+            "#;
+
+            let result = strict_schema().extract_schema_coordinates(document.to_string());
+            assert!(result.is_ok());
+        }
+
+        #[test]
+        fn test_unknown_fields_are_extracted_by_default() {
+            let result = extract_and_sort_with_options(
+                "{ animalOwner { I_DONT_EXIST } }",
+                ExtractionOptions::default(),
+            );
+            assert_eq!(result, vec!["Human.I_DONT_EXIST", "Root.animalOwner"]);
+        }
+    }
+
+    mod scalar_subselection_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            scalar DateTime
+            enum Mood { HAPPY SAD }
+            type Query { me: User }
+            type User { name: String, born: DateTime, mood: Mood, friend: User }
+        "#;
+
+        fn parse_schema(error_on_subselection_of_scalar: bool) -> ParsedSchema {
+            ParsedSchema::with_options(
+                SCHEMA.to_string(),
+                ExtractionOptions {
+                    error_on_subselection_of_scalar: Some(error_on_subselection_of_scalar),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+        }
+
+        #[test]
+        fn test_skips_selections_on_scalar_fields() {
+            let result = extract_and_sort_with_options(
+                "{ animalOwner { name { foo } age } }",
+                ExtractionOptions::default(),
+            );
+            assert_eq!(result, vec!["Human.age", "Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_skips_selections_on_custom_scalar_and_enum_fields() {
+            let document = "{ me { born { year } mood { label } friend { name } } }";
+
+            let mut result = parse_schema(false)
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            assert_eq!(
+                result,
+                vec![
+                    "Query.me",
+                    "User.born",
+                    "User.friend",
+                    "User.mood",
+                    "User.name",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_errors_on_selections_on_scalar_and_enum_fields() {
+            let document = "{ me { name { foo } born { year } mood { label } friend { name } } }";
+
+            let error = parse_schema(true)
+                .extract_schema_coordinates(document.to_string())
+                .expect_err("Should reject selections on scalar fields");
+            assert_eq!(
+                error.reason,
+                "Selections on scalar or enum fields: User.born, User.mood, User.name"
+            );
+        }
+
+        #[test]
+        fn test_valid_documents_pass_strict_check() {
+            let result = parse_schema(true)
+                .extract_schema_coordinates("{ me { name born mood } }".to_string());
+            assert!(result.is_ok());
+        }
+    }
+
+    mod input_fields_from_variables_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            type Query {
+                search(filter: SearchFilter): [String]
+            }
+
+            input SearchFilter {
+                text: String = "cats"
+                order: SortOrder = DESC
+                orders: [SortOrder] = [ASC, DESC]
+                page: PageInput = { size: 10 }
+            }
+
+            input PageInput {
+                size: Int = 20
+                parent: SearchFilter
+            }
+
+            enum SortOrder {
+                ASC
+                DESC
+            }
+        "#;
+
+        fn extract(
+            document: &str,
+            input_fields_from_variables: Option<VariableInputFields>,
+        ) -> Vec<String> {
+            let parsed_schema = ParsedSchema::with_options(
+                SCHEMA.to_string(),
+                ExtractionOptions {
+                    input_fields_from_variables,
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let mut result = parsed_schema
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_all_extracts_every_input_field_and_default_enum_values() {
+            let document = r#"
+                query Search($filter: SearchFilter) {
+                    search(filter: $filter)
+                }
+            "#;
+
+            assert_eq!(
+                extract(document, Some(VariableInputFields::All)),
+                vec![
+                    "PageInput",
+                    "PageInput.parent",
+                    "PageInput.size",
+                    "Query.search",
+                    "SearchFilter",
+                    "SearchFilter.order",
+                    "SearchFilter.orders",
+                    "SearchFilter.page",
+                    "SearchFilter.text",
+                    "SortOrder",
+                    "SortOrder.ASC",
+                    "SortOrder.DESC",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_input_fields_are_not_extracted_by_default() {
+            let document = r#"
+                query Search($filter: SearchFilter!) {
+                    search(filter: $filter)
+                }
+            "#;
+
+            assert_eq!(
+                extract(document, None),
+                vec!["Query.search", "SearchFilter"]
+            );
+            assert_eq!(
+                extract(document, Some(VariableInputFields::None)),
+                vec!["Query.search", "SearchFilter"]
+            );
+        }
+    }
+
+    mod extract_with_variables_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            mutation AddVet($details: VetDetailsInput!, $name: String) {
+                addVet(details: $details)
+            }
+        "#;
+
+        #[test]
+        fn test_extracts_input_fields_present_in_variables() {
+            let mut result = PARSED_SCHEMA
+                .extract_with_variables(
+                    DOCUMENT.to_string(),
+                    r#"{"details": {"name": "Dr. Dolittle", "phone": "555-0100"}, "name": "x"}"#
+                        .to_string(),
+                )
+                .expect("Should extract coordinates");
+            result.sort();
+
+            assert_eq!(
+                result,
+                vec![
+                    "Mutation.addVet",
+                    "VetDetailsInput",
+                    "VetDetailsInput.name",
+                    "VetDetailsInput.phone",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_invalid_variables() {
+            let error = PARSED_SCHEMA
+                .extract_with_variables(DOCUMENT.to_string(), r#"{"details": }"#.to_string())
+                .expect_err("Should fail to parse variables");
+            assert_eq!(error.reason, "Failed to parse variables at byte 12");
+
+            let error = PARSED_SCHEMA
+                .extract_with_variables(DOCUMENT.to_string(), "[]".to_string())
+                .expect_err("Should reject variables");
+            assert_eq!(error.reason, "Variables must be a JSON object");
+
+            let deeply_nested = format!(r#"{{"details": {}}}"#, "[".repeat(100_000));
+            let error = PARSED_SCHEMA
+                .extract_with_variables(DOCUMENT.to_string(), deeply_nested)
+                .expect_err("Should reject deeply nested variables");
+            assert_eq!(
+                error.reason,
+                "Variables are nested more than 128 levels deep at byte 139"
+            );
+        }
+    }
+
+    mod extract_used_variables_tests {
+        use super::*;
+
+        fn variable(name: &str, type_name: &str) -> UsedVariable {
+            UsedVariable {
+                name: name.to_string(),
+                type_name: type_name.to_string(),
+            }
+        }
+
+        #[test]
+        fn test_same_variable_name_with_different_types_per_operation() {
+            let document = r#"
+                mutation AddVet($input: VetDetailsInput!) {
+                    addVet(details: $input)
+                }
+
+                query Pets($input: Species) {
+                    pets(species: $input) {
+                        __typename
+                    }
+                }
+
+                query Owner($input: VetDetailsInput) {
+                    animalOwner {
+                        name
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                PARSED_SCHEMA
+                    .extract_used_variables(document.to_string())
+                    .expect("Should extract variables"),
+                vec![
+                    variable("input", "VetDetailsInput!"),
+                    variable("input", "Species")
+                ]
+            );
+
+            // Only the input object variable type is a coordinate, the enum one isn't
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec![
+                    "Human.name",
+                    "Mutation.addVet",
+                    "Pet.__typename",
+                    "Root.animalOwner",
+                    "Root.pets",
+                    "VetDetailsInput",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_excludes_unused_variables() {
+            let document = r#"
+                query Owner($includeAge: Boolean!, $unused: String, $details: VetDetailsInput!) {
+                    animalOwner {
+                        name
+                        age @include(if: $includeAge)
+                    }
+                    pets(first: 10) {
+                        ...PetFields
+                    }
+                }
+
+                fragment PetFields on Pet {
+                    __typename
+                }
+
+                mutation AddVet($details: VetDetailsInput!, $name: String!, $skipped: Int) {
+                    addVet(details: $details)
+                    addCat(name: $name) {
+                        name
+                    }
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_used_variables(document.to_string())
+                .expect("Should extract variables");
+            assert_eq!(
+                result,
+                vec![
+                    variable("includeAge", "Boolean!"),
+                    variable("details", "VetDetailsInput!"),
+                    variable("name", "String!"),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_variables_in_fragments_and_lists() {
+            let document = r#"
+                query Pets($first: Int, $species: Species) {
+                    ...PetsFragment
+                }
+
+                fragment PetsFragment on Root {
+                    pets(first: $first, species: $species) {
+                        __typename
+                    }
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_used_variables(document.to_string())
+                .expect("Should extract variables");
+            assert_eq!(
+                result,
+                vec![variable("first", "Int"), variable("species", "Species")]
+            );
+        }
+    }
+
+    mod root_aliases_tests {
+        use super::*;
+
+        fn options(lazy_type_map: bool) -> ExtractionOptions {
+            ExtractionOptions {
+                root_aliases: Some(HashMap::from([("Viewer".to_string(), "Human".to_string())])),
+                lazy_type_map: Some(lazy_type_map),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_custom_alias_resolves_to_actual_type() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ViewerFields
+                    }
+                }
+
+                fragment ViewerFields on Viewer {
+                    name
+                    contactDetails {
+                        email
+                    }
+                }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                assert_eq!(
+                    extract_and_sort_with_options(document, options(lazy_type_map)),
+                    vec![
+                        "ContactDetails.email",
+                        "Human.contactDetails",
+                        "Human.name",
+                        "Root.animalOwner",
+                    ]
+                );
+            }
+        }
+
+        #[test]
+        fn test_custom_alias_with_has_field() {
+            let parsed_schema = ParsedSchema::with_options(PETS_SCHEMA.to_string(), options(false))
+                .expect("Should parse schema");
+
+            assert!(parsed_schema.has_field("Viewer.name".to_string()).unwrap());
+            assert!(!parsed_schema.has_field("Viewer.breed".to_string()).unwrap());
+            assert!(parsed_schema.has_field("Query.pets".to_string()).unwrap());
+        }
+
+        #[test]
+        fn test_rejects_alias_shadowing_a_type() {
+            for alias in ["Dog", "Query"] {
+                let error = ParsedSchema::with_options(
+                    PETS_SCHEMA.to_string(),
+                    ExtractionOptions {
+                        root_aliases: Some(HashMap::from([(
+                            alias.to_string(),
+                            "Human".to_string(),
+                        )])),
+                        ..Default::default()
+                    },
+                )
+                .err()
+                .expect("Should reject the alias");
+                assert_eq!(
+                    error.reason,
+                    format!(
+                        "Root alias {} can't reuse the name of a schema type or root alias",
+                        alias
+                    )
+                );
+            }
+        }
+
+        #[test]
+        fn test_rejects_schema_root_of_undefined_type() {
+            let error = ParsedSchema::new(
+                "schema { query: Root subscription: Events } type Root { ping: Int }".to_string(),
+            )
+            .err()
+            .expect("Should reject the schema");
+            assert_eq!(
+                error.reason,
+                "Root alias Subscription resolves to Events, which the schema doesn't define"
+            );
+        }
+
+        #[test]
+        fn test_rejects_alias_to_undefined_type() {
+            let error = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    root_aliases: Some(HashMap::from([("Viewer".to_string(), "User".to_string())])),
+                    ..Default::default()
+                },
+            )
+            .err()
+            .expect("Should reject the alias");
+            assert_eq!(
+                error.reason,
+                "Root alias Viewer resolves to User, which the schema doesn't define"
+            );
+        }
+    }
+
+    mod deprecated_usages_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            type Query {
+                pets(first: Int, limit: Int @deprecated, filter: PetFilter): [Pet]
+                pet(id: ID): Pet
+            }
+
+            type Pet {
+                name: String
+                nickname: String @deprecated(reason: "Use name")
+            }
+
+            input PetFilter {
+                name: String
+                species: String @deprecated
+                owner: OwnerFilter
+            }
+
+            input OwnerFilter {
+                age: Int @deprecated
+            }
+        "#;
+
+        fn usage(coordinate: &str, kind: CoordinateKind) -> DeprecatedUsage {
+            DeprecatedUsage {
+                coordinate: coordinate.to_string(),
+                kind,
+                reason: None,
+            }
+        }
+
+        fn extract(document: &str) -> Vec<DeprecatedUsage> {
+            ParsedSchema::new(SCHEMA.to_string())
+                .expect("Should parse schema")
+                .extract_deprecated_usages(document.to_string())
+                .expect("Should extract deprecated usages")
+        }
+
+        #[test]
+        fn test_deprecated_argument_supplied() {
+            let document = r#"
+                {
+                    pets(first: 10, limit: 5) {
+                        name
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                extract(document),
+                vec![usage("Query.pets(limit:)", CoordinateKind::Argument)]
+            );
+        }
+
+        #[test]
+        fn test_deprecated_fields_and_input_fields() {
+            let document = r#"
+                query Pets($age: Int) {
+                    pets(filter: { name: "Rex", species: "dog", owner: { age: $age } }) {
+                        nickname
+                    }
+                    pet(id: 1) {
+                        ...PetFields
+                    }
+                }
+
+                fragment PetFields on Pet {
+                    nickname
+                }
+            "#;
+
+            assert_eq!(
+                extract(document),
+                vec![
+                    usage("OwnerFilter.age", CoordinateKind::InputField),
+                    DeprecatedUsage {
+                        reason: Some("Use name".to_string()),
+                        ..usage("Pet.nickname", CoordinateKind::OutputField)
+                    },
+                    usage("PetFilter.species", CoordinateKind::InputField),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_reports_deprecation_reason() {
+            let usages = extract("{ pet(id: 1) { nickname } pets(limit: 1) { name } }");
+
+            let reasons: Vec<_> = usages
+                .iter()
+                .map(|usage| (usage.coordinate.as_str(), usage.reason.as_deref()))
+                .collect();
+            assert_eq!(
+                reasons,
+                vec![
+                    ("Pet.nickname", Some("Use name")),
+                    ("Query.pets(limit:)", None)
+                ]
+            );
+        }
+
+        #[test]
+        fn test_no_deprecated_usages() {
+            let document = r#"
+                {
+                    pets(first: 10, filter: { name: "Rex" }) {
+                        name
+                    }
+                }
+            "#;
+
+            assert!(extract(document).is_empty());
+        }
+    }
+
+    mod extract_flagged_by_directive_tests {
+        use super::*;
+
+        #[test]
+        fn test_flags_coordinates_of_tagged_types() {
+            let parsed_schema = ParsedSchema::new(
+                r#"
+                directive @internal on OBJECT | INPUT_OBJECT
+
+                type Query {
+                    account: Account
+                    ledger: Ledger
+                }
+
+                type Account {
+                    name: String
+                    ledger: Ledger
+                }
+
+                type Ledger @internal {
+                    balance: Int
+                }
+
+                extend type Account @audited
+                "#
+                .to_string(),
+            )
+            .expect("Should parse schema");
+            let document = "{ account { name ledger { balance } } }";
+
+            let flagged = |directive_name: &str| {
+                let mut result = parsed_schema
+                    .extract_flagged_by_directive(document.to_string(), directive_name.to_string())
+                    .expect("Should extract coordinates");
+                result.sort();
+                result
+            };
+
+            assert_eq!(flagged("internal"), vec!["Ledger.balance"]);
+            assert_eq!(flagged("@internal"), vec!["Ledger.balance"]);
+            assert_eq!(flagged("audited"), vec!["Account.ledger", "Account.name"]);
+            assert!(flagged("deprecated").is_empty());
+        }
+    }
+
+    mod extract_abstract_returning_fields_tests {
+        use super::*;
+
+        #[test]
+        fn test_only_fields_returning_interfaces_and_unions() {
+            let document = r#"
+                {
+                    allSpecies {
+                        name
+                    }
+                    pets {
+                        ... on Dog {
+                            breed
+                        }
+                    }
+                    animalOwner {
+                        name
+                        bestFriend {
+                            name
+                        }
+                    }
+                }
+            "#;
+
+            let mut result = PARSED_SCHEMA
+                .extract_abstract_returning_fields(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+
+            assert_eq!(result, vec!["Root.allSpecies", "Root.pets"]);
+        }
+    }
+
+    mod extract_coordinate_to_operations_tests {
+        use super::*;
+
+        fn operations(names: &[&str]) -> Vec<String> {
+            names.iter().map(|name| name.to_string()).collect()
+        }
+
+        #[test]
+        fn test_operations_sharing_coordinates() {
+            let document = r#"
+                query OwnerName {
+                    animalOwner {
+                        ...ownerFields
+                    }
+                }
+
+                query OwnerContact {
+                    animalOwner {
+                        name
+                        contactDetails {
+                            email
+                        }
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_coordinate_to_operations(document.to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(
+                result,
+                HashMap::from([
+                    (
+                        "Root.animalOwner".to_string(),
+                        operations(&["OwnerContact", "OwnerName"])
+                    ),
+                    (
+                        "Human.name".to_string(),
+                        operations(&["OwnerContact", "OwnerName"])
+                    ),
+                    (
+                        "Human.contactDetails".to_string(),
+                        operations(&["OwnerContact"])
+                    ),
+                    (
+                        "ContactDetails.email".to_string(),
+                        operations(&["OwnerContact"])
+                    ),
+                ])
+            );
+        }
+
+        #[test]
+        fn test_anonymous_operation_placeholder() {
+            let result = PARSED_SCHEMA
+                .extract_coordinate_to_operations("{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(result["Human.name"], operations(&["<anonymous>"]));
+        }
+    }
+
+    mod is_read_only_tests {
+        use super::*;
+
+        #[test]
+        fn test_query_only_document_is_read_only() {
+            let document = r#"
+                query Owner {
+                    animalOwner {
+                        ...ownerFields
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                }
+
+                { allSpecies { name } }
+            "#;
+
+            assert!(PARSED_SCHEMA
+                .is_read_only(document.to_string())
+                .expect("Should parse document"));
+        }
+
+        #[test]
+        fn test_document_with_mutation_is_not_read_only() {
+            let document = r#"
+                query Owner {
+                    animalOwner {
+                        name
+                    }
+                }
+
+                mutation AddCat {
+                    addCat {
+                        name
+                    }
+                }
+            "#;
+
+            assert!(!PARSED_SCHEMA
+                .is_read_only(document.to_string())
+                .expect("Should parse document"));
+        }
+    }
+
+    mod is_introspection_only_tests {
+        use super::*;
+
+        fn is_introspection_only(document: &str) -> bool {
+            PARSED_SCHEMA
+                .is_introspection_only(document.to_string())
+                .expect("Should parse document")
+        }
+
+        #[test]
+        fn test_pure_introspection_query() {
+            let document = r#"
+                query IntrospectionQuery {
+                    __schema {
+                        queryType {
+                            name
+                        }
+                    }
+                    ...typeFields
+                }
+
+                fragment typeFields on Root {
+                    __type(name: "Human") {
+                        fields {
+                            name
+                        }
+                    }
+                }
+            "#;
+
+            assert!(is_introspection_only(document));
+        }
+
+        #[test]
+        fn test_mixed_query() {
+            let document = r#"
+                {
+                    __schema {
+                        queryType {
+                            name
+                        }
+                    }
+                    animalOwner {
+                        name
+                    }
+                }
+            "#;
+
+            assert!(!is_introspection_only(document));
+        }
+
+        #[test]
+        fn test_documents_without_operations() {
+            assert!(!is_introspection_only(""));
+            assert!(!is_introspection_only(
+                "fragment typeFields on Root { __typename }"
+            ));
+        }
+    }
+
+    mod extract_directives_tests {
+        use super::*;
+
+        #[test]
+        fn test_distinct_directives_across_document() {
+            let document = r#"
+                query Owner($withName: Boolean!) @cached {
+                    animalOwner {
+                        name @include(if: $withName)
+                        ...ownerFields @skip(if: false)
+                        ... on Human @include(if: true) {
+                            age @cached
+                        }
+                    }
+                }
+
+                fragment ownerFields on Human @tracked {
+                    contactDetails @skip(if: true) {
+                        email @tracked
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                PARSED_SCHEMA
+                    .extract_directives(document.to_string())
+                    .expect("Should extract directives"),
+                vec!["@cached", "@include", "@skip", "@tracked"]
+            );
+        }
+
+        #[test]
+        fn test_no_directives() {
+            assert!(PARSED_SCHEMA
+                .extract_directives("{ animalOwner { name } }".to_string())
+                .expect("Should extract directives")
+                .is_empty());
+        }
+
+        #[test]
+        fn test_prefixed_in_document_order() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    coordinate_prefix: Some("pets".to_string()),
+                    order: Some(CoordinateOrder::Document),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert_eq!(
+                parsed_schema
+                    .extract_directives(
+                        "{ animalOwner @skip(if: false) { name @cached age @skip(if: true) } }"
+                            .to_string()
+                    )
+                    .expect("Should extract directives"),
+                vec!["pets:@skip", "pets:@cached"]
+            );
+        }
+    }
+
+    mod extract_with_max_depth_tests {
+        use super::*;
+
+        #[test]
+        fn test_max_depth_of_nested_query() {
+            let document = r#"
+                query Owner {
+                    animalOwner {
+                        ...ownerFields
+                    }
+                    allSpecies {
+                        name
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    contactDetails {
+                        email
+                    }
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_with_max_depth(document.to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(result.max_depth, 3);
+            let mut coordinates = result.coordinates;
+            coordinates.sort();
+            assert_eq!(
+                coordinates,
+                vec![
+                    "Animal.name",
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Root.allSpecies",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_max_depth_without_fields() {
+            let result = PARSED_SCHEMA
+                .extract_with_max_depth("fragment ownerFields on Human { name }".to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(result.max_depth, 0);
+        }
+    }
+
+    mod extract_schema_coordinates_multi_tests {
+        use super::*;
+
+        #[test]
+        fn test_extracts_each_part() {
+            let blob =
+                "{ animalOwner { name } }\0{ pets {\0mutation { addCat(name: \"Tom\") { name } }\0";
+
+            let parts = PARSED_SCHEMA
+                .extract_schema_coordinates_multi(blob.to_string(), "\0".to_string())
+                .expect("Should split blob");
+
+            assert_eq!(parts.len(), 3);
+            let mut first = parts[0].coordinates.clone();
+            first.sort();
+            assert_eq!(first, vec!["Human.name", "Root.animalOwner"]);
+            assert_eq!(parts[0].error, None);
+
+            assert!(parts[1].coordinates.is_empty());
+            assert!(parts[1]
+                .error
+                .as_ref()
+                .is_some_and(|error| error.starts_with("Failed to parse document: ")));
+
+            let mut third = parts[2].coordinates.clone();
+            third.sort();
+            assert_eq!(third, vec!["Cat.name", "Mutation.addCat"]);
+            assert_eq!(parts[2].error, None);
+        }
+
+        #[test]
+        fn test_rejects_empty_separator() {
+            assert!(PARSED_SCHEMA
+                .extract_schema_coordinates_multi(
+                    "{ animalOwner { name } }".to_string(),
+                    String::new()
+                )
+                .is_err());
+        }
+    }
+
+    mod extract_with_origins_tests {
+        use super::*;
+
+        #[test]
+        fn test_tags_coordinates_with_origins() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    include_arguments: Some(true),
+                    include_enum_values: Some(true),
+                    include_directives: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let document = r#"
+                mutation AddVet($details: VetDetailsInput!) @cached {
+                    addVet(details: $details)
+                    addCat(name: "Tom") {
+                        name @include(if: true)
+                    }
+                }
+
+                query Pets($details: VetDetailsInput) {
+                    pets(species: DOG) {
+                        ... on Cat {
+                            name
+                        }
+                    }
+                }
+            "#;
+
+            let origins = parsed_schema
+                .extract_with_origins(document.to_string())
+                .expect("Should extract coordinates");
+
+            use CoordinateOrigin::*;
+            let expected = vec![
+                ("@cached", vec![Directive]),
+                ("@include", vec![Directive]),
+                ("Cat.name", vec![Selection]),
+                ("Mutation.addCat", vec![Selection]),
+                ("Mutation.addCat(name:)", vec![Argument]),
+                ("Mutation.addVet", vec![Selection]),
+                ("Mutation.addVet(details:)", vec![Argument]),
+                ("Root.pets", vec![Selection]),
+                ("Root.pets(species:)", vec![Argument]),
+                ("Species", vec![Argument]),
+                ("Species.DOG", vec![Argument]),
+                ("VetDetailsInput", vec![Variable]),
+            ];
+            let expected: Vec<CoordinateOrigins> = expected
+                .into_iter()
+                .map(|(coordinate, origins)| CoordinateOrigins {
+                    coordinate: coordinate.to_string(),
+                    origins,
+                })
+                .collect();
+            assert_eq!(origins, expected);
+        }
+    }
+
+    mod extract_with_unresolved_tests {
+        use super::*;
+
+        #[test]
+        fn test_lists_missing_fragments() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ownerFields
+                        ...contactFields
+                    }
+                    pets {
+                        ...petFields
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                    ...contactFields
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_with_unresolved(document.to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(
+                result.unresolved_fragments,
+                vec!["contactFields", "petFields"]
+            );
+            let mut coordinates = result.coordinates;
+            coordinates.sort();
+            assert_eq!(
+                coordinates,
+                vec!["Human.name", "Root.animalOwner", "Root.pets"]
+            );
+        }
+    }
+
+    mod validate_and_extract_tests {
+        use super::*;
+
+        #[test]
+        fn test_collects_every_issue() {
+            let document = "query Pets($filter: PetFilter) {\n  animalOwner { name wings ...missing }\n  pets(species: DOG, limit: 2) { ... on Fish { fins } }\n}\nfragment ownerFields on Person { name }";
+
+            let result = PARSED_SCHEMA
+                .validate_and_extract(document.to_string())
+                .expect("Should extract coordinates");
+
+            let issues: Vec<_> = result
+                .issues
+                .iter()
+                .map(|issue| {
+                    (
+                        issue.kind,
+                        issue.message.as_str(),
+                        issue.position.line,
+                        issue.position.column,
+                    )
+                })
+                .collect();
+            assert_eq!(
+                issues,
+                vec![
+                    (IssueKind::UnknownType, "Unknown type \"PetFilter\"", 1, 12),
+                    (
+                        IssueKind::UnknownField,
+                        "Unknown field \"Human.wings\"",
+                        2,
+                        22
+                    ),
+                    (
+                        IssueKind::UnknownFragment,
+                        "Unknown fragment \"missing\"",
+                        2,
+                        31
+                    ),
+                    (
+                        IssueKind::UnknownArgument,
+                        "Unknown argument \"Root.pets(limit:)\"",
+                        3,
+                        3
+                    ),
+                    (IssueKind::UnknownType, "Unknown type \"Fish\"", 3, 38),
+                    (IssueKind::UnknownType, "Unknown type \"Person\"", 5, 1),
+                ]
+            );
+
+            let mut coordinates = result.coordinates;
+            coordinates.sort();
+            assert_eq!(
+                coordinates,
+                vec![
+                    "Fish.fins",
+                    "Human.name",
+                    "Human.wings",
+                    "Root.animalOwner",
+                    "Root.pets",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_valid_document_has_no_issues() {
+            let result = PARSED_SCHEMA
+                .validate_and_extract(
+                    "query ($first: Int) { pets(first: $first) { __typename ... on Dog { breed } } }"
+                        .to_string(),
+                )
+                .expect("Should extract coordinates");
+
+            assert!(result.issues.is_empty());
+        }
+    }
+
+    mod extract_with_etag_tests {
+        use super::*;
+
+        fn etag(document: &str) -> String {
+            PARSED_SCHEMA
+                .extract_with_etag(document.to_string())
+                .expect("Should extract coordinates")
+                .etag
+        }
+
+        #[test]
+        fn test_equivalent_queries_share_etag() {
+            let query = "query Owner { animalOwner { name age } }";
+            let equivalent = r#"
+                {
+                    owner: animalOwner {
+                        ...ownerFields
+                        name
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    age
+                }
+            "#;
+
+            assert_eq!(etag(query), etag(equivalent));
+            assert_eq!(etag(query).len(), 16);
+            assert_ne!(etag(query), etag("{ animalOwner { name } }"));
+        }
+
+        #[test]
+        fn test_etag_is_stable() {
+            let result = PARSED_SCHEMA
+                .extract_with_etag("{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+
+            // Changing the hash would invalidate every ETag handed out by earlier versions
+            assert_eq!(result.etag, "c656f43ddc1abccc");
+            assert_eq!(coordinates_etag(&[]), "cbf29ce484222325");
+        }
+    }
+
+    mod extract_streaming_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            {
+                animalOwner { name bestFriend { name age } }
+                owner: animalOwner { name }
+            }
+        "#;
+
+        #[test]
+        fn test_streams_each_coordinate_once() {
+            let mut streamed = Vec::new();
+            PARSED_SCHEMA
+                .extract_streaming_with(DOCUMENT, |coordinate| {
+                    streamed.push(coordinate);
+                    Ok(())
+                })
+                .expect("Should extract coordinates");
+
+            let mut batch = PARSED_SCHEMA
+                .extract_schema_coordinates(DOCUMENT.to_string())
+                .expect("Should extract coordinates");
+            batch.sort();
+            assert_eq!(streamed.len(), batch.len());
+            streamed.sort();
+            assert_eq!(streamed, batch);
+        }
+
+        #[test]
+        fn test_stops_calling_after_callback_error() {
+            let mut calls = 0;
+            let error = PARSED_SCHEMA
+                .extract_streaming_with(DOCUMENT, |coordinate| {
+                    calls += 1;
+                    Err(Error::from_reason(format!("Rejected {}", coordinate)))
+                })
+                .expect_err("Should surface the callback error");
+
+            assert_eq!(calls, 1);
+            assert_eq!(error.reason, "Rejected Root.animalOwner");
+        }
+    }
+
+    mod extract_counts_csv_tests {
+        use super::*;
+
+        #[test]
+        fn test_counts_usages_sorted_by_coordinate() {
+            let document = r#"
+                {
+                    pets {
+                        ... on Dog { name }
+                    }
+                    animalOwner { name }
+                    owner: animalOwner { name age }
+                }
+            "#;
+
+            let csv = PARSED_SCHEMA
+                .extract_counts_csv(document.to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(
+                csv,
+                "coordinate,count\nDog.name,1\nHuman.age,1\nHuman.name,2\nRoot.animalOwner,2\nRoot.pets,1\n"
+            );
+        }
+
+        #[test]
+        fn test_counts_each_spread_of_a_fragment() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...ownerFields
+                        ...ownerFields
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                }
+            "#;
+
+            let csv = PARSED_SCHEMA
+                .extract_counts_csv(document.to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(csv, "coordinate,count\nHuman.name,2\nRoot.animalOwner,1\n");
+        }
+
+        #[test]
+        fn test_quotes_coordinates_containing_commas() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    separator: Some(",".to_string()),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let csv = parsed_schema
+                .extract_counts_csv("{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(
+                csv,
+                "coordinate,count\n\"Human,name\",1\n\"Root,animalOwner\",1\n"
+            );
+            assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+        }
+    }
+
+    mod extract_with_spans_tests {
+        use super::*;
+
+        #[test]
+        fn test_spans_slice_back_to_names() {
+            let document = "query Owner($details: VetDetailsInput) {\n\towner: animalOwner {\n    name\n    ...ownerFields\n  }\n  pets(species: DOG, first: 2) { ...petFields }\n}\n\nfragment ownerFields on Human { name age }\nfragment petFields on Pet { __typename }";
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    include_arguments: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let result = parsed_schema
+                .extract_with_spans(document.to_string())
+                .expect("Should extract coordinates");
+            let sliced: Vec<(&str, Vec<&str>)> = result
+                .iter()
+                .map(|coordinate_spans| {
+                    let names = coordinate_spans
+                        .spans
+                        .iter()
+                        .map(|span| &document[span.start as usize..span.end as usize])
+                        .collect();
+                    (coordinate_spans.coordinate.as_str(), names)
+                })
+                .collect();
+
+            assert_eq!(
+                sliced,
+                vec![
+                    ("Human.age", vec!["age"]),
+                    ("Human.name", vec!["name", "name"]),
+                    ("Pet.__typename", vec!["__typename"]),
+                    ("Root.animalOwner", vec!["animalOwner"]),
+                    ("Root.pets", vec!["pets"]),
+                    ("Root.pets(first:)", vec!["first"]),
+                    ("Root.pets(species:)", vec!["species"]),
+                    ("VetDetailsInput", vec![]),
+                ]
+            );
+            assert_eq!(
+                result[1].spans,
+                vec![
+                    SourceSpan { start: 67, end: 71 },
+                    SourceSpan {
+                        start: 178,
+                        end: 182
+                    },
+                ]
+            );
+        }
+    }
+
+    mod extract_with_depths_tests {
+        use super::*;
+
+        fn depth(coordinate: &str, min_depth: u32) -> CoordinateDepth {
+            CoordinateDepth {
+                coordinate: coordinate.to_string(),
+                min_depth,
+            }
+        }
+
+        #[test]
+        fn test_depths_of_nested_fields() {
+            let document = r#"
+                {
+                    animalOwner {
+                        contactDetails {
+                            email
+                        }
+                    }
+                }
+            "#;
+
+            assert_eq!(
+                PARSED_SCHEMA
+                    .extract_with_depths(document.to_string())
+                    .expect("Should extract coordinates"),
+                vec![
+                    depth("ContactDetails.email", 3),
+                    depth("Human.contactDetails", 2),
+                    depth("Root.animalOwner", 1),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_smallest_depth_through_fragments() {
+            let document = r#"
+                query Owner($details: VetDetailsInput) {
+                    animalOwner {
+                        bestFriend {
+                            ...ownerFields
+                        }
+                        ...ownerFields
+                    }
+                }
+
+                fragment ownerFields on Human {
+                    name
+                }
+            "#;
+
+            assert_eq!(
+                PARSED_SCHEMA
+                    .extract_with_depths(document.to_string())
+                    .expect("Should extract coordinates"),
+                vec![
+                    depth("Human.bestFriend", 2),
+                    depth("Human.name", 2),
+                    depth("Root.animalOwner", 1),
+                    depth("VetDetailsInput", 0),
+                ]
+            );
+        }
+    }
+
+    mod extract_attributed_tests {
+        use super::*;
+
+        #[test]
+        fn test_attributes_coordinates_to_fragments() {
+            let document = r#"
+                query GetOwner($details: VetDetailsInput!) {
+                    animalOwner {
+                        name
+                        ...ownerDetails
+                    }
+                    pets {
+                        ...doggoDetails
+                    }
+                }
+
+                mutation AddVet($details: VetDetailsInput!) {
+                    addVet(details: $details)
+                }
+
+                fragment ownerDetails on Human {
+                    age
+                    contactDetails {
+                        ...contactFields
+                    }
+                }
+
+                fragment contactFields on ContactDetails {
+                    email
+                }
+
+                fragment doggoDetails on Dog {
+                    name
+                    breed
+                }
+            "#;
+
+            let result = PARSED_SCHEMA
+                .extract_attributed(document.to_string())
+                .expect("Should extract coordinates");
+
+            let to_strings = |coordinates: &[&str]| -> Vec<String> {
+                coordinates.iter().map(|c| c.to_string()).collect()
+            };
+            assert_eq!(
+                result,
+                vec![
+                    AttributedCoordinates {
+                        operation: Some("GetOwner".to_string()),
+                        direct: to_strings(&[
+                            "Human.name",
+                            "Root.animalOwner",
+                            "Root.pets",
+                            "VetDetailsInput",
+                        ]),
+                        fragments: HashMap::from([
+                            (
+                                "ownerDetails".to_string(),
+                                to_strings(&["Human.age", "Human.contactDetails"]),
+                            ),
+                            (
+                                "contactFields".to_string(),
+                                to_strings(&["ContactDetails.email"]),
+                            ),
+                            (
+                                "doggoDetails".to_string(),
+                                to_strings(&["Dog.breed", "Dog.name"]),
+                            ),
+                        ]),
+                    },
+                    AttributedCoordinates {
+                        operation: Some("AddVet".to_string()),
+                        direct: to_strings(&["Mutation.addVet", "VetDetailsInput"]),
+                        fragments: HashMap::new(),
+                    },
+                ]
+            );
+        }
+
+        #[test]
+        fn test_anonymous_operation() {
+            let result = PARSED_SCHEMA
+                .extract_attributed("{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(result.len(), 1);
+            assert_eq!(result[0].operation, None);
+            assert_eq!(result[0].direct, vec!["Human.name", "Root.animalOwner"]);
+        }
+    }
+
+    mod unicode_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            "Die Wurzel 🌳"
+            type Query {
+                "Grüße, 世界"
+                greet(message: String = "héllo 👋"): Greeting
+            }
+
+            type Greeting {
+                text: String
+            }
+        "#;
+
+        #[test]
+        fn test_emoji_string_arguments() {
+            let document = r#"
+                {
+                    greet(message: "🐶🐱 naïve café ✨") {
+                        text
+                    }
+                }
+            "#;
+
+            let mut result = ParsedSchema::new(SCHEMA.to_string())
+                .expect("Should parse schema")
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            assert_eq!(result, vec!["Greeting.text", "Query.greet"]);
+        }
+
+        #[test]
+        fn test_multi_byte_separator() {
+            let parsed_schema = ParsedSchema::with_options(
+                SCHEMA.to_string(),
+                ExtractionOptions {
+                    separator: Some("→".to_string()),
+                    include_arguments: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let mut result = parsed_schema
+                .extract_schema_coordinates(r#"{ greet(message: "🎉") { text } }"#.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            assert_eq!(
+                result,
+                vec!["Greeting→text", "Query→greet", "Query→greet(message:)"]
+            );
+            assert!(parsed_schema
+                .has_field("Greeting→text".to_string())
+                .unwrap());
+            assert!(!parsed_schema
+                .has_field("Greeting→tëxt".to_string())
+                .unwrap());
+        }
+
+        #[test]
+        fn test_unicode_identifiers_are_parse_errors() {
+            // The GraphQL spec limits names to ASCII letters, digits and underscores
+            assert!(ParsedSchema::new("type Query { grüße: String }".to_string()).is_err());
+
+            let result = ParsedSchema::new(SCHEMA.to_string())
+                .expect("Should parse schema")
+                .extract_schema_coordinates("{ grüße }".to_string());
+            assert!(result.is_err());
+        }
+
+        #[test]
+        fn test_tolerant_extraction_with_multi_byte_text() {
+            let document = r#"
+                # Ünïcödé comment with a brace } 🙃
+                query Greet {
+                    greet(message: "${emoji} 🚀 }") {
+                        text
+                    }
+                }
+            "#;
+
+            let mut result = ParsedSchema::new(SCHEMA.to_string())
+                .expect("Should parse schema")
+                .extract_tolerant(document.to_string());
+            result.sort();
+            assert_eq!(result, vec!["Greeting.text", "Query.greet"]);
+        }
+    }
+
+    mod coordinate_prefix_tests {
+        use super::*;
+
+        fn prefix_options(separator: Option<&str>) -> ExtractionOptions {
+            ExtractionOptions {
+                coordinate_prefix: Some("pets".to_string()),
+                coordinate_prefix_separator: separator.map(str::to_string),
+                include_arguments: Some(true),
+                include_directives: Some(true),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_prefix_applies_to_every_coordinate() {
+            let document = r#"
+                mutation AddVet($details: VetDetailsInput!, $skip: Boolean!) {
+                    addVet(details: $details) @skip(if: $skip)
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, prefix_options(None)),
+                vec![
+                    "pets:@skip",
+                    "pets:Mutation.addVet",
+                    "pets:Mutation.addVet(details:)",
+                    "pets:VetDetailsInput",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_custom_prefix_separator() {
+            assert_eq!(
+                extract_and_sort_with_options(
+                    "{ animalOwner { name } }",
+                    prefix_options(Some("/"))
+                ),
+                vec!["pets/Human.name", "pets/Root.animalOwner"]
+            );
+        }
+
+        #[test]
+        fn test_prefix_applies_to_listed_coordinates() {
+            let parsed_schema =
+                ParsedSchema::with_options(PETS_SCHEMA.to_string(), prefix_options(None))
+                    .expect("Should parse schema");
+
+            assert_eq!(
+                parsed_schema.all_input_coordinates(),
+                vec![
+                    "pets:VetDetailsInput.address",
+                    "pets:VetDetailsInput.name",
+                    "pets:VetDetailsInput.phone",
+                ]
+            );
+            assert!(parsed_schema
+                .all_coordinates()
+                .contains(&"pets:Human.name".to_string()));
+        }
+    }
+
+    mod forward_reference_tests {
+        use super::*;
+
+        // Every type is referenced before the SDL declares it
+        const SCHEMA: &str = r#"
+            type Query {
+                owner: Owner
+            }
+
+            type Owner implements Named {
+                pet: Pet
+            }
+
+            type Pet {
+                toy: Toy
+            }
+
+            type Toy {
+                name: String
+            }
+
+            interface Named {
+                name: String
+            }
+        "#;
+
+        #[test]
+        fn test_types_declared_after_their_use() {
+            let document = "{ owner { name pet { toy { name } } } }";
+
+            for lazy_type_map in [false, true] {
+                let parsed_schema = ParsedSchema::with_options(
+                    SCHEMA.to_string(),
+                    ExtractionOptions {
+                        lazy_type_map: Some(lazy_type_map),
+                        error_on_unknown_fields: Some(true),
+                        ..Default::default()
+                    },
+                )
+                .expect("Should parse schema");
+                let mut result = parsed_schema
+                    .extract_schema_coordinates(document.to_string())
+                    .expect("Should extract coordinates");
+                result.sort();
+
+                assert_eq!(
+                    result,
+                    vec![
+                        "Owner.name",
+                        "Owner.pet",
+                        "Pet.toy",
+                        "Query.owner",
+                        "Toy.name",
+                    ]
+                );
+            }
+        }
+    }
+
+    mod same_named_fields_tests {
+        use super::*;
+
+        #[test]
+        fn test_same_named_fields_resolve_per_type() {
+            let parsed_schema = ParsedSchema::with_options(
+                r#"
+                type Query {
+                    person: Person
+                    company: Company
+                }
+
+                type Person {
+                    name: String
+                    address: String
+                }
+
+                type Company {
+                    name: LegalName
+                    address: Address
+                }
+
+                type LegalName {
+                    registered: String
+                }
+
+                type Address {
+                    city: String
+                }
+                "#
+                .to_string(),
+                ExtractionOptions {
+                    error_on_subselection_of_scalar: Some(true),
+                    error_on_unknown_fields: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let document = r#"
+                {
+                    person {
+                        name
+                        address
+                    }
+                    company {
+                        name {
+                            registered
+                        }
+                        address {
+                            city
+                        }
+                    }
+                }
+            "#;
+
+            let mut result = parsed_schema
+                .extract_schema_coordinates(document.to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+
+            assert_eq!(
+                result,
+                vec![
+                    "Address.city",
+                    "Company.address",
+                    "Company.name",
+                    "LegalName.registered",
+                    "Person.address",
+                    "Person.name",
+                    "Query.company",
+                    "Query.person",
+                ]
+            );
+        }
+    }
+
+    mod shorthand_query_tests {
+        use super::*;
+
+        #[test]
+        fn test_shorthand_resolves_unconventional_query_root() {
+            let schema = r#"
+                schema {
+                    query: EntryPoint
+                }
+
+                type EntryPoint {
+                    viewer: Viewer
+                }
+
+                type Viewer {
+                    login: String
+                }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                let parsed_schema = ParsedSchema::with_options(
+                    schema.to_string(),
+                    ExtractionOptions {
+                        lazy_type_map: Some(lazy_type_map),
+                        ..Default::default()
+                    },
+                )
+                .expect("Should parse schema");
+                let mut result = parsed_schema
+                    .extract_schema_coordinates("{ viewer { login } }".to_string())
+                    .expect("Should extract coordinates");
+                result.sort();
+
+                assert_eq!(result, vec!["EntryPoint.viewer", "Viewer.login"]);
+            }
+        }
+    }
+
+    mod from_schemas_tests {
+        use super::*;
+
+        const USERS_SCHEMA: &str = r#"
+            type Query {
+                viewer: User
+            }
+
+            type User {
+                name: String
+            }
+        "#;
+
+        const ACCOUNTS_SCHEMA: &str = r#"
+            extend type User {
+                account: Account
+            }
+
+            type Account {
+                id: ID
+                owner: User
+            }
+        "#;
+
+        #[test]
+        fn test_descends_into_field_from_extension_in_another_schema() {
+            let document = "{ viewer { account { id owner { name } } } }";
+
+            for lazy_type_map in [false, true] {
+                let parsed_schema = ParsedSchema::from_schemas(
+                    vec![USERS_SCHEMA.to_string(), ACCOUNTS_SCHEMA.to_string()],
+                    Some(ExtractionOptions {
+                        lazy_type_map: Some(lazy_type_map),
+                        error_on_unknown_fields: Some(true),
+                        ..Default::default()
+                    }),
+                )
+                .expect("Should parse schemas");
+                let mut result = parsed_schema
+                    .extract_schema_coordinates(document.to_string())
+                    .expect("Should extract coordinates");
+                result.sort();
+
+                assert_eq!(
+                    result,
+                    vec![
+                        "Account.id",
+                        "Account.owner",
+                        "Query.viewer",
+                        "User.account",
+                        "User.name",
+                    ]
+                );
+            }
+        }
+
+        #[test]
+        fn test_error_names_schema_that_failed_to_parse() {
+            let error = ParsedSchema::from_schemas(
+                vec![USERS_SCHEMA.to_string(), "type {".to_string()],
+                None,
+            )
+            .err()
+            .expect("Should fail to parse");
+
+            assert!(error.reason.starts_with("Failed to parse schema 1:"));
+        }
+    }
+
+    mod root_type_extension_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            schema {
+                query: Root
+            }
+
+            type Root {
+                name: String
+            }
+
+            extend type Root {
+                extra: Int
+            }
+        "#;
+
+        #[test]
+        fn test_fields_added_by_root_type_extension() {
+            let document = r#"
+                {
+                    name
+                    ...extraFields
+                }
+
+                fragment extraFields on Query {
+                    extra
+                }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                let parsed_schema = ParsedSchema::with_options(
+                    SCHEMA.to_string(),
+                    ExtractionOptions {
+                        lazy_type_map: Some(lazy_type_map),
+                        // Fails the extraction if the extended field didn't reach the alias
+                        error_on_unknown_fields: Some(true),
+                        ..Default::default()
+                    },
+                )
+                .expect("Should parse schema");
+                let mut result = parsed_schema
+                    .extract_schema_coordinates(document.to_string())
+                    .expect("Should extract coordinates");
+                result.sort();
+
+                assert_eq!(result, vec!["Root.extra", "Root.name"]);
+            }
+        }
+    }
+
+    mod root_typename_tests {
+        use super::*;
+
+        #[test]
+        fn test_bare_root_typename() {
+            let result = PARSED_SCHEMA
+                .extract_schema_coordinates("{ __typename }".to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(result, vec!["Root.__typename"]);
+        }
+
+        #[test]
+        fn test_named_query_and_mutation_root_typename() {
+            let document = r#"
+                query HealthCheck {
+                    __typename
+                }
+
+                mutation MutationHealthCheck {
+                    __typename
+                }
+            "#;
+
+            assert_eq!(
+                extract_and_sort_with_options(document, ExtractionOptions::default()),
+                vec!["Mutation.__typename", "Root.__typename"]
+            );
+        }
+
+        #[test]
+        fn test_root_typename_with_strict_options() {
+            let options = ExtractionOptions {
+                error_on_unknown_fields: Some(true),
+                error_on_subselection_of_scalar: Some(true),
+                single_subscription_root: Some(true),
+                ..Default::default()
+            };
+            assert_eq!(
+                extract_and_sort_with_options("{ __typename }", options),
+                vec!["Root.__typename"]
+            );
+        }
+    }
+
+    mod coordinates_intersecting_tests {
+        use super::*;
+
+        #[test]
+        fn test_only_used_changed_coordinates_are_returned() {
+            let document = r#"
+                {
+                    animalOwner {
+                        name
+                    }
+                }
+            "#;
+            let changed_coordinates = vec![
+                "Human.name".to_string(),
+                "Human.contactDetails".to_string(),
+                "Root.animalOwner".to_string(),
+                "Cat.name".to_string(),
+            ];
+
+            let mut coordinates = PARSED_SCHEMA
+                .coordinates_intersecting(document.to_string(), changed_coordinates)
+                .expect("Should extract coordinates");
+            coordinates.sort();
+
+            assert_eq!(coordinates, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_no_overlap() {
+            let coordinates = PARSED_SCHEMA
+                .coordinates_intersecting(
+                    "{ animalOwner { name } }".to_string(),
+                    vec!["Cat.name".to_string()],
+                )
+                .expect("Should extract coordinates");
+
+            assert!(coordinates.is_empty());
+        }
+
+        #[test]
+        fn test_changed_coordinates_without_prefix() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    coordinate_prefix: Some("pets".to_string()),
+                    normalize_case: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            let mut coordinates = parsed_schema
+                .coordinates_intersecting(
+                    "{ animalOwner { name age } }".to_string(),
+                    vec![
+                        "Human.name".to_string(),
+                        "root.animalowner".to_string(),
+                        "Cat.name".to_string(),
+                    ],
+                )
+                .expect("Should extract coordinates");
+            coordinates.sort();
+
+            assert_eq!(
+                coordinates,
+                vec!["pets:human.name", "pets:root.animalowner"]
+            );
+        }
+    }
+
+    mod schema_as_document_tests {
+        use super::*;
+
+        #[test]
+        fn test_schema_passed_as_document() {
+            let error = PARSED_SCHEMA
+                .extract_schema_coordinates(PETS_SCHEMA.to_string())
+                .expect_err("Should fail to parse");
+
+            assert!(error.reason.starts_with("Failed to parse document:"));
+            assert!(error
+                .reason
+                .ends_with("The input looks like a schema (SDL) rather than a query document"));
+        }
+    }
+
+    mod document_padding_tests {
+        use super::*;
+
+        #[test]
+        fn test_trailing_null_bytes() {
+            let coordinates = PARSED_SCHEMA
+                .extract_schema_coordinates("{ animalOwner { name } }\0\0\0\0".to_string())
+                .expect("Should extract coordinates");
+
+            assert_eq!(coordinates.len(), 2);
+        }
+
+        #[test]
+        fn test_leading_byte_order_mark() {
+            let mut coordinates = PARSED_SCHEMA
+                .extract_schema_coordinates("\u{feff}{ animalOwner { name } }\n\0".to_string())
+                .expect("Should extract coordinates");
+            coordinates.sort();
+
+            assert_eq!(coordinates, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_empty_after_trimming() {
+            for document in ["", "\u{feff}", "\0\0", "\u{feff}  \n\0"] {
+                assert_eq!(
+                    PARSED_SCHEMA
+                        .extract_schema_coordinates(document.to_string())
+                        .expect("Should extract coordinates"),
+                    Vec::<String>::new()
+                );
+            }
+        }
+    }
+
+    mod type_renames_tests {
+        use super::*;
+
+        fn options(lazy_type_map: bool) -> ExtractionOptions {
+            ExtractionOptions {
+                type_renames: Some(HashMap::from([("Human".to_string(), "Person".to_string())])),
+                lazy_type_map: Some(lazy_type_map),
+                ..Default::default()
+            }
+        }
+
+        #[test]
+        fn test_coordinates_use_new_type_name() {
+            let document = r#"
+                {
+                    animalOwner {
+                        name
+                        ... on Human {
+                            contactDetails {
+                                email
+                            }
+                        }
+                    }
+                }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                assert_eq!(
+                    extract_and_sort_with_options(document, options(lazy_type_map)),
+                    vec![
+                        "ContactDetails.email",
+                        "Person.contactDetails",
+                        "Person.name",
+                        "Root.animalOwner",
+                    ]
+                );
+            }
+        }
+
+        #[test]
+        fn test_new_type_name_in_type_condition() {
+            let document = r#"
+                {
+                    animalOwner {
+                        ...OwnerFields
+                    }
+                }
+
+                fragment OwnerFields on Person {
+                    name
+                }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                assert_eq!(
+                    extract_and_sort_with_options(document, options(lazy_type_map)),
+                    vec!["Person.name", "Root.animalOwner"]
+                );
+            }
+        }
+
+        #[test]
+        fn test_listed_coordinates_use_new_type_name() {
+            let parsed_schema = ParsedSchema::with_options(PETS_SCHEMA.to_string(), options(false))
+                .expect("Should parse schema");
+            let coordinates = parsed_schema.all_coordinates();
+
+            assert!(coordinates.contains(&"Person.name".to_string()));
+            assert!(!coordinates.iter().any(|c| c.starts_with("Human")));
+        }
+    }
+
+    mod incremental_extractor_tests {
+        use super::*;
+
+        const OWNER_QUERY: &str = r#"
+            query Owner {
+                animalOwner { ...ownerFields }
+                pets { ...petFields }
+            }
+
+            fragment ownerFields on Human { name contactDetails { ...contactFields } }
+
+            fragment contactFields on ContactDetails { email }
+
+            fragment petFields on Pet { ... on Dog { breed } }
+        "#;
+
+        fn full_extraction(document: &str) -> Vec<String> {
+            let mut coordinates = PARSED_SCHEMA
                 .extract_schema_coordinates(document.to_string())
-                .expect("Should extract schema coordinates");
+                .expect("Should extract coordinates");
+            coordinates.sort();
+            coordinates
+        }
+
+        fn incremental_extraction(
+            extractor: &mut IncrementalExtractor,
+            document: &str,
+        ) -> Vec<String> {
+            let mut coordinates = extractor
+                .extract(document.to_string())
+                .expect("Should extract coordinates");
+            coordinates.sort();
+            coordinates
+        }
+
+        #[test]
+        fn test_recomputes_only_changed_fragments() {
+            let mut extractor = PARSED_SCHEMA
+                .incremental_extractor()
+                .expect("Should create extractor");
+
+            assert_eq!(
+                incremental_extraction(&mut extractor, OWNER_QUERY),
+                full_extraction(OWNER_QUERY)
+            );
+            assert_eq!(extractor.recomputed(), 4);
+
+            let edited =
+                OWNER_QUERY.replace("... on Dog { breed }", "... on Cat { favoriteMilkBrand }");
+            assert_eq!(
+                incremental_extraction(&mut extractor, &edited),
+                full_extraction(&edited)
+            );
+            assert_eq!(extractor.recomputed(), 1);
+
+            // Reformatting doesn't change a definition's content
+            let reformatted = edited.replace("{ name contactDetails", "{\n name\n contactDetails");
+            assert_eq!(
+                incremental_extraction(&mut extractor, &reformatted),
+                full_extraction(&edited)
+            );
+            assert_eq!(extractor.recomputed(), 0);
+        }
+
+        #[test]
+        fn test_drops_fragments_no_longer_spread() {
+            let mut extractor = PARSED_SCHEMA
+                .incremental_extractor()
+                .expect("Should create extractor");
+            incremental_extraction(&mut extractor, OWNER_QUERY);
+
+            let edited = OWNER_QUERY.replace("pets { ...petFields }", "");
+            assert_eq!(
+                incremental_extraction(&mut extractor, &edited),
+                vec![
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+            assert_eq!(extractor.recomputed(), 1);
+        }
+
+        #[test]
+        fn test_rejects_position_dependent_options() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    max_depth: Some(2),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert!(parsed_schema.incremental_extractor().is_err());
+        }
+
+        #[test]
+        fn test_matches_full_extraction_under_strict_options() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    error_on_unknown_fields: Some(true),
+                    error_on_unknown_type_conditions: Some(true),
+                    error_on_subselection_of_scalar: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let mut extractor = parsed_schema
+                .incremental_extractor()
+                .expect("Should create extractor");
+
+            for unused in [
+                "fragment Unused on Human { bogus }",
+                "fragment Unused on Bogus { name }",
+                "fragment Unused on Human { name { first } }",
+            ] {
+                let document = format!("{{ animalOwner {{ name }} }} {}", unused);
+                let mut expected = parsed_schema
+                    .extract_schema_coordinates(document.clone())
+                    .expect("Should skip the unused fragment");
+                expected.sort();
+                let mut coordinates = extractor
+                    .extract(document.clone())
+                    .expect("Should skip the unused fragment");
+                coordinates.sort();
+                assert_eq!(coordinates, expected);
+
+                // The fragment's problem is reported once it's spread
+                let spread = document.replace("{ name }", "{ name ...Unused }");
+                assert_eq!(
+                    extractor.extract(spread.clone()).unwrap_err().reason,
+                    parsed_schema
+                        .extract_schema_coordinates(spread)
+                        .unwrap_err()
+                        .reason
+                );
+            }
+        }
+
+        #[test]
+        fn test_rechecks_cached_subscription_root() {
+            let parsed_schema = ParsedSchema::with_options(
+                include_str!("../testing/messages.schema.graphql").to_string(),
+                ExtractionOptions {
+                    single_subscription_root: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+            let mut extractor = parsed_schema
+                .incremental_extractor()
+                .expect("Should create extractor");
+            let document = r#"
+                subscription OnMessage { ...rootFields }
+                fragment rootFields on Subscription { messageAdded { body } }
+            "#;
+            extractor
+                .extract(document.to_string())
+                .expect("Should extract coordinates");
+
+            // Only the fragment changes, but it adds a second root field to the subscription
+            let edited = document.replace("{ body } }", "{ body } messageDeleted }");
+            assert_eq!(
+                extractor.extract(edited).unwrap_err().reason,
+                "Subscription must select only one root field, but selects: messageAdded, messageDeleted"
+            );
+        }
+    }
+
+    #[cfg(feature = "flate2")]
+    mod gzip_tests {
+        use super::*;
+        use std::io::Write;
+
+        fn gzip(text: &str) -> Buffer {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(text.as_bytes())
+                .expect("Should compress text");
+            encoder.finish().expect("Should compress text").into()
+        }
+
+        #[test]
+        fn test_extracts_from_gzipped_inputs() {
+            let parsed_schema =
+                ParsedSchema::from_gzip(gzip(PETS_SCHEMA), None).expect("Should parse schema");
+
+            let mut coordinates = parsed_schema
+                .extract_schema_coordinates_gzip(gzip("{ animalOwner { name } }"))
+                .expect("Should extract coordinates");
+            coordinates.sort();
+            assert_eq!(coordinates, vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_distinguishes_decompression_errors() {
+            let error = ParsedSchema::from_gzip(PETS_SCHEMA.as_bytes().to_vec().into(), None)
+                .err()
+                .expect("Should reject uncompressed schema");
+            assert!(error.reason.starts_with("Failed to decompress schema: "));
+
+            let error = PARSED_SCHEMA
+                .extract_schema_coordinates_gzip(gzip("{ animalOwner {"))
+                .expect_err("Should reject invalid document");
+            assert!(error.reason.starts_with("Failed to parse document: "));
+        }
+
+        #[test]
+        fn test_limits_decompressed_size() {
+            let document = "{ animalOwner { name } }";
+            assert_eq!(
+                gunzip(&gzip(document), "document", document.len()).expect("Should decompress"),
+                document
+            );
+
+            let error = gunzip(&gzip(document), "document", document.len() - 1)
+                .expect_err("Should reject document over the limit");
+            assert_eq!(
+                error.reason,
+                "Failed to decompress document: it's larger than 23 bytes"
+            );
+        }
+    }
+
+    mod extractor_tests {
+        use super::*;
+
+        #[test]
+        fn test_repeated_extractions_do_not_leak() {
+            let mut extractor = PARSED_SCHEMA.extractor();
+
+            let mut first = extractor
+                .extract("{ animalOwner { name } }".to_string())
+                .expect("Should extract coordinates");
+            first.sort();
+            assert_eq!(first, vec!["Human.name", "Root.animalOwner"]);
+
+            let mut second = extractor
+                .extract("mutation { addCat(name: \"Tom\") { name } }".to_string())
+                .expect("Should extract coordinates");
+            second.sort();
+            assert_eq!(second, vec!["Cat.name", "Mutation.addCat"]);
+
+            assert!(extractor.extract("{ animalOwner {".to_string()).is_err());
+            assert!(extractor.coordinates.is_empty());
+
+            let third = extractor
+                .extract("{ animalOwner { age } }".to_string())
+                .expect("Should extract coordinates");
+            assert_eq!(third.len(), 2);
+        }
+
+        #[test]
+        fn test_extractor_uses_schema_options() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    separator: Some("::".to_string()),
+                    sorted: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert_eq!(
+                parsed_schema
+                    .extractor()
+                    .extract("{ animalOwner { name } }".to_string())
+                    .expect("Should extract coordinates"),
+                vec!["Human::name", "Root::animalOwner"]
+            );
+        }
+    }
+
+    mod conditional_fragment_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            query Owner($withContact: Boolean!) {
+                animalOwner {
+                    name
+                    ...contactFields @include(if: false)
+                    ... on Human @skip(if: $withContact) {
+                        bestFriend {
+                            age
+                        }
+                    }
+                }
+            }
+
+            fragment contactFields on Human {
+                contactDetails {
+                    email
+                }
+            }
+        "#;
+
+        #[test]
+        fn test_directives_on_spreads_and_inline_fragments() {
+            let options = ExtractionOptions {
+                include_directives: Some(true),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, options),
+                vec![
+                    "@include",
+                    "@skip",
+                    "ContactDetails.email",
+                    "Human.age",
+                    "Human.bestFriend",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_statically_excluded_spread_is_pruned() {
+            let options = ExtractionOptions {
+                include_directives: Some(true),
+                evaluate_static_conditions: Some(true),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract_and_sort_with_options(DOCUMENT, options),
+                vec![
+                    "@skip",
+                    "Human.age",
+                    "Human.bestFriend",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_statically_included_field_is_kept() {
+            let document = r#"
+                {
+                    animalOwner {
+                        name @include(if: true)
+                        contactDetails @skip(if: true) {
+                            email
+                        }
+                    }
+                }
+            "#;
+            let options = ExtractionOptions {
+                evaluate_static_conditions: Some(true),
+                ..Default::default()
+            };
+
+            assert_eq!(
+                extract_and_sort_with_options(document, options),
+                vec!["Human.name", "Root.animalOwner"]
+            );
+        }
+    }
+
+    mod scalar_predicate_tests {
+        use super::*;
+
+        const SCHEMA: &str = r#"
+            type Query {
+                config: JSON
+                owner: Owner
+            }
+
+            type Owner {
+                name: String
+            }
+        "#;
+
+        fn uppercase_scalars() -> ParsedSchema {
+            ParsedSchema::with_options(
+                SCHEMA.to_string(),
+                ExtractionOptions {
+                    error_on_subselection_of_scalar: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema")
+            .with_scalar_predicate(|type_name| type_name.chars().all(|c| c.is_ascii_uppercase()))
+        }
+
+        #[test]
+        fn test_predicate_scalars_are_leaves() {
+            let mut result = uppercase_scalars()
+                .extract_schema_coordinates("{ config owner { name } }".to_string())
+                .expect("Should extract coordinates");
             result.sort();
-            result
+            assert_eq!(result, vec!["Owner.name", "Query.config", "Query.owner"]);
+
+            let error = uppercase_scalars()
+                .extract_schema_coordinates("{ config { theme } }".to_string())
+                .expect_err("Should reject selections on predicate scalars");
+            assert_eq!(
+                error.reason,
+                "Selections on scalar or enum fields: Query.config"
+            );
+        }
+
+        #[test]
+        fn test_without_predicate_undefined_types_are_traversed() {
+            let mut result = ParsedSchema::new(SCHEMA.to_string())
+                .expect("Should parse schema")
+                .extract_schema_coordinates("{ config { theme } }".to_string())
+                .expect("Should extract coordinates");
+            result.sort();
+            assert_eq!(result, vec!["JSON.theme", "Query.config"]);
+        }
+    }
+
+    mod document_ast_tests {
+        use super::*;
+
+        #[test]
+        fn test_extracts_from_parsed_documents() {
+            let schema_doc = schema::parse_schema::<String>(PETS_SCHEMA).expect("Should parse");
+            let parsed_schema = ParsedSchema::from_schema_document(
+                schema_doc,
+                ExtractionOptions {
+                    include_arguments: Some(true),
+                    ..Default::default()
+                },
+            )
+            .expect("Should build schema");
+            let query_doc = query::parse_query::<String>("{ pets(first: 1) { __typename } }")
+                .expect("Should parse")
+                .into_static();
+
+            let coordinates = parsed_schema
+                .extract_from_query_document(&query_doc)
+                .expect("Should extract coordinates");
+
+            assert_eq!(
+                coordinates,
+                HashSet::from([
+                    "Pet.__typename".to_string(),
+                    "Root.pets".to_string(),
+                    "Root.pets(first:)".to_string(),
+                ])
+            );
+        }
+
+        #[test]
+        fn test_rejects_invalid_options() {
+            let schema_doc = schema::parse_schema::<String>(PETS_SCHEMA).expect("Should parse");
+            let error = ParsedSchema::from_schema_document(
+                schema_doc,
+                ExtractionOptions {
+                    separator: Some(String::new()),
+                    ..Default::default()
+                },
+            )
+            .err()
+            .expect("Should reject the separator");
+
+            assert_eq!(error.reason, "Coordinate separator must not be empty");
+        }
+    }
+
+    mod coordinate_sink_tests {
+        use super::*;
+
+        const DOCUMENT: &str = r#"
+            query {
+                animalOwner {
+                    ...ownerFields
+                    bestFriend {
+                        ...ownerFields
+                    }
+                }
+                allSpecies {
+                    name
+                }
+            }
+
+            fragment ownerFields on Human {
+                name
+                contactDetails {
+                    email
+                }
+            }
+        "#;
+
+        #[test]
+        fn test_sink_matches_standard_output() {
+            let mut emitted: Vec<String> = Vec::new();
+
+            PARSED_SCHEMA
+                .extract_into_sink(DOCUMENT, &mut emitted)
+                .expect("Should extract coordinates");
+            emitted.sort();
+            emitted.dedup();
+
+            assert_eq!(
+                emitted,
+                extract_and_sort_with_options(DOCUMENT, ExtractionOptions::default())
+            );
+        }
+
+        #[test]
+        fn test_sink_receives_each_use() {
+            let mut emitted: Vec<String> = Vec::new();
+
+            PARSED_SCHEMA
+                .extract_into_sink(DOCUMENT, &mut emitted)
+                .expect("Should extract coordinates");
+
+            // The fragment's three coordinates are emitted at both depths it's spread at
+            assert_eq!(emitted.len(), 10);
         }
+    }
+
+    mod fragment_registry_tests {
+        use super::*;
 
-        #[test]
-        fn test_basic_query() {
-        let document = r#"
-            {
-                animalOwner {
+        fn registry() -> FragmentRegistry {
+            FragmentRegistry::new(
+                r#"
+                fragment ownerFields on Human {
                     name
                     contactDetails {
-                        email
+                        ...contactFields
                     }
                 }
-            }
-        "#;
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "ContactDetails.email",
-                "Human.contactDetails",
-                "Human.name",
-                "Root.animalOwner",
-            ]
-        );
-    }
+                fragment contactFields on ContactDetails {
+                    email
+                }
 
-    #[test]
-    fn test_basic_mutation() {
-        let document = r#"
-            mutation {
-                addCat(name: "Palmerston") {
+                fragment dogFields on Dog {
+                    breed
+                }
+                "#
+                .to_string(),
+            )
+            .expect("Should parse fragments")
+        }
+
+        fn extract_and_sort(document: &str, registry: &FragmentRegistry) -> Vec<String> {
+            let mut result = PARSED_SCHEMA
+                .extract_with_registry(document.to_string(), registry)
+                .expect("Should extract coordinates");
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_operations_share_registry_fragments() {
+            let registry = registry();
+            assert_eq!(registry.length(), 3);
+
+            assert_eq!(
+                extract_and_sort("{ animalOwner { ...ownerFields } }", &registry),
+                vec![
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                ]
+            );
+            assert_eq!(
+                extract_and_sort("{ pets { ...dogFields } }", &registry),
+                vec!["Dog.breed", "Root.pets"]
+            );
+            assert_eq!(
+                extract_and_sort(
+                    "query Contact { animalOwner { contactDetails { ...contactFields } } }",
+                    &registry
+                ),
+                vec![
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Root.animalOwner",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_document_fragments_take_precedence() {
+            let document = r#"
+                { pets { ...dogFields } }
+
+                fragment dogFields on Dog {
                     name
-                    favoriteMilkBrand
                 }
-            }
-        "#;
+            "#;
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec!["Cat.favoriteMilkBrand", "Cat.name", "Mutation.addCat"]
-        );
+            assert_eq!(
+                extract_and_sort(document, &registry()),
+                vec!["Dog.name", "Root.pets"]
+            );
+        }
+
+        #[test]
+        fn test_rejects_operations() {
+            let error = FragmentRegistry::new("{ animalOwner { name } }".to_string())
+                .err()
+                .expect("Should reject the operation");
+
+            assert_eq!(
+                error.reason,
+                "Fragment registries can only contain fragment definitions"
+            );
+        }
     }
 
-    #[test]
-    fn test_extended_types() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                    contactDetails {
-                        email
-                        address {
-                            zip
-                        }
+    mod schema_registry_tests {
+        use super::*;
+
+        fn registry() -> SchemaRegistry {
+            let mut registry = SchemaRegistry::new();
+            registry
+                .register(
+                    "products".to_string(),
+                    r#"
+                    type Query {
+                        product: Product
                     }
-                }
-            }
-        "#;
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "Address.zip",
-                "ContactDetails.address",
-                "ContactDetails.email",
-                "Human.contactDetails",
-                "Human.name",
-                "Root.animalOwner",
-            ]
-        );
+                    type Product {
+                        id: ID!
+                        name: String
+                    }
+                    "#
+                    .to_string(),
+                )
+                .expect("Should register schema");
+            registry
+                .register(
+                    "inventory".to_string(),
+                    r#"
+                    type Query {
+                        stock: Int
+                    }
+
+                    type Product {
+                        id: ID!
+                        inStock: Boolean
+                    }
+                    "#
+                    .to_string(),
+                )
+                .expect("Should register schema");
+            registry
+        }
+
+        fn schemas(names: &[&str]) -> Vec<String> {
+            names.iter().map(|name| name.to_string()).collect()
+        }
+
+        #[test]
+        fn test_partially_overlapping_schemas() {
+            let result = registry()
+                .attribute_coordinates(
+                    "{ product { id name inStock discontinued } stock }".to_string(),
+                )
+                .expect("Should attribute coordinates");
+
+            assert_eq!(
+                result,
+                HashMap::from([
+                    ("Query.product".to_string(), schemas(&["products"])),
+                    ("Query.stock".to_string(), schemas(&["inventory"])),
+                    (
+                        "Product.id".to_string(),
+                        schemas(&["products", "inventory"])
+                    ),
+                    ("Product.name".to_string(), schemas(&["products"])),
+                    ("Product.inStock".to_string(), schemas(&["inventory"])),
+                    ("Product.discontinued".to_string(), schemas(&["<unknown>"])),
+                ])
+            );
+        }
+
+        #[test]
+        fn test_registering_a_name_again_replaces_its_schema() {
+            let mut registry = registry();
+            registry
+                .register(
+                    "inventory".to_string(),
+                    "type Query { warehouse: String }".to_string(),
+                )
+                .expect("Should register schema");
+
+            let result = registry
+                .attribute_coordinates("{ stock warehouse }".to_string())
+                .expect("Should attribute coordinates");
+
+            assert_eq!(result["Query.stock"], schemas(&["<unknown>"]));
+            assert_eq!(result["Query.warehouse"], schemas(&["inventory"]));
+        }
     }
 
-    #[test]
-    fn test_multiple_operations() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                }
+    mod versioned_schema_registry_tests {
+        use super::*;
+
+        const V1_SCHEMA: &str = r#"
+            type Query {
+                product: Product
             }
-            {
-                animalOwner {
-                    contactDetails {
-                        email
-                    }
-                }
+
+            type Product {
+                name: String
             }
         "#;
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "ContactDetails.email",
-                "Human.contactDetails",
-                "Human.name",
-                "Root.animalOwner",
-            ]
-        );
-    }
+        const V2_SCHEMA: &str = r#"
+            type Root {
+                item: Product
+            }
 
-    #[test]
-    fn test_includes_non_existent_fields_as_leaf_nodes() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                    I_DONT_EXIST
-                    contactDetails {
-                        email
-                        I_DONT_EXIST
-                    }
-                }
+            type Product {
+                title: String
+            }
+
+            schema {
+                query: Root
             }
         "#;
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "ContactDetails.I_DONT_EXIST",
-                "ContactDetails.email",
-                "Human.I_DONT_EXIST",
-                "Human.contactDetails",
-                "Human.name",
-                "Root.animalOwner",
-            ]
-        );
+        #[test]
+        fn test_extracts_against_each_version() {
+            let mut registry = VersionedSchemaRegistry::new(2).expect("Should create registry");
+            registry
+                .put("v1".to_string(), V1_SCHEMA.to_string())
+                .expect("Should parse schema");
+            registry
+                .put("v2".to_string(), V2_SCHEMA.to_string())
+                .expect("Should parse schema");
+
+            let document = "{ product { name } item { title } }";
+            let mut v1 = registry
+                .extract("v1".to_string(), document.to_string())
+                .expect("Should extract against v1");
+            v1.sort();
+            let mut v2 = registry
+                .extract("v2".to_string(), document.to_string())
+                .expect("Should extract against v2");
+            v2.sort();
+
+            assert_eq!(v1, vec!["Product.name", "Query.item", "Query.product"]);
+            assert_eq!(v2, vec!["Product.title", "Root.item", "Root.product"]);
+        }
+
+        #[test]
+        fn test_evicts_least_recently_used_version() {
+            let mut registry = VersionedSchemaRegistry::new(2).expect("Should create registry");
+            for version in ["v1", "v2"] {
+                registry
+                    .put(version.to_string(), V1_SCHEMA.to_string())
+                    .expect("Should parse schema");
+            }
+            registry
+                .extract("v1".to_string(), "{ product { name } }".to_string())
+                .expect("Should extract against v1");
+            registry
+                .put("v3".to_string(), V2_SCHEMA.to_string())
+                .expect("Should parse schema");
+
+            assert_eq!(registry.versions(), vec!["v1", "v3"]);
+            let error = registry
+                .extract("v2".to_string(), "{ product { name } }".to_string())
+                .expect_err("Should reject the evicted version");
+            assert_eq!(error.reason, "Unknown schema version: v2");
+        }
+
+        #[test]
+        fn test_put_replaces_version() {
+            let mut registry = VersionedSchemaRegistry::new(1).expect("Should create registry");
+            registry
+                .put("latest".to_string(), V1_SCHEMA.to_string())
+                .expect("Should parse schema");
+            registry
+                .put("latest".to_string(), V2_SCHEMA.to_string())
+                .expect("Should parse schema");
+
+            let mut result = registry
+                .extract("latest".to_string(), "{ item { title } }".to_string())
+                .expect("Should extract against the replacement");
+            result.sort();
+
+            assert_eq!(registry.versions(), vec!["latest"]);
+            assert_eq!(result, vec!["Product.title", "Root.item"]);
+        }
+
+        #[test]
+        fn test_rejects_zero_capacity() {
+            let error = VersionedSchemaRegistry::new(0)
+                .err()
+                .expect("Should reject the capacity");
+            assert_eq!(
+                error.reason,
+                "Versioned schema registry capacity must be at least 1"
+            );
+        }
     }
 
-    #[test]
-    fn test_includes_non_existent_fields_as_non_leaf_nodes() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                    contactDetails {
-                        email
-                        I_DONT_EXIST {
-                            foo
-                            bar
-                        }
-                    }
-                }
+    mod coordinate_buffer_tests {
+        use super::*;
+
+        fn sorted(buffer: &CoordinateBuffer) -> Vec<String> {
+            let mut result = buffer.to_array();
+            result.sort();
+            result
+        }
+
+        #[test]
+        fn test_extract_into_buffer() {
+            let mut buffer = CoordinateBuffer::new();
+            PARSED_SCHEMA
+                .extract_schema_coordinates_into(
+                    "{ animalOwner { name } }".to_string(),
+                    &mut buffer,
+                )
+                .expect("Should extract schema coordinates");
+
+            assert_eq!(buffer.length(), 2);
+            assert_eq!(sorted(&buffer), vec!["Human.name", "Root.animalOwner"]);
+        }
+
+        #[test]
+        fn test_reused_buffer_does_not_leak_between_extractions() {
+            let mut buffer = CoordinateBuffer::new();
+            PARSED_SCHEMA
+                .extract_schema_coordinates_into(
+                    "{ animalOwner { name contactDetails { email } } }".to_string(),
+                    &mut buffer,
+                )
+                .expect("Should extract schema coordinates");
+            PARSED_SCHEMA
+                .extract_schema_coordinates_into(
+                    "mutation { addCat(name: \"Tom\") { name } }".to_string(),
+                    &mut buffer,
+                )
+                .expect("Should extract schema coordinates");
+
+            assert_eq!(sorted(&buffer), vec!["Cat.name", "Mutation.addCat"]);
+        }
+
+        #[test]
+        fn test_buffer_is_emptied_on_parse_error() {
+            let mut buffer = CoordinateBuffer::new();
+            PARSED_SCHEMA
+                .extract_schema_coordinates_into(
+                    "{ animalOwner { name } }".to_string(),
+                    &mut buffer,
+                )
+                .expect("Should extract schema coordinates");
+
+            let result = PARSED_SCHEMA
+                .extract_schema_coordinates_into("{ animalOwner {".to_string(), &mut buffer);
+
+            assert!(result.is_err());
+            assert_eq!(buffer.length(), 0);
+        }
+    }
+
+    mod all_input_coordinates_tests {
+        use super::*;
+
+        #[test]
+        fn test_all_input_coordinates() {
+            assert_eq!(
+                PARSED_SCHEMA.all_input_coordinates(),
+                vec![
+                    "VetDetailsInput.address",
+                    "VetDetailsInput.name",
+                    "VetDetailsInput.phone",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_all_input_coordinates_without_inputs() {
+            let parsed_schema = ParsedSchema::new("type Query { a: String }".to_string())
+                .expect("Should parse schema");
+            assert!(parsed_schema.all_input_coordinates().is_empty());
+        }
+
+        #[test]
+        fn test_includes_fields_from_input_extensions() {
+            let schema = r#"
+                extend input Filter { species: String }
+                type Query { pets(filter: Filter): String }
+                input Filter { name: String }
+                extend input Filter { age: Int }
+            "#;
+
+            for lazy_type_map in [false, true] {
+                let parsed_schema = ParsedSchema::with_options(
+                    schema.to_string(),
+                    ExtractionOptions {
+                        lazy_type_map: Some(lazy_type_map),
+                        include_input_fields: Some(true),
+                        ..Default::default()
+                    },
+                )
+                .expect("Should parse schema");
+                assert_eq!(
+                    parsed_schema.all_input_coordinates(),
+                    vec!["Filter.age", "Filter.name", "Filter.species"]
+                );
+
+                let mut coordinates = parsed_schema
+                    .extract_schema_coordinates(
+                        "{ pets(filter: { name: \"Rex\", age: 3 }) }".to_string(),
+                    )
+                    .expect("Should extract coordinates");
+                coordinates.sort();
+                assert_eq!(coordinates, vec!["Filter.age", "Filter.name", "Query.pets"]);
             }
-        "#;
+        }
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "ContactDetails.I_DONT_EXIST",
-                "ContactDetails.email",
-                "Human.contactDetails",
-                "Human.name",
-                "Root.animalOwner",
-            ]
-        );
+        #[test]
+        fn test_includes_values_and_members_from_extensions() {
+            let parsed_schema = ParsedSchema::new(
+                r#"
+                    type Query { pet: Pet, size: Size }
+                    type Dog { name: String }
+                    type Cat { name: String }
+                    union Pet = Dog
+                    extend union Pet = Cat
+                    enum Size { SMALL }
+                    extend enum Size { LARGE }
+                "#
+                .to_string(),
+            )
+            .expect("Should parse schema");
+
+            assert!(parsed_schema.has_coordinate("Size.LARGE".to_string()));
+            assert_eq!(
+                parsed_schema.possible_types("Pet".to_string()),
+                vec!["Cat", "Dog"]
+            );
+        }
     }
 
-    #[test]
-    fn test_fragments() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                }
-                allSpecies {
-                    ...doggoDetails
-                    ...catFacts
-                }
-                pets {
-                    ...parrotParticulars
-                }
-            }
+    mod all_coordinates_tests {
+        use super::*;
+
+        fn kind_of(coordinate: &str) -> Option<CoordinateKind> {
+            PARSED_SCHEMA
+                .all_coordinates_detailed()
+                .into_iter()
+                .find(|detailed| detailed.coordinate == coordinate)
+                .map(|detailed| detailed.kind)
+        }
+
+        #[test]
+        fn test_classifies_pets_schema_coordinates() {
+            assert_eq!(kind_of("Human"), Some(CoordinateKind::Type));
+            assert_eq!(kind_of("Species"), Some(CoordinateKind::Type));
+            assert_eq!(kind_of("Pet"), Some(CoordinateKind::Type));
+            assert_eq!(kind_of("Human.name"), Some(CoordinateKind::OutputField));
+            assert_eq!(kind_of("Animal.name"), Some(CoordinateKind::OutputField));
+            assert_eq!(
+                kind_of("VetDetailsInput.name"),
+                Some(CoordinateKind::InputField)
+            );
+            assert_eq!(
+                kind_of("Mutation.addCat(name:)"),
+                Some(CoordinateKind::Argument)
+            );
+            assert_eq!(
+                kind_of("Root.pets(species:)"),
+                Some(CoordinateKind::Argument)
+            );
+            assert_eq!(kind_of("Species.DOG"), Some(CoordinateKind::EnumValue));
+            assert_eq!(
+                kind_of("ContactDetails.address"),
+                Some(CoordinateKind::OutputField)
+            );
+        }
+
+        #[test]
+        fn test_skips_root_type_aliases() {
+            assert_eq!(
+                kind_of("Root.animalOwner"),
+                Some(CoordinateKind::OutputField)
+            );
+            assert_eq!(kind_of("Query.animalOwner"), None);
+        }
 
-            fragment doggoDetails on Dog {
-                breed
-            }
+        #[test]
+        fn test_all_coordinates_matches_detailed() {
+            let detailed: Vec<String> = PARSED_SCHEMA
+                .all_coordinates_detailed()
+                .into_iter()
+                .map(|detailed| detailed.coordinate)
+                .collect();
+            let all = PARSED_SCHEMA.all_coordinates();
 
-            fragment catFacts on Cat {
-                favoriteMilkBrand
-                name
-            }
+            assert_eq!(all, detailed);
+            let mut sorted = all.clone();
+            sorted.sort();
+            assert_eq!(all, sorted);
+        }
 
-            fragment parrotParticulars on Parrot {
-                wingSpan
-            }
-        "#;
+        #[test]
+        fn test_directives_and_separator() {
+            let schema = r#"
+                directive @auth(role: String) on FIELD_DEFINITION
+                type Query { me(id: ID): String @auth }
+            "#;
+            let parsed_schema = ParsedSchema::with_options(
+                schema.to_string(),
+                ExtractionOptions {
+                    separator: Some("::".to_string()),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "Cat.favoriteMilkBrand",
-                "Cat.name",
-                "Dog.breed",
-                "Human.name",
-                "Parrot.wingSpan",
-                "Root.allSpecies",
-                "Root.animalOwner",
-                "Root.pets",
-            ]
-        );
+            assert_eq!(
+                parsed_schema.all_coordinates_detailed(),
+                vec![
+                    Coordinate {
+                        coordinate: "@auth".to_string(),
+                        kind: CoordinateKind::Directive,
+                    },
+                    Coordinate {
+                        coordinate: "@auth(role:)".to_string(),
+                        kind: CoordinateKind::Argument,
+                    },
+                    Coordinate {
+                        coordinate: "Query".to_string(),
+                        kind: CoordinateKind::Type,
+                    },
+                    Coordinate {
+                        coordinate: "Query::me".to_string(),
+                        kind: CoordinateKind::OutputField,
+                    },
+                    Coordinate {
+                        coordinate: "Query::me(id:)".to_string(),
+                        kind: CoordinateKind::Argument,
+                    },
+                ]
+            );
+        }
     }
 
-    #[test]
-    fn test_fragments_with_interface_fields() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                }
-                allSpecies {
-                    name
-                    ...doggoDetails
-                }
-            }
+    mod all_directive_coordinates_tests {
+        use super::*;
 
-            fragment doggoDetails on Dog {
-                breed
-                name
-            }
-        "#;
+        #[test]
+        fn test_all_directive_coordinates() {
+            let schema = r#"
+                directive @auth(role: Role!, scopes: [String!]) on FIELD_DEFINITION | OBJECT
+                directive @cacheControl(maxAge: Int) on FIELD_DEFINITION
+                directive @internal on FIELD_DEFINITION
+                enum Role { ADMIN USER }
+                type Query { me: String @auth(role: USER) @internal }
+            "#;
+            let parsed_schema = ParsedSchema::new(schema.to_string()).expect("Should parse schema");
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "Animal.name",
-                "Dog.breed",
-                "Dog.name",
-                "Human.name",
-                "Root.allSpecies",
-                "Root.animalOwner",
-            ]
-        );
+            assert_eq!(
+                parsed_schema.all_directive_coordinates(),
+                vec![
+                    "@auth",
+                    "@auth(role:)",
+                    "@auth(scopes:)",
+                    "@cacheControl",
+                    "@cacheControl(maxAge:)",
+                    "@internal",
+                ]
+            );
+        }
+
+        #[test]
+        fn test_all_directive_coordinates_without_directives() {
+            assert!(PARSED_SCHEMA.all_directive_coordinates().is_empty());
+        }
     }
 
-    #[test]
-    fn test_inline_fragments() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                }
-                allSpecies {
-                    ... on Dog {
-                        breed
-                    }
-                    ... on Cat {
-                        favoriteMilkBrand
+    mod coordinate_bitset_tests {
+        use super::*;
+
+        #[test]
+        fn test_round_trip() {
+            let document = r#"
+                query Owner($details: VetDetailsInput) {
+                    animalOwner {
                         name
+                        contactDetails {
+                            email
+                        }
                     }
-                }
-                pets {
-                    ... on Parrot {
-                        wingSpan
+                    pets {
+                        __typename
                     }
                 }
-            }
-        "#;
+            "#;
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "Cat.favoriteMilkBrand",
-                "Cat.name",
-                "Dog.breed",
-                "Human.name",
-                "Parrot.wingSpan",
-                "Root.allSpecies",
-                "Root.animalOwner",
-                "Root.pets",
-            ]
-        );
-    }
+            let bitset = PARSED_SCHEMA
+                .extract_coordinate_bitset(document.to_string())
+                .expect("Should extract coordinates");
 
-    #[test]
-    fn test_inline_fragments_with_interface_fields() {
-        let document = r#"
-            {
-                animalOwner {
-                    name
-                }
-                allSpecies {
-                    name
-                    ... on Dog {
-                        breed
-                        name
-                    }
-                }
-            }
-        "#;
+            assert_eq!(
+                bitset.len(),
+                PARSED_SCHEMA.all_coordinates().len().div_ceil(8)
+            );
+            // `Pet.__typename` isn't defined by the schema, so it has no bit
+            assert_eq!(
+                PARSED_SCHEMA.decode_coordinate_bitset(bitset),
+                vec![
+                    "ContactDetails.email",
+                    "Human.contactDetails",
+                    "Human.name",
+                    "Root.animalOwner",
+                    "Root.pets",
+                    "VetDetailsInput",
+                ]
+            );
+        }
 
-        let result = extract_and_sort(document);
-        assert_eq!(
-            result,
-            vec![
-                "Animal.name",
-                "Dog.breed",
-                "Dog.name",
-                "Human.name",
-                "Root.allSpecies",
-                "Root.animalOwner",
-            ]
-        );
-    }
+        #[test]
+        fn test_bit_positions_follow_all_coordinates() {
+            let all_coordinates = PARSED_SCHEMA.all_coordinates();
+            let index = all_coordinates
+                .iter()
+                .position(|coordinate| coordinate == "Root.animalOwner")
+                .expect("Should list the coordinate");
 
-    #[test]
-    fn test_inline_fragments_without_type_condition() {
-        let document = r#"
-            query Foo($expandedInfo: Boolean) {
-                allSpecies {
-                    ... @include(if: $expandedInfo) {
-                        name
-                    }
-                }
-            }
-        "#;
+            let bitset = PARSED_SCHEMA
+                .extract_coordinate_bitset("{ animalOwner { __typename } }".to_string())
+                .expect("Should extract coordinates");
 
-        let result = extract_and_sort(document);
-        assert_eq!(result, vec!["Animal.name", "Root.allSpecies"]);
+            let mut expected = vec![0u8; bitset.len()];
+            expected[index / 8] = 1 << (index % 8);
+            assert_eq!(bitset.to_vec(), expected);
+        }
+
+        #[test]
+        fn test_decoding_ignores_extra_bits() {
+            assert!(PARSED_SCHEMA
+                .decode_coordinate_bitset(vec![0; 1024].into())
+                .is_empty());
+            assert_eq!(
+                PARSED_SCHEMA.decode_coordinate_bitset(vec![0xff; 1024].into()),
+                PARSED_SCHEMA.all_coordinates()
+            );
+        }
     }
 
-    #[test]
-    fn test_copes_with_types_that_dont_exist_in_schema() {
-        let document = r#"
-            {
-                allSpecies {
-                    name
-                    ... on Snake {
-                        skin {
-                            color
-                        }
-                    }
-                }
-            }
-        "#;
+    mod has_coordinate_tests {
+        use super::*;
 
-        let result = extract_and_sort(document);
-        assert_eq!(result, vec!["Animal.name", "Root.allSpecies", "Snake.skin"]);
-    }
+        static SCHEMA_WITH_DIRECTIVE: LazyLock<ParsedSchema> = LazyLock::new(|| {
+            ParsedSchema::new(format!(
+                "{}\ndirective @cached(ttl: Int) on FIELD",
+                PETS_SCHEMA
+            ))
+            .expect("Should parse schema")
+        });
 
-    #[test]
-    fn test_shows_inputs() {
-        let document = r#"
-            mutation AddVet($vetInfo: VetDetailsInput!, $somethingElse: String!) {
-                addVet(details: $vetInfo)
+        #[test]
+        fn test_existing_coordinates() {
+            for coordinate in [
+                "Human",
+                "Human.name",
+                "Animal.name",
+                "VetDetailsInput.phone",
+                "Species.DOG",
+                "Root.pets(species:)",
+                "Query.animalOwner",
+                "@cached",
+                "@cached(ttl:)",
+            ] {
+                assert!(
+                    SCHEMA_WITH_DIRECTIVE.has_coordinate(coordinate.to_string()),
+                    "{} should exist",
+                    coordinate
+                );
             }
-        "#;
-
-        let result = extract_and_sort(document);
-        assert_eq!(result, vec!["Mutation.addVet", "VetDetailsInput"]);
-    }
+        }
 
-    #[test]
-    fn test_mutation_with_arguments() {
-        let document = r#"
-            mutation AddCat($name: String) {
-                addCat(name: $name) {
-                    name
-                }
+        #[test]
+        fn test_nonexistent_and_malformed_coordinates() {
+            for coordinate in [
+                "Robot",
+                "Human.wings",
+                "Species.FISH",
+                "Root.pets(color:)",
+                "@deferred",
+                "@cached(ttl:",
+                "Root.pets(species)",
+                "",
+                ".name",
+            ] {
+                assert!(
+                    !SCHEMA_WITH_DIRECTIVE.has_coordinate(coordinate.to_string()),
+                    "{} shouldn't exist",
+                    coordinate
+                );
             }
-        "#;
+        }
 
-        let result = extract_and_sort(document);
-        assert_eq!(result, vec!["Cat.name", "Mutation.addCat"]);
+        #[test]
+        fn test_prefixed_coordinates() {
+            let parsed_schema = ParsedSchema::with_options(
+                PETS_SCHEMA.to_string(),
+                ExtractionOptions {
+                    coordinate_prefix: Some("pets".to_string()),
+                    ..Default::default()
+                },
+            )
+            .expect("Should parse schema");
+
+            assert!(parsed_schema.has_coordinate("pets:Human.name".to_string()));
+            assert!(!parsed_schema.has_coordinate("Human.name".to_string()));
+        }
     }
 
+    mod possible_types_tests {
+        use super::*;
+
         #[test]
-        #[should_panic(expected = "Schema is not configured to execute subscription")]
-        fn test_throws_error_on_unsupported_operation_types() {
-            let document = r#"
-            subscription Foo {
-                bar
-            }
-        "#;
+        fn test_lists_interface_implementations() {
+            assert_eq!(
+                PARSED_SCHEMA.possible_types("Animal".to_string()),
+                vec!["Cat", "Dog", "Human", "Parrot"]
+            );
+        }
 
-            let _ = extract_and_sort(document);
+        #[test]
+        fn test_lists_union_members() {
+            assert_eq!(
+                PARSED_SCHEMA.possible_types("Pet".to_string()),
+                vec!["Cat", "Dog", "Parrot"]
+            );
+        }
+
+        #[test]
+        fn test_empty_for_other_types() {
+            assert!(PARSED_SCHEMA.possible_types("Human".to_string()).is_empty());
+            assert!(PARSED_SCHEMA
+                .possible_types("Species".to_string())
+                .is_empty());
+            assert!(PARSED_SCHEMA.possible_types("Snake".to_string()).is_empty());
+        }
+
+        #[test]
+        fn test_skips_implementing_interfaces() {
+            let parsed_schema = ParsedSchema::new(
+                r#"
+                type Query { node: Node }
+                interface Node { id: ID! }
+                interface Resource implements Node { id: ID! url: String }
+                type Image implements Resource & Node { id: ID! url: String }
+                "#
+                .to_string(),
+            )
+            .expect("Should parse schema");
+
+            assert_eq!(
+                parsed_schema.possible_types("Node".to_string()),
+                vec!["Image"]
+            );
         }
     }
 
@@ -844,6 +12037,16 @@ mod tests {
             assert!(PARSED_SCHEMA.has_field("Animal.name".to_string()).unwrap());
         }
 
+        #[test]
+        fn test_has_field_with_input_type() {
+            assert!(PARSED_SCHEMA
+                .has_field("VetDetailsInput.name".to_string())
+                .unwrap());
+            assert!(!PARSED_SCHEMA
+                .has_field("VetDetailsInput.email".to_string())
+                .unwrap());
+        }
+
         #[test]
         fn test_has_field_with_extended_types() {
             // ContactDetails has an extended field 'address'
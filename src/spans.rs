@@ -0,0 +1,190 @@
+//! Locating fields and arguments in a document's source text, from the line and column
+//! positions the parser records for fields.
+
+use graphql_parser::Pos;
+
+/// Converts a parser position into a byte offset into `text`. Columns are counted the way the
+/// parser counts them: tabs are 8 columns wide, and carriage returns and byte order marks take
+/// up none.
+pub(crate) fn byte_offset(text: &str, position: Pos) -> Option<usize> {
+    let line_start = match position.line {
+        0 => return None,
+        1 => 0,
+        line => text.match_indices('\n').nth(line - 2)?.0 + 1,
+    };
+
+    let mut column = 1;
+    for (index, c) in text[line_start..].char_indices() {
+        if column >= position.column {
+            return (column == position.column).then_some(line_start + index);
+        }
+        column += match c {
+            '\n' => return None,
+            '\t' => 8,
+            '\r' | '\u{feff}' => 0,
+            _ => 1,
+        };
+    }
+    None
+}
+
+/// The byte span of a field's name, given the offset of the field, which starts at its alias if
+/// it has one. `None` if the name isn't found there.
+pub(crate) fn field_name_span(
+    text: &str,
+    offset: usize,
+    alias: Option<&str>,
+    name: &str,
+) -> Option<(usize, usize)> {
+    let mut start = offset;
+    if let Some(alias) = alias {
+        text.get(start..)?.strip_prefix(alias)?;
+        start = skip_ignored(text, start + alias.len());
+        text.get(start..)?.strip_prefix(':')?;
+        start = skip_ignored(text, start + 1);
+    }
+
+    let end = start + name.len();
+    (text.get(start..end)? == name && !is_name_byte(text.as_bytes().get(end).copied()))
+        .then_some((start, end))
+}
+
+/// The names and byte spans of the arguments in the argument list following a field name that
+/// ends at `offset`. Names in nested object values aren't arguments, so they're skipped.
+pub(crate) fn argument_spans(text: &str, offset: usize) -> Vec<(&str, usize, usize)> {
+    let mut spans = Vec::new();
+    let bytes = text.as_bytes();
+    let mut index = skip_ignored(text, offset);
+    if bytes.get(index) != Some(&b'(') {
+        return spans;
+    }
+
+    let mut depth = 0;
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'#' => {
+                index = text[index..]
+                    .find('\n')
+                    .map_or(text.len(), |end| index + end);
+                continue;
+            }
+            b'"' => {
+                index = skip_string(text, index);
+                continue;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ if is_name_byte(Some(byte)) && !byte.is_ascii_digit() => {
+                let start = index;
+                while is_name_byte(bytes.get(index).copied()) {
+                    index += 1;
+                }
+                if depth == 1 && bytes.get(skip_ignored(text, index)) == Some(&b':') {
+                    spans.push((&text[start..index], start, index));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+
+    spans
+}
+
+/// The offset of the first character from `offset` that isn't whitespace, a comma or part of a
+/// comment
+fn skip_ignored(text: &str, mut offset: usize) -> usize {
+    let bytes = text.as_bytes();
+    while let Some(&byte) = bytes.get(offset) {
+        match byte {
+            b' ' | b'\t' | b'\n' | b'\r' | b',' => offset += 1,
+            b'#' => {
+                offset = text[offset..]
+                    .find('\n')
+                    .map_or(text.len(), |end| offset + end)
+            }
+            _ if text[offset..].starts_with('\u{feff}') => offset += '\u{feff}'.len_utf8(),
+            _ => break,
+        }
+    }
+    offset
+}
+
+/// The offset just past the string or block string starting at `offset`
+fn skip_string(text: &str, offset: usize) -> usize {
+    if let Some(block) = text[offset..].strip_prefix("\"\"\"") {
+        let mut index = 0;
+        while let Some(end) = block[index..].find("\"\"\"") {
+            if !block[..index + end].ends_with('\\') {
+                return offset + 3 + index + end + 3;
+            }
+            index += end + 3;
+        }
+        return text.len();
+    }
+
+    let mut escaped = false;
+    for (index, c) in text[offset + 1..].char_indices() {
+        match c {
+            '"' if !escaped => return offset + 1 + index + 1,
+            '\n' => return offset + 1 + index,
+            _ => {}
+        }
+        escaped = !escaped && c == '\\';
+    }
+    text.len()
+}
+
+fn is_name_byte(byte: Option<u8>) -> bool {
+    byte.is_some_and(|byte| byte == b'_' || byte.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(line: usize, column: usize) -> Pos {
+        Pos { line, column }
+    }
+
+    #[test]
+    fn test_byte_offset() {
+        let text = "{\n\ta(x: \"🐶\") {\r\n  b\n}";
+
+        assert_eq!(byte_offset(text, pos(1, 1)), Some(0));
+        assert_eq!(byte_offset(text, pos(2, 9)), Some(3));
+        assert_eq!(byte_offset(text, pos(3, 3)), Some(21));
+        assert_eq!(byte_offset(text, pos(2, 40)), None);
+        assert_eq!(byte_offset(text, pos(9, 1)), None);
+    }
+
+    #[test]
+    fn test_field_name_span() {
+        let text = "{ owner: animalOwner { name } }";
+
+        assert_eq!(
+            field_name_span(text, 2, Some("owner"), "animalOwner"),
+            Some((9, 20))
+        );
+        assert_eq!(field_name_span(text, 23, None, "name"), Some((23, 27)));
+        assert_eq!(field_name_span(text, 23, None, "nam"), None);
+        assert_eq!(field_name_span(text, 23, None, "owner"), None);
+    }
+
+    #[test]
+    fn test_argument_spans() {
+        let text = r#"pets(species: DOG, filter: { name: "a)b" }, first: 1) { name }"#;
+
+        assert_eq!(
+            argument_spans(text, 4),
+            vec![("species", 5, 12), ("filter", 19, 25), ("first", 44, 49)]
+        );
+        assert!(argument_spans("name { a(b: 1) }", 4).is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! Pre-processing for documents that may not be valid GraphQL as written, such as the
+//! bodies of `gql` tagged template literals.
+
+/// Blanks out `${...}` template literal interpolations, along with a `...` spread that
+/// directly precedes one (e.g. `...${OwnerFields}`), so the remaining text can be parsed
+pub(crate) fn strip_interpolations(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '$' && chars.peek() == Some(&'{') {
+            chars.next();
+            let mut depth = 1;
+            for c in chars.by_ref() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let trimmed_len = result.trim_end().len();
+            if result[..trimmed_len].ends_with("...") {
+                result.truncate(trimmed_len - 3);
+            }
+            result.push(' ');
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Splits a document into its top-level definitions by tracking brace depth, so each
+/// definition can be parsed on its own. Braces inside strings and comments are ignored.
+pub(crate) fn split_definitions(text: &str) -> Vec<&str> {
+    let mut definitions = Vec::new();
+    let mut depth: usize = 0;
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '#' => {
+                // Skip to the end of the comment
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '"' => {
+                // Skip to the end of the string, honouring escapes
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' => depth += 1,
+            '}' => {
+                if depth == 0 {
+                    // Stray closing brace: discard everything up to and including it
+                    start = index + 1;
+                    continue;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    definitions.push(&text[start..index + 1]);
+                    start = index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !text[start..].trim().is_empty() {
+        definitions.push(&text[start..]);
+    }
+
+    definitions
+}
+
+/// Keywords that start type system definitions, which documents can't contain
+const SCHEMA_KEYWORDS: &[&str] = &[
+    "schema",
+    "scalar",
+    "type",
+    "interface",
+    "union",
+    "enum",
+    "input",
+    "directive",
+    "extend",
+];
+
+/// Whether any top-level definition starts with a type system keyword, suggesting the text is
+/// a schema (SDL) rather than a document. Leading comments and descriptions are skipped.
+pub(crate) fn looks_like_schema(text: &str) -> bool {
+    split_definitions(text).into_iter().any(|definition| {
+        let mut rest = definition.trim_start();
+        loop {
+            if let Some(comment) = rest.strip_prefix('#') {
+                rest = comment.split_once('\n').map_or("", |(_, after)| after);
+            } else if let Some(block_string) = rest.strip_prefix("\"\"\"") {
+                rest = block_string
+                    .split_once("\"\"\"")
+                    .map_or("", |(_, after)| after);
+            } else if let Some(string) = rest.strip_prefix('"') {
+                rest = string.split_once('"').map_or("", |(_, after)| after);
+            } else {
+                break;
+            }
+            rest = rest.trim_start();
+        }
+
+        let keyword = rest
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        SCHEMA_KEYWORDS.contains(&keyword)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_interpolations() {
+        assert_eq!(strip_interpolations("{ a } ${FRAGMENT}"), "{ a }  ");
+        assert_eq!(
+            strip_interpolations("{ a { ...${fragments.map((f) => { return f; })} b } }"),
+            "{ a {   b } }"
+        );
+    }
+
+    #[test]
+    fn test_split_definitions() {
+        let text = r#"
+            query Foo($x: String = "}") { a { b } }
+            # a comment with a brace }
+            fragment bar on Baz { c }
+        "#;
+
+        let definitions: Vec<&str> = split_definitions(text).into_iter().map(str::trim).collect();
+        assert_eq!(
+            definitions,
+            vec![
+                r#"query Foo($x: String = "}") { a { b } }"#,
+                "# a comment with a brace }\n            fragment bar on Baz { c }",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_looks_like_schema() {
+        assert!(looks_like_schema("type Query { a: String }"));
+        assert!(looks_like_schema(
+            "# Pets\n\"\"\"The root\"\"\"\nschema { query: Root }"
+        ));
+        assert!(looks_like_schema("query A { a }\nscalar Date"));
+        assert!(!looks_like_schema(
+            "query type { input }\nfragment f on type { enum }"
+        ));
+        assert!(!looks_like_schema("{ type { a } }"));
+    }
+
+    #[test]
+    fn test_multi_byte_characters() {
+        assert_eq!(
+            strip_interpolations("{ a(x: \"🐶\") ...${Frag} } ✨"),
+            "{ a(x: \"🐶\")   } ✨"
+        );
+
+        let text = "query Ü { a(x: \"}🐱\") } # 🙃 }\nfragment b on C { d }";
+        let definitions: Vec<&str> = split_definitions(text).into_iter().map(str::trim).collect();
+        assert_eq!(
+            definitions,
+            vec!["query Ü { a(x: \"}🐱\") }", "# 🙃 }\nfragment b on C { d }",]
+        );
+    }
+}